@@ -0,0 +1,40 @@
+//! Times random lookups into a collection of 10k pages. Run with
+//! `cargo bench --bench find_page`.
+
+use std::fs;
+use std::time::Instant;
+
+use eadb::cache::PageCache;
+use eadb::collection::Collection;
+
+const PAGES: usize = 10_000;
+const LOOKUPS: usize = 100_000;
+
+fn main() {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    let collection = Collection::from_ints(&dir, (0..PAGES as i64 * 4).map(Some), 4).unwrap();
+    let mut cache = PageCache::with_capacity(PAGES);
+    for idx in 0..collection.size() {
+        collection.get_int(&mut cache, idx).unwrap();
+    }
+
+    let start = Instant::now();
+    let mut idx = 0;
+    for _ in 0..LOOKUPS {
+        idx = (idx + 7919) % collection.size();
+        assert_eq!(
+            collection.get_int(&mut cache, idx).unwrap(),
+            Some(idx as i64)
+        );
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{} lookups over {} pages: {:?} ({:?} per lookup)",
+        LOOKUPS,
+        PAGES,
+        elapsed,
+        elapsed / LOOKUPS as u32
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,46 @@
+//! Times reading every entry of a 100k-entry nullable bool page through
+//! `get_bool`. Run with `cargo bench --bench get_bool`.
+
+use std::fs;
+use std::time::Instant;
+
+use eadb::page::{Compression, Page, PageData, PageMeta, PageReader, PageWriter, Type};
+
+const ENTRIES: usize = 100_000;
+const PASSES: usize = 20;
+
+fn main() {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    let entries = (0..ENTRIES)
+        .map(|idx| match idx % 3 {
+            0 => None,
+            _ => Some(idx % 2 == 0),
+        })
+        .collect::<Vec<_>>();
+    let meta = PageMeta::new(Type::Bool, &dir.join("page"), 0, ENTRIES);
+    let page = Page::new(&meta, PageData::from_bools(&entries).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Uncompressed).unwrap();
+    let page = PageReader::read(&meta).unwrap();
+
+    let start = Instant::now();
+    let mut trues = 0;
+    for _ in 0..PASSES {
+        for idx in 0..ENTRIES {
+            if page.get_bool(idx).unwrap() == Some(true) {
+                trues += 1;
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+    let expected = entries.iter().filter(|entry| **entry == Some(true)).count();
+    assert_eq!(trues, expected * PASSES);
+    println!(
+        "{} passes over {} bools: {:?} ({:?} per entry)",
+        PASSES,
+        ENTRIES,
+        elapsed,
+        elapsed / (PASSES * ENTRIES) as u32
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
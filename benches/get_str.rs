@@ -0,0 +1,56 @@
+//! Times reading every entry of a string page through the borrowing
+//! `get_str` against the allocating `get_string`. Run with
+//! `cargo bench --bench get_str`.
+
+use std::fs;
+use std::time::Instant;
+
+use eadb::page::{Compression, Page, PageData, PageMeta, PageReader, PageWriter, Type};
+
+const ENTRIES: usize = 100_000;
+const PASSES: usize = 10;
+
+fn main() {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    let values = (0..ENTRIES)
+        .map(|idx| format!("a string value of moderate length {}", idx))
+        .collect::<Vec<_>>();
+    let entries = values
+        .iter()
+        .map(|value| Some(value.as_str()))
+        .collect::<Vec<_>>();
+    let meta = PageMeta::new(Type::String, &dir.join("page"), 0, ENTRIES);
+    let page = Page::new(&meta, PageData::from_strings(&entries).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Uncompressed).unwrap();
+    let page = PageReader::read(&meta).unwrap();
+
+    let start = Instant::now();
+    let mut bytes = 0;
+    for _ in 0..PASSES {
+        for idx in 0..ENTRIES {
+            bytes += page.get_str(idx).unwrap().map_or(0, str::len);
+        }
+    }
+    let borrowed = start.elapsed();
+
+    let start = Instant::now();
+    let mut owned_bytes = 0;
+    for _ in 0..PASSES {
+        for idx in 0..ENTRIES {
+            owned_bytes += page.get_string(idx).unwrap().map_or(0, |value| value.len());
+        }
+    }
+    let owned = start.elapsed();
+
+    assert_eq!(bytes, owned_bytes);
+    let reads = (PASSES * ENTRIES) as u32;
+    println!(
+        "get_str: {:?} ({:?} per entry), get_string: {:?} ({:?} per entry)",
+        borrowed,
+        borrowed / reads,
+        owned,
+        owned / reads
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,74 @@
+//! Times random `get_string` calls on a string page written with delta
+//! offsets against the same page written with absolute offsets. Run with
+//! `cargo bench --bench string_offsets`.
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use eadb::page::{Compression, Page, PageData, PageMeta, PageReader, PageWriter, Type};
+
+const ENTRIES: usize = 100_000;
+const LOOKUPS: usize = 1_000_000;
+
+fn write(dir: &Path, name: &str, fixed_offsets: bool) -> PageMeta {
+    let values = (0..ENTRIES)
+        .map(|idx| format!("value {}", idx))
+        .collect::<Vec<_>>();
+    let entries = values
+        .iter()
+        .map(|value| Some(value.as_str()))
+        .collect::<Vec<_>>();
+    let meta = PageMeta::new(Type::String, &dir.join(name), 0, ENTRIES);
+    let page = Page::new(&meta, PageData::from_strings(&entries).unwrap()).unwrap();
+    if fixed_offsets {
+        PageWriter::write_fixed_offsets(&page, Compression::Uncompressed).unwrap();
+    } else {
+        PageWriter::write(&page, Compression::Uncompressed).unwrap();
+    }
+    meta
+}
+
+fn lookups<F: FnMut(usize) -> Option<String>>(mut get: F) -> Duration {
+    let start = Instant::now();
+    let mut idx = 0;
+    for _ in 0..LOOKUPS {
+        idx = (idx + 7919) % ENTRIES;
+        assert_eq!(get(idx), Some(format!("value {}", idx)));
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    let delta = write(&dir, "delta", false);
+    let absolute = write(&dir, "absolute", true);
+
+    for (name, meta) in &[("delta", &delta), ("absolute", &absolute)] {
+        let page = PageReader::read(meta).unwrap();
+        let elapsed = lookups(|idx| page.get_string(idx).unwrap());
+        println!(
+            "{} offsets: {} lookups in {:?} ({:?} per lookup)",
+            name,
+            LOOKUPS,
+            elapsed,
+            elapsed / LOOKUPS as u32
+        );
+
+        let mut lazy = PageReader::read_lazy(meta).unwrap();
+        let start = Instant::now();
+        lazy.rebuild_offset_index().unwrap();
+        let rebuilt = start.elapsed();
+        let elapsed = lookups(|idx| lazy.get_string(idx).unwrap());
+        println!(
+            "{} offsets, lazy: index rebuilt in {:?}, {} lookups in {:?} ({:?} per lookup)",
+            name,
+            rebuilt,
+            LOOKUPS,
+            elapsed,
+            elapsed / LOOKUPS as u32
+        );
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,66 @@
+#![no_main]
+
+use std::fs;
+use std::process;
+
+use eadb::page::{PageMeta, PageReader, Type};
+use libfuzzer_sys::fuzz_target;
+
+// The first byte picks the page type and the second its size, the rest is
+// written out as the page file. Reading it must fail cleanly or produce a
+// page whose every entry can be read.
+fuzz_target!(|input: &[u8]| {
+    if input.len() < 2 {
+        return;
+    }
+    let typ = match input[0] % 10 {
+        0 => Type::Bool,
+        1 => Type::Int,
+        2 => Type::Float,
+        3 => Type::String,
+        4 => Type::Int32,
+        5 => Type::UInt,
+        6 => Type::Float32,
+        7 => Type::Timestamp,
+        8 => Type::Binary,
+        _ => Type::Decimal,
+    };
+    let size = input[1] as usize;
+
+    let path = std::env::temp_dir().join(format!("eadb-fuzz-{}", process::id()));
+    fs::write(&path, &input[2..]).unwrap();
+    let meta = PageMeta::new(typ, &path, 0, size);
+
+    if let Ok(page) = PageReader::read(&meta) {
+        for idx in 0..size {
+            match typ {
+                Type::Bool => drop(page.get_bool(idx)),
+                Type::Int => drop(page.get_int(idx)),
+                Type::Float => drop(page.get_float(idx)),
+                Type::String => drop(page.get_string(idx)),
+                Type::Int32 => drop(page.get_i32(idx)),
+                Type::UInt => drop(page.get_u64(idx)),
+                Type::Float32 => drop(page.get_f32(idx)),
+                Type::Timestamp => drop(page.get_timestamp(idx)),
+                Type::Binary => drop(page.get_binary(idx)),
+                Type::Decimal => drop(page.get_decimal(idx)),
+            }
+        }
+    }
+    if let Ok(mut page) = PageReader::read_lazy(&meta) {
+        for idx in 0..size {
+            let _ = match typ {
+                Type::Bool => page.get_bool(idx).map(drop),
+                Type::Int => page.get_int(idx).map(drop),
+                Type::Float => page.get_float(idx).map(drop),
+                Type::String => page.get_string(idx).map(drop),
+                Type::Int32 => page.get_i32(idx).map(drop),
+                Type::UInt => page.get_u64(idx).map(drop),
+                Type::Float32 => page.get_f32(idx).map(drop),
+                Type::Timestamp => page.get_timestamp(idx).map(drop),
+                Type::Binary => page.get_binary(idx).map(drop),
+                Type::Decimal => page.get_decimal(idx).map(drop),
+            };
+        }
+    }
+});
@@ -0,0 +1,82 @@
+/// A fixed-size bloom filter over int values. Every filter has the same
+/// size, so the filters of several pages can be ORed into one that answers
+/// for all of them. Filters of large pages saturate and then answer `true`
+/// for any value, which is still correct.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BloomFilter {
+    words: Vec<u64>,
+}
+
+impl BloomFilter {
+    const BITS: usize = 8192;
+    const HASHES: u64 = 3;
+
+    /// Size of the filter once serialized with `to_bytes`.
+    pub const BYTE_LEN: usize = BloomFilter::BITS / 8;
+
+    pub fn new() -> Self {
+        BloomFilter {
+            words: vec![0; BloomFilter::BITS / 64],
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != BloomFilter::BYTE_LEN {
+            return None;
+        }
+        let words = bytes
+            .chunks_exact(8)
+            .map(|word| {
+                let mut buf = [0; 8];
+                buf.copy_from_slice(word);
+                u64::from_le_bytes(buf)
+            })
+            .collect();
+        Some(BloomFilter { words })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.words
+            .iter()
+            .flat_map(|word| word.to_le_bytes().to_vec())
+            .collect()
+    }
+
+    pub fn insert(&mut self, value: i64) {
+        for bit in BloomFilter::bits(value) {
+            self.words[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    /// `false` guarantees `value` was never inserted.
+    pub fn might_contain(&self, value: i64) -> bool {
+        BloomFilter::bits(value).all(|bit| self.words[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Adds every value of `other` to the filter.
+    pub fn union(&mut self, other: &BloomFilter) {
+        for (word, other_word) in self.words.iter_mut().zip(&other.words) {
+            *word |= other_word;
+        }
+    }
+
+    /// The bit positions of `value`, derived by double hashing from two
+    /// halves of a SplitMix64 finalization.
+    fn bits(value: i64) -> impl Iterator<Item = usize> {
+        let mut hash = (value as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        hash ^= hash >> 31;
+
+        let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        (0..BloomFilter::HASHES).map(move |idx| {
+            (h1.wrapping_add(idx.wrapping_mul(h2)) % BloomFilter::BITS as u64) as usize
+        })
+    }
+}
+
+impl Default for BloomFilter {
+    fn default() -> Self {
+        BloomFilter::new()
+    }
+}
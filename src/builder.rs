@@ -0,0 +1,175 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::prelude::*;
+use std::io::{BufWriter, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use bitvec::prelude as bv;
+use bitvec::vec::BitVec;
+use uuid::Uuid;
+
+use crate::page::{self, Bound, Compression, Encoding, HashingWriter, PageMeta, PageWriter, Type};
+use crate::work::{SpillFiles, WorkDir};
+
+/// Writes a string page one value at a time. String lengths and the compressed
+/// payload are streamed to spill files as values arrive, so only the null
+/// bitmap and the smallest and largest value are held in memory. `finish`
+/// stitches the spill files into a regular page file. The spill files are
+/// deleted when the builder is dropped, finished or not.
+pub struct StringPageBuilder {
+    id: Uuid,
+    path: PathBuf,
+    offset: usize,
+    nulls: BitVec<bv::LittleEndian, u8>,
+    bound: Option<Bound<String>>,
+    byte_offset: usize,
+    offsets: BufWriter<File>,
+    offsets_hasher: crc32fast::Hasher,
+    payload: snap::write::FrameEncoder<BufWriter<File>>,
+    payload_hasher: crc32fast::Hasher,
+    spill: SpillFiles,
+}
+
+impl StringPageBuilder {
+    pub fn new(path: &Path, offset: usize) -> io::Result<Self> {
+        StringPageBuilder::with_id(Uuid::new_v4(), path, offset)
+    }
+
+    /// Like `new`, but the finished page gets the caller-chosen `id`.
+    pub fn with_id(id: Uuid, path: &Path, offset: usize) -> io::Result<Self> {
+        StringPageBuilder::with_work_dir(id, path, offset, &WorkDir::default())
+    }
+
+    /// Like `with_id`, but the spill files go in `work_dir` instead of next
+    /// to the page.
+    pub fn with_work_dir(
+        id: Uuid,
+        path: &Path,
+        offset: usize,
+        work_dir: &WorkDir,
+    ) -> io::Result<Self> {
+        let data_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let spill_base = work_dir
+            .resolve(data_dir)
+            .join(path.file_name().unwrap_or_default());
+
+        let mut spill = SpillFiles::default();
+        let offsets = spill_file(&spill.add(spill_base.with_extension("offsets.tmp")))?;
+        let payload = spill_file(&spill.add(spill_base.with_extension("payload.tmp")))?;
+        Ok(StringPageBuilder {
+            id,
+            path: path.to_path_buf(),
+            offset,
+            nulls: BitVec::new(),
+            bound: None,
+            byte_offset: 0,
+            offsets: BufWriter::new(offsets),
+            offsets_hasher: crc32fast::Hasher::new(),
+            payload: snap::write::FrameEncoder::new(BufWriter::new(payload)),
+            payload_hasher: crc32fast::Hasher::new(),
+            spill,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.nulls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nulls.is_empty()
+    }
+
+    pub fn push(&mut self, value: Option<&str>) -> io::Result<()> {
+        self.hash_offset();
+
+        let bytes = value.unwrap_or("").as_bytes();
+        page::write_varint(&mut self.offsets, bytes.len() as u64)?;
+        self.payload.write_all(bytes)?;
+        self.payload_hasher.update(bytes);
+        self.nulls.push(value.is_none());
+        if let Some(value) = value {
+            self.widen_bound(value);
+        }
+        self.byte_offset += bytes.len();
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<PageMeta> {
+        self.hash_offset();
+
+        let mut offsets = self.offsets.into_inner().map_err(|err| err.into_error())?;
+        let mut payload = self
+            .payload
+            .into_inner()
+            .map_err(|err| io::Error::new(err.error().kind(), err.to_string()))?
+            .into_inner()
+            .map_err(|err| err.into_error())?;
+
+        let mut meta = PageMeta::with_id(
+            self.id,
+            Type::String,
+            &self.path,
+            self.offset,
+            self.nulls.len(),
+        );
+        meta.stats.null_count = self.nulls.count_ones();
+        meta.stats.contains_nulls = meta.stats.null_count > 0;
+        meta.stats.string_bound = self
+            .bound
+            .as_ref()
+            .map(|bound| page::truncate_bound(bound, page::STRING_BOUND_LEN));
+
+        let mut file = HashingWriter::new(File::create(&self.path)?);
+        PageWriter::write_header(&mut file, &meta)?;
+        if PageWriter::write_nulls(&mut file, &self.nulls)? {
+            PageWriter::write_stats(&mut file, &meta)?;
+            PageWriter::write_offset_encoding(&mut file)?;
+            offsets.seek(SeekFrom::Start(0))?;
+            io::copy(&mut offsets, &mut file)?;
+            PageWriter::write_encoding(&mut file, Encoding::Plain)?;
+            PageWriter::write_compression(&mut file, Compression::Snappy)?;
+            payload.seek(SeekFrom::Start(0))?;
+            io::copy(&mut payload, &mut file)?;
+        }
+        PageWriter::write_footer(file)?;
+        drop(self.spill);
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(self.nulls.as_slice());
+        hasher.combine(&self.offsets_hasher);
+        hasher.combine(&self.payload_hasher);
+
+        meta.checksum = Some(hasher.finalize());
+        Ok(meta)
+    }
+
+    fn widen_bound(&mut self, value: &str) {
+        match &mut self.bound {
+            None => {
+                self.bound = Some(Bound {
+                    min: value.to_string(),
+                    max: value.to_string(),
+                })
+            }
+            Some(bound) if value < bound.min.as_str() => bound.min = value.to_string(),
+            Some(bound) if value > bound.max.as_str() => bound.max = value.to_string(),
+            Some(_) => {}
+        }
+    }
+
+    /// Adds the current offset to the checksum, which covers the decoded
+    /// offsets rather than the lengths written to the page.
+    fn hash_offset(&mut self) {
+        self.offsets_hasher
+            .update(&(self.byte_offset as u64).to_le_bytes());
+    }
+}
+
+fn spill_file(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+}
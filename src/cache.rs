@@ -0,0 +1,287 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use lru::LruCache;
+use uuid::Uuid;
+
+use crate::page::{Page, PageKey, PageMeta, PageReader};
+
+/// Identifies a cached page by the namespace of the store owning it and its
+/// key within that store.
+pub type CacheKey = (Uuid, PageKey);
+
+/// Called with every page the cache evicts to make room for another.
+pub type EvictCallback = Box<dyn FnMut(CacheKey, &Page) + Send>;
+
+/// Measured cost of loading pages from disk, for calibrating query cost
+/// estimates.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LoadProfile {
+    /// Mean time to read and decode one page, in microseconds.
+    pub avg_load_us: f64,
+    /// Page file bytes read and decoded per microsecond.
+    pub bytes_per_us: f64,
+}
+
+/// Counts of cache lookups since the cache was created or its stats were
+/// last reset.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Lookups of a page already in the cache.
+    pub hits: u64,
+    /// Lookups that loaded the page from disk.
+    pub misses: u64,
+    /// Pages dropped to make room for others.
+    pub evictions: u64,
+}
+
+pub struct PageCache {
+    pages: LruCache<CacheKey, Page>,
+    on_evict: Option<EvictCallback>,
+    /// Most decoded bytes the cached pages may take, for a cache bounded by
+    /// memory rather than by page count.
+    byte_budget: Option<usize>,
+    /// Decoded bytes of every cached page.
+    bytes: usize,
+    stats: CacheStats,
+}
+
+impl PageCache {
+    const SIZE: usize = 256;
+
+    /// The most pages `ensure_capacity_for` will grow the cache to.
+    pub const MAX_SIZE: usize = 16384;
+
+    pub fn new() -> Self {
+        PageCache::with_capacity(PageCache::SIZE)
+    }
+
+    /// Like `new`, but holds up to `cap` pages instead of 256.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is zero: `get` hands out a reference into the cache,
+    /// so it needs room for at least the page it just loaded.
+    pub fn with_capacity(cap: usize) -> Self {
+        assert!(cap > 0, "PageCache capacity must be at least 1");
+        PageCache {
+            pages: LruCache::new(cap),
+            on_evict: None,
+            byte_budget: None,
+            bytes: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// A cache holding pages until their decoded bytes would exceed
+    /// `bytes`, however many pages that is. A page larger than the whole
+    /// budget is still loaded, evicting every other page.
+    pub fn with_byte_budget(bytes: usize) -> Self {
+        PageCache {
+            pages: LruCache::unbounded(),
+            on_evict: None,
+            byte_budget: Some(bytes),
+            bytes: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Installs `on_evict` to be called with each page the LRU drops, for
+    /// instance to move it to a slower tier. Replaces any earlier callback.
+    pub fn set_on_evict<F>(&mut self, on_evict: F)
+    where
+        F: FnMut(CacheKey, &Page) + Send + 'static,
+    {
+        self.on_evict = Some(Box::new(on_evict));
+    }
+
+    /// Grows the cache to hold at least every page of `keys`, such as the
+    /// pages a query is about to touch, so that none of them is evicted
+    /// before the query is done with it. The cache never shrinks. Fails
+    /// without resizing if the working set exceeds `MAX_SIZE` pages.
+    pub fn ensure_capacity_for(&mut self, keys: &[PageKey]) -> io::Result<()> {
+        let needed = keys.iter().collect::<HashSet<_>>().len();
+        if needed > PageCache::MAX_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Working set of {} pages exceeds the cache limit of {}",
+                    needed,
+                    PageCache::MAX_SIZE
+                ),
+            ));
+        }
+        if needed > self.pages.cap() {
+            self.pages.resize(needed);
+        }
+        Ok(())
+    }
+
+    /// Number of pages the cache holds before evicting. Unbounded for a
+    /// cache with a byte budget.
+    pub fn capacity(&self) -> usize {
+        self.pages.cap()
+    }
+
+    /// Decoded bytes of every cached page.
+    pub fn decoded_len(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Zeroes the counts of `stats`, leaving the cached pages in place.
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Times loading every page of `sample` from disk. The pages bypass the
+    /// cache, so they are always read and the cache is left untouched. An
+    /// empty sample gives an all-zero profile.
+    pub fn benchmark_load(&self, sample: &[PageMeta]) -> io::Result<LoadProfile> {
+        if sample.is_empty() {
+            return Ok(LoadProfile::default());
+        }
+        let mut bytes = 0;
+        let start = Instant::now();
+        for meta in sample {
+            bytes += fs::metadata(&meta.path)?.len();
+            PageReader::read(meta)?;
+        }
+        let elapsed_us = start.elapsed().as_secs_f64() * 1_000_000.0;
+        Ok(LoadProfile {
+            avg_load_us: elapsed_us / sample.len() as f64,
+            bytes_per_us: bytes as f64 / elapsed_us,
+        })
+    }
+
+    /// Loads a page of the default, nil namespace.
+    pub fn get(&mut self, key: &PageKey, meta: &PageMeta) -> io::Result<&Page> {
+        self.get_in(Uuid::nil(), key, meta)
+    }
+
+    /// Like `get`, for a page of the store identified by `namespace`. Pages
+    /// of different namespaces never alias, even under equal keys, so
+    /// independent stores can share one cache.
+    pub fn get_in(&mut self, namespace: Uuid, key: &PageKey, meta: &PageMeta) -> io::Result<&Page> {
+        let cache_key = (namespace, *key);
+        if self.pages.contains(&cache_key) {
+            self.stats.hits += 1;
+        } else {
+            let page = PageReader::read(meta)?;
+            self.stats.misses += 1;
+            let page_len = page.data().decoded_len();
+            if let Some(budget) = self.byte_budget {
+                while self.bytes + page_len > budget {
+                    match self.pages.pop_lru() {
+                        Some((key, page)) => self.evicted(key, page),
+                        None => break,
+                    }
+                }
+            }
+            self.bytes += page_len;
+            if let Some((key, page)) = self.pages.push(cache_key, page) {
+                self.evicted(key, page);
+            }
+        }
+        Ok(self.pages.get(&cache_key).unwrap())
+    }
+
+    /// The page of `namespace` under `key` if it is cached, without loading
+    /// it or counting a hit or miss.
+    pub(crate) fn peek_in(&self, namespace: Uuid, key: &PageKey) -> Option<&Page> {
+        self.pages.peek(&(namespace, *key))
+    }
+
+    fn evicted(&mut self, key: CacheKey, page: Page) {
+        self.bytes -= page.data().decoded_len();
+        self.stats.evictions += 1;
+        if let Some(on_evict) = &mut self.on_evict {
+            on_evict(key, &page);
+        }
+    }
+}
+
+impl Default for PageCache {
+    fn default() -> Self {
+        PageCache::new()
+    }
+}
+
+/// A page cache shared by threads, for scanning collections from several
+/// threads at once. Lookups take `&self` and hand out pages as `Arc`s, so a
+/// page stays usable after another thread evicts it.
+pub struct SharedPageCache {
+    pages: Mutex<LruCache<CacheKey, Arc<Page>>>,
+    stats: Mutex<CacheStats>,
+}
+
+impl SharedPageCache {
+    pub fn new() -> Self {
+        SharedPageCache::with_capacity(PageCache::SIZE)
+    }
+
+    /// Like `new`, but holds up to `cap` pages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is zero, like `PageCache::with_capacity`.
+    pub fn with_capacity(cap: usize) -> Self {
+        assert!(cap > 0, "SharedPageCache capacity must be at least 1");
+        SharedPageCache {
+            pages: Mutex::new(LruCache::new(cap)),
+            stats: Mutex::new(CacheStats::default()),
+        }
+    }
+
+    /// Loads a page of the default, nil namespace.
+    pub fn get(&self, key: &PageKey, meta: &PageMeta) -> io::Result<Arc<Page>> {
+        self.get_in(Uuid::nil(), key, meta)
+    }
+
+    /// Like `PageCache::get_in`. Pages are read from disk without holding
+    /// the lock, so threads missing different pages load them in parallel.
+    /// Threads missing the same page at once may each read it.
+    pub fn get_in(&self, namespace: Uuid, key: &PageKey, meta: &PageMeta) -> io::Result<Arc<Page>> {
+        let cache_key = (namespace, *key);
+        if let Some(page) = self.pages.lock().unwrap().get(&cache_key) {
+            self.stats.lock().unwrap().hits += 1;
+            return Ok(Arc::clone(page));
+        }
+        let page = Arc::new(PageReader::read(meta)?);
+        let evicted = self
+            .pages
+            .lock()
+            .unwrap()
+            .push(cache_key, Arc::clone(&page));
+        let mut stats = self.stats.lock().unwrap();
+        stats.misses += 1;
+        // Another thread may have loaded the same page meanwhile, which
+        // `push` replaces rather than evicts.
+        if evicted.is_some_and(|(evicted_key, _)| evicted_key != cache_key) {
+            stats.evictions += 1;
+        }
+        Ok(page)
+    }
+
+    /// Like `PageCache::stats`, counting the lookups of every thread.
+    pub fn stats(&self) -> CacheStats {
+        *self.stats.lock().unwrap()
+    }
+
+    /// Number of pages the cache holds before evicting.
+    pub fn capacity(&self) -> usize {
+        self.pages.lock().unwrap().cap()
+    }
+}
+
+impl Default for SharedPageCache {
+    fn default() -> Self {
+        SharedPageCache::new()
+    }
+}
@@ -0,0 +1,2461 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::error;
+use std::fmt::{self, Write};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::mem;
+use std::ops::{self, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bitvec::prelude as bv;
+use bitvec::vec::BitVec;
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use uuid::Uuid;
+
+use crate::bloom::BloomFilter;
+use crate::cache::{PageCache, SharedPageCache};
+use crate::error::Error;
+use crate::json;
+use crate::manifest;
+use crate::page::{
+    Bound, Compression, Decimal, Page, PageData, PageKey, PageMeta, PageReader, PageVersion,
+    PageWriter, Type, Value, PAGE_FOOTER_LEN, PAGE_HEADER_LEN,
+};
+use crate::work::{SpillFiles, WorkDir};
+
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "memmap2")]
+mod mmap;
+#[cfg(feature = "rayon")]
+mod parallel;
+
+#[cfg(feature = "memmap2")]
+pub use self::mmap::MappedCollection;
+
+/// A predicate whose per-page match count is kept in the page stats.
+type CountPredicate = Arc<dyn Fn(&Value) -> bool + Send + Sync>;
+
+/// Subdirectory of a collection directory that `overwrite_int` moves the
+/// replaced page files to, out of the way of `rebuild_manifest`.
+const HISTORY_DIR: &str = "history";
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// The error for combining collections of `left` and `right` entries
+/// element-wise.
+fn size_mismatch(left: usize, right: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "Cannot combine collections of {} and {} entries",
+            left, right
+        ),
+    )
+}
+
+fn overlaps(bound: &Bound<i64>, range: &(ops::Bound<i64>, ops::Bound<i64>)) -> bool {
+    let above_lo = match range.0 {
+        ops::Bound::Included(lo) => bound.max >= lo,
+        ops::Bound::Excluded(lo) => bound.max > lo,
+        ops::Bound::Unbounded => true,
+    };
+    let below_hi = match range.1 {
+        ops::Bound::Included(hi) => bound.min <= hi,
+        ops::Bound::Excluded(hi) => bound.min < hi,
+        ops::Bound::Unbounded => true,
+    };
+    above_lo && below_hi
+}
+
+/// ORs the bloom filters of the pages of an int collection. `None` for other
+/// types or when a page has no filter.
+fn union_int_blooms<'a, I>(typ: Type, page_metas: I) -> Option<BloomFilter>
+where
+    I: IntoIterator<Item = &'a PageMeta>,
+{
+    if typ != Type::Int {
+        return None;
+    }
+    page_metas
+        .into_iter()
+        .try_fold(BloomFilter::new(), |mut bloom, meta| {
+            bloom.union(meta.stats.int_bloom.as_ref()?);
+            Some(bloom)
+        })
+}
+
+/// Snappy framing written into the payload of every page with values: the
+/// stream identifier and the header of its first chunk.
+const PAYLOAD_FRAME_LEN: u64 = 10 + 8;
+
+/// The on-disk size of a collection, split into the compressed values and
+/// the per-page overhead of headers, null sections and offsets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Footprint {
+    pub total: u64,
+    pub overhead: u64,
+    pub payload: u64,
+    pub pages: usize,
+    pub size: usize,
+}
+
+impl Footprint {
+    /// Estimates the total size after rewriting the collection into pages
+    /// of `target_page_size` entries. The compressed values and the
+    /// overhead that grows with the number of entries are kept, and the
+    /// fixed header and framing costs are charged once per new page.
+    pub fn estimate_compacted(&self, target_page_size: usize) -> u64 {
+        assert!(target_page_size > 0);
+        let pages = self.size.div_ceil(target_page_size) as u64;
+        let fixed = PAGE_HEADER_LEN + PAGE_FOOTER_LEN + PAYLOAD_FRAME_LEN;
+        let variable = self.total.saturating_sub(self.pages as u64 * fixed);
+        variable + pages * fixed
+    }
+}
+
+/// The schema and shape of a collection, as recorded in its manifest.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CollectionInfo {
+    pub typ: Type,
+    pub size: usize,
+    pub page_count: usize,
+}
+
+/// How `cumsum_int` writes the rows of null entries, which never add to
+/// the running total.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NullPolicy {
+    /// The row holds the running total, as if the entry were zero.
+    AsZero,
+    /// The row stays null.
+    Propagate,
+}
+
+/// How the pages of a collection fail to tile its entries, found by
+/// `check_continuity`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContinuityError {
+    /// The page starts after the end of the page before it.
+    Gap {
+        key: PageKey,
+        expected: usize,
+        offset: usize,
+    },
+    /// The page starts before the end of the page before it.
+    Overlap {
+        key: PageKey,
+        expected: usize,
+        offset: usize,
+    },
+    /// The pages hold another number of entries than the collection.
+    SizeMismatch { pages: usize, size: usize },
+}
+
+impl fmt::Display for ContinuityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContinuityError::Gap {
+                key,
+                expected,
+                offset,
+            } => write!(
+                f,
+                "Page {:?} starts at {}, leaving a gap after {}",
+                key, offset, expected
+            ),
+            ContinuityError::Overlap {
+                key,
+                expected,
+                offset,
+            } => write!(
+                f,
+                "Page {:?} starts at {}, overlapping the page ending at {}",
+                key, offset, expected
+            ),
+            ContinuityError::SizeMismatch { pages, size } => write!(
+                f,
+                "Pages hold {} entries but the collection {}",
+                pages, size
+            ),
+        }
+    }
+}
+
+impl error::Error for ContinuityError {}
+
+#[derive(Clone)]
+pub struct Collection {
+    id: Uuid,
+    namespace: Uuid,
+    page_metas: BTreeMap<PageKey, PageMeta>,
+    /// First index and key of every page in order, for binary searching the
+    /// page holding an entry. Rebuilt by `reindex` whenever pages change.
+    starts: Vec<(usize, PageKey)>,
+    size: usize,
+    typ: Type,
+    version: u64,
+    /// Set by `seal`, after which the pages can no longer change.
+    sealed: bool,
+    /// Union of the page bloom filters of an int collection, or `None` when
+    /// a page has no filter.
+    int_bloom: Option<BloomFilter>,
+    /// Predicates counted in every page written from now on, by name.
+    count_predicates: Vec<(String, CountPredicate)>,
+    /// How the pages written from now on compress their values.
+    compression: Compression,
+    /// Whether the string and binary pages written from now on store their
+    /// offsets as fixed-width words.
+    fixed_offsets: bool,
+    /// Whether plain int pages written from now on are re-encoded with the
+    /// encoding `PageData::recommend_encoding` picks for them.
+    auto_encoding: bool,
+}
+
+impl Collection {
+    pub fn new(page_metas: Vec<PageMeta>) -> Self {
+        let typ = {
+            let mut types = page_metas
+                .iter()
+                .map(|meta| meta.typ)
+                .collect::<HashSet<Type>>()
+                .into_iter();
+            let t = types.next();
+            assert!(t.is_some() && types.next().is_none());
+            t.unwrap()
+        };
+
+        let id = Uuid::new_v4();
+        let size = page_metas.iter().fold(0, |acc, meta| acc + meta.size);
+        let int_bloom = union_int_blooms(typ, &page_metas);
+        let mut collection = Collection {
+            id,
+            namespace: Uuid::nil(),
+            page_metas: page_metas
+                .into_iter()
+                .enumerate()
+                .map(|(page_idx, meta)| ((id, page_idx), meta))
+                .collect(),
+            size,
+            typ,
+            version: 0,
+            sealed: false,
+            int_bloom,
+            count_predicates: vec![],
+            starts: vec![],
+            compression: Compression::Snappy,
+            fixed_offsets: false,
+            auto_encoding: false,
+        };
+        collection.reindex();
+        collection
+    }
+
+    pub fn empty(typ: Type) -> Self {
+        Collection {
+            id: Uuid::new_v4(),
+            namespace: Uuid::nil(),
+            page_metas: BTreeMap::new(),
+            size: 0,
+            typ,
+            version: 0,
+            sealed: false,
+            int_bloom: union_int_blooms(typ, &[]),
+            count_predicates: vec![],
+            starts: vec![],
+            compression: Compression::Snappy,
+            fixed_offsets: false,
+            auto_encoding: false,
+        }
+    }
+
+    /// Writes `values` to `dir` as a new int collection made of pages of
+    /// `page_size` entries, holding at most one page in memory.
+    pub fn from_ints<I>(dir: &Path, values: I, page_size: usize) -> io::Result<Collection>
+    where
+        I: IntoIterator<Item = Option<i64>>,
+    {
+        Collection::try_from_ints(dir, values.into_iter().map(Ok), page_size)
+    }
+
+    /// Like `from_ints`, but stops at the first value that failed to be read
+    /// and returns its error.
+    fn try_from_ints<I>(dir: &Path, values: I, page_size: usize) -> io::Result<Collection>
+    where
+        I: IntoIterator<Item = crate::Result<Option<i64>>>,
+    {
+        assert!(page_size > 0);
+        let mut collection = Collection::empty(Type::Int);
+        let mut page = Vec::with_capacity(page_size);
+
+        for value in values {
+            page.push(value?);
+            if page.len() == page_size {
+                collection.append_page(dir, PageData::from_ints(&page)?)?;
+                page.clear();
+            }
+        }
+        if !page.is_empty() {
+            collection.append_page(dir, PageData::from_ints(&page)?)?;
+        }
+        Ok(collection)
+    }
+
+    /// Like `from_ints`, for bool values.
+    pub fn from_bools<I>(dir: &Path, values: I, page_size: usize) -> io::Result<Collection>
+    where
+        I: IntoIterator<Item = Option<bool>>,
+    {
+        Collection::try_from_bools(dir, values.into_iter().map(Ok), page_size)
+    }
+
+    /// Like `from_bools`, but stops at the first value that failed to be read
+    /// and returns its error.
+    fn try_from_bools<I>(dir: &Path, values: I, page_size: usize) -> io::Result<Collection>
+    where
+        I: IntoIterator<Item = crate::Result<Option<bool>>>,
+    {
+        assert!(page_size > 0);
+        let mut collection = Collection::empty(Type::Bool);
+        let mut page = Vec::with_capacity(page_size);
+
+        for value in values {
+            page.push(value?);
+            if page.len() == page_size {
+                collection.append_page(dir, PageData::from_bools(&page)?)?;
+                page.clear();
+            }
+        }
+        if !page.is_empty() {
+            collection.append_page(dir, PageData::from_bools(&page)?)?;
+        }
+        Ok(collection)
+    }
+
+    /// Opens the collection whose manifest was written to `dir` by
+    /// `write_manifest`.
+    pub fn open(dir: &Path) -> io::Result<Collection> {
+        let (summary, pages) = manifest::read(dir)?;
+        let mut collection = Collection::empty(summary.typ);
+        for meta in pages {
+            collection.size += meta.size;
+            collection
+                .page_metas
+                .insert((collection.id, collection.page_metas.len()), meta);
+        }
+        if collection.size != summary.size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Manifest pages hold {} entries but expect {}",
+                    collection.size, summary.size
+                ),
+            ));
+        }
+        collection.version = summary.version;
+        collection.sealed = summary.sealed;
+        collection.int_bloom = union_int_blooms(collection.typ, collection.page_metas.values());
+        collection.reindex();
+        Ok(collection)
+    }
+
+    /// Like `open`, but fails if the collection in `dir` is not of the
+    /// `expected` type.
+    pub fn open_typed(dir: &Path, expected: Type) -> io::Result<Collection> {
+        let collection = Collection::open(dir)?;
+        if collection.typ != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Expected {:?} collection in {:?}, found {:?}",
+                    expected, dir, collection.typ
+                ),
+            ));
+        }
+        Ok(collection)
+    }
+
+    /// Reads the type, size and page count of the collection in `dir` from
+    /// the summary line of its manifest, without reading the page list or
+    /// opening any page.
+    pub fn describe(dir: &Path) -> io::Result<CollectionInfo> {
+        let summary = manifest::summary(dir)?;
+        Ok(CollectionInfo {
+            typ: summary.typ,
+            size: summary.size,
+            page_count: summary.page_count,
+        })
+    }
+
+    /// Records the pages of the collection in a manifest in `dir`, replacing
+    /// any previous one, so that `open` and `describe` can find them.
+    pub fn write_manifest(&self, dir: &Path) -> io::Result<()> {
+        let summary = manifest::Summary {
+            typ: self.typ,
+            size: self.size,
+            page_count: self.page_metas.len(),
+            version: self.version,
+            sealed: self.sealed,
+        };
+        manifest::write(dir, &summary, self.page_metas.values())
+    }
+
+    /// Recovers a lost manifest of `dir` from the descriptors in the headers
+    /// of the page files it holds, ordering the pages by their stored
+    /// offsets. Every page is read to restore its checksum and stats. The
+    /// version of the rebuilt collection is its page count, and it stays
+    /// sealed if it was when its manifest was last written. Page files
+    /// replaced by `overwrite_int` are kept apart in the history
+    /// subdirectory and are not part of the rebuilt collection, so its page
+    /// history is lost.
+    pub fn rebuild_manifest(dir: &Path) -> io::Result<()> {
+        let mut pages = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_spill = path.extension().is_some_and(|ext| ext == "tmp");
+            // Skips the history subdirectory along with any other.
+            let is_marker = path.ends_with(manifest::MANIFEST) || path.ends_with(manifest::SEALED);
+            if path.is_file() && !is_spill && !is_marker {
+                let meta = PageReader::read_meta(&path)?;
+                pages.push(PageReader::read(&meta)?.meta().clone());
+            }
+        }
+        pages.sort_by_key(|meta| meta.offset);
+
+        let typ = match pages.first() {
+            Some(meta) => meta.typ,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No pages found in {:?}", dir),
+                ))
+            }
+        };
+        let mut size = 0;
+        for meta in &pages {
+            if meta.typ != typ || meta.offset != size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Page {:?} does not continue a {:?} collection at offset {}",
+                        meta.path, typ, size
+                    ),
+                ));
+            }
+            size += meta.size;
+        }
+
+        let summary = manifest::Summary {
+            typ,
+            size,
+            page_count: pages.len(),
+            version: pages.len() as u64,
+            sealed: dir.join(manifest::SEALED).is_file(),
+        };
+        manifest::write(dir, &summary, &pages)
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// The store the collection belongs to, which keeps its pages apart
+    /// from those of other stores in a shared cache.
+    pub fn namespace(&self) -> Uuid {
+        self.namespace
+    }
+
+    pub fn set_namespace(&mut self, namespace: Uuid) {
+        self.namespace = namespace;
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn typ(&self) -> Type {
+        self.typ
+    }
+
+    /// Incremented by every append, so a version identifies a prefix of the
+    /// collection's pages.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Whether `seal` was called, on this collection or on the one whose
+    /// manifest it was opened from.
+    pub fn is_sealed(&self) -> bool {
+        self.sealed
+    }
+
+    /// Marks the collection as final. Afterwards every method that would
+    /// change its pages fails with `Error::Sealed`, carried in an
+    /// `io::Error` of kind `PermissionDenied`, while reads are unaffected.
+    /// The mark is persisted by `write_manifest`.
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Whether the non-null values of the int collection are known to never
+    /// decrease, decided from page stats alone: every page must be sorted and
+    /// start no lower than the previous page ends. `false` when a non-empty
+    /// page has no recorded bound.
+    pub fn is_globally_sorted(&self) -> bool {
+        assert!(self.typ == Type::Int);
+        let mut prev_max = i64::MIN;
+        for meta in self.page_metas.values().filter(|meta| meta.size > 0) {
+            match &meta.stats.int_bound {
+                Some(bound) if meta.stats.sorted && bound.min >= prev_max => {
+                    prev_max = bound.max;
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// The lexicographic min and max of the string collection, folded from
+    /// the page stats without loading any page. `None` when an empty
+    /// collection or any non-empty page has no recorded bound.
+    pub fn string_bound(&self) -> Option<Bound<String>> {
+        assert!(self.typ == Type::String);
+        let mut bounds = self
+            .page_metas
+            .values()
+            .filter(|meta| meta.size > 0)
+            .map(|meta| meta.stats.string_bound.as_ref());
+        let first = bounds.next()??.clone();
+        bounds.try_fold(first, |mut acc, bound| {
+            let bound = bound?;
+            if bound.min < acc.min {
+                acc.min = bound.min.clone();
+            }
+            if bound.max > acc.max {
+                acc.max = bound.max.clone();
+            }
+            Some(acc)
+        })
+    }
+
+    /// Whether the int collection may hold `value`, answered from the union
+    /// of its page bloom filters without loading any page. `false`
+    /// guarantees absence.
+    pub fn might_contain_int(&self, value: i64) -> bool {
+        assert!(self.typ == Type::Int);
+        match &self.int_bloom {
+            Some(bloom) => bloom.might_contain(value),
+            None => true,
+        }
+    }
+
+    /// Fails with `OutOfBounds` for an index past the end, `TypeMismatch`
+    /// on a collection of another type and with the load error of an
+    /// unreadable page.
+    pub fn get_bool(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Option<bool>> {
+        let (page, start) = self.find_page(cache, idx)?;
+        page.get_bool(idx - start)
+    }
+
+    pub fn get_int(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Option<i64>> {
+        let (page, start) = self.find_page(cache, idx)?;
+        page.get_int(idx - start)
+    }
+
+    /// Like `get_int`, but substitutes `default` for a null entry.
+    pub fn get_int_or(
+        &self,
+        cache: &mut PageCache,
+        idx: usize,
+        default: i64,
+    ) -> crate::Result<i64> {
+        Ok(self.get_int(cache, idx)?.unwrap_or(default))
+    }
+
+    /// Reads entry `idx` as it was at collection `version`, following the
+    /// page history kept by `overwrite_int`. Pages appended after `version`
+    /// are read as they are now. Replaced files are cached under their own
+    /// page id.
+    pub fn get_int_at(
+        &self,
+        cache: &mut PageCache,
+        idx: usize,
+        version: u64,
+    ) -> crate::Result<Option<i64>> {
+        let (key, start) = self.locate(idx).ok_or(Error::OutOfBounds {
+            index: idx,
+            size: self.size,
+        })?;
+        let meta = &self.page_metas[&key];
+        match meta.history.iter().find(|old| old.replaced_at > version) {
+            Some(old) => {
+                let mut old_meta =
+                    PageMeta::with_id(old.id, meta.typ, &old.path, meta.offset, meta.size);
+                old_meta.checksum = old.checksum;
+                cache
+                    .get_in(self.namespace, &(old.id, key.1), &old_meta)?
+                    .get_int(idx - start)
+            }
+            None => cache
+                .get_in(self.namespace, &key, meta)?
+                .get_int(idx - start),
+        }
+    }
+
+    pub fn get_i32(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Option<i32>> {
+        let (page, start) = self.find_page(cache, idx)?;
+        page.get_i32(idx - start)
+    }
+
+    /// Milliseconds since the Unix epoch.
+    pub fn get_timestamp(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Option<i64>> {
+        let (page, start) = self.find_page(cache, idx)?;
+        page.get_timestamp(idx - start)
+    }
+
+    pub fn get_u64(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Option<u64>> {
+        let (page, start) = self.find_page(cache, idx)?;
+        page.get_u64(idx - start)
+    }
+
+    pub fn get_float(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Option<f64>> {
+        let (page, start) = self.find_page(cache, idx)?;
+        page.get_float(idx - start)
+    }
+
+    pub fn get_f32(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Option<f32>> {
+        let (page, start) = self.find_page(cache, idx)?;
+        page.get_f32(idx - start)
+    }
+
+    /// The decimal at `idx`, with the scale of the page holding it.
+    pub fn get_decimal(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Option<Decimal>> {
+        let (page, start) = self.find_page(cache, idx)?;
+        page.get_decimal(idx - start)
+    }
+
+    pub fn get_binary(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Option<Vec<u8>>> {
+        let (page, start) = self.find_page(cache, idx)?;
+        Ok(page.get_binary(idx - start)?.map(<[u8]>::to_vec))
+    }
+
+    pub fn get_string(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Option<String>> {
+        let (page, start) = self.find_page(cache, idx)?;
+        page.get_string(idx - start)
+    }
+
+    /// Like `get_bool`, but reads through a cache shared with other threads.
+    pub fn get_bool_shared(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<Option<bool>> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        page.get_bool(idx - start)
+    }
+
+    pub fn get_int_shared(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<Option<i64>> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        page.get_int(idx - start)
+    }
+
+    pub fn get_float_shared(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<Option<f64>> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        page.get_float(idx - start)
+    }
+
+    pub fn get_string_shared(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<Option<String>> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        page.get_string(idx - start)
+    }
+
+    pub fn get_i32_shared(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<Option<i32>> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        page.get_i32(idx - start)
+    }
+
+    pub fn get_u64_shared(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<Option<u64>> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        page.get_u64(idx - start)
+    }
+
+    pub fn get_f32_shared(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<Option<f32>> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        page.get_f32(idx - start)
+    }
+
+    pub fn get_timestamp_shared(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<Option<i64>> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        page.get_timestamp(idx - start)
+    }
+
+    pub fn get_binary_shared(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<Option<Vec<u8>>> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        Ok(page.get_binary(idx - start)?.map(<[u8]>::to_vec))
+    }
+
+    pub fn get_decimal_shared(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<Option<Decimal>> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        page.get_decimal(idx - start)
+    }
+
+    /// Like `get_value`, but reads through a cache shared with other
+    /// threads.
+    pub fn get_value_shared(&self, cache: &SharedPageCache, idx: usize) -> crate::Result<Value> {
+        let (page, start) = self.find_shared_page(cache, idx)?;
+        page.get_value(idx - start)
+    }
+
+    /// Sets how the pages this collection writes from now on compress their
+    /// values, including the zstd level. Existing pages keep theirs.
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    /// Makes the string and binary pages this collection writes from now on
+    /// store their offsets as fixed-width words rather than varint lengths,
+    /// as `PageWriter::write_fixed_offsets` does, so they can be mapped.
+    /// Existing pages keep theirs.
+    pub fn set_fixed_offsets(&mut self, fixed_offsets: bool) {
+        self.fixed_offsets = fixed_offsets;
+    }
+
+    /// Makes the plain int pages this collection writes from now on use
+    /// the smallest encoding for their values, such as bit packing for a
+    /// small range. Existing pages keep theirs.
+    pub fn set_auto_encoding(&mut self, auto_encoding: bool) {
+        self.auto_encoding = auto_encoding;
+    }
+
+    /// Counts the entries matching `predicate` in every page written by
+    /// this collection from now on, keeping the count in the page stats and
+    /// the manifest so `cached_count` can answer without loading pages.
+    /// `name` must not contain whitespace.
+    pub fn register_count<F>(&mut self, name: &str, predicate: F)
+    where
+        F: Fn(&Value) -> bool + Send + Sync + 'static,
+    {
+        assert!(!name.is_empty() && !name.contains(char::is_whitespace));
+        self.count_predicates.retain(|(other, _)| other != name);
+        self.count_predicates
+            .push((name.to_string(), Arc::new(predicate)));
+    }
+
+    /// The number of entries matching the predicate registered as `name`,
+    /// summed from page metadata. `None` if a page was written without
+    /// counting it.
+    pub fn cached_count(&self, name: &str) -> Option<usize> {
+        self.page_metas
+            .values()
+            .map(|meta| meta.stats.match_counts.get(name))
+            .sum::<Option<usize>>()
+    }
+
+    /// Reads an entry of any type, dispatching on the collection's type.
+    pub fn get_value(&self, cache: &mut PageCache, idx: usize) -> crate::Result<Value> {
+        let (page, start) = self.find_page(cache, idx)?;
+        page.get_value(idx - start)
+    }
+
+    /// Writes every entry to `writer` as newline-delimited JSON, one value
+    /// or `null` per line.
+    pub fn to_ndjson<W: io::Write>(&self, cache: &mut PageCache, mut writer: W) -> io::Result<()> {
+        let mut line = String::new();
+        for idx in 0..self.size {
+            line.clear();
+            json::push_value(&mut line, &self.get_value(cache, idx)?);
+            line.push('\n');
+            writer.write_all(line.as_bytes())?;
+        }
+        writer.flush()
+    }
+
+    pub fn bool_iter<'a>(&'a self, cache: &'a mut PageCache) -> CollectionBoolIter<'a> {
+        CollectionBoolIter::new(cache, self)
+    }
+
+    pub fn int_iter<'a>(&'a self, cache: &'a mut PageCache) -> CollectionIntIter<'a> {
+        CollectionIntIter::new(cache, self)
+    }
+
+    /// Like `int_iter`, but yields `default` for null entries.
+    pub fn int_iter_or<'a>(
+        &'a self,
+        cache: &'a mut PageCache,
+        default: i64,
+    ) -> impl Iterator<Item = crate::Result<i64>> + 'a {
+        self.int_iter(cache)
+            .map(move |entry| Ok(entry?.unwrap_or(default)))
+    }
+
+    /// Walks this collection and an equally sized `other` int collection in
+    /// lockstep, loading the pages of both through one cache. Fails with
+    /// `InvalidInput` if the sizes differ.
+    pub fn zip_int_iter<'a>(
+        &'a self,
+        other: &'a Collection,
+        cache: &'a mut PageCache,
+    ) -> io::Result<CollectionIntZipIter<'a>> {
+        CollectionIntZipIter::new(cache, self, other)
+    }
+
+    pub fn i32_iter<'a>(&'a self, cache: &'a mut PageCache) -> CollectionI32Iter<'a> {
+        CollectionI32Iter::new(cache, self)
+    }
+
+    pub fn timestamp_iter<'a>(&'a self, cache: &'a mut PageCache) -> CollectionTimestampIter<'a> {
+        CollectionTimestampIter::new(cache, self)
+    }
+
+    pub fn uint_iter<'a>(&'a self, cache: &'a mut PageCache) -> CollectionUIntIter<'a> {
+        CollectionUIntIter::new(cache, self)
+    }
+
+    pub fn float_iter<'a>(&'a self, cache: &'a mut PageCache) -> CollectionFloatIter<'a> {
+        CollectionFloatIter::new(cache, self)
+    }
+
+    pub fn float32_iter<'a>(&'a self, cache: &'a mut PageCache) -> CollectionFloat32Iter<'a> {
+        CollectionFloat32Iter::new(cache, self)
+    }
+
+    pub fn string_iter<'a>(&'a self, cache: &'a mut PageCache) -> CollectionStringIter<'a> {
+        CollectionStringIter::new(cache, self)
+    }
+
+    /// Yields, for each entry, the minimum non-null value among it and the
+    /// `window - 1` entries before it, or `None` if they are all null. The
+    /// first entries see a shorter window. Each entry costs amortized O(1).
+    pub fn rolling_min_int<'a>(
+        &'a self,
+        cache: &'a mut PageCache,
+        window: usize,
+    ) -> impl Iterator<Item = crate::Result<Option<i64>>> + 'a {
+        self.rolling_int(cache, window, |value, other| value <= other)
+    }
+
+    /// Like `rolling_min_int`, for the maximum.
+    pub fn rolling_max_int<'a>(
+        &'a self,
+        cache: &'a mut PageCache,
+        window: usize,
+    ) -> impl Iterator<Item = crate::Result<Option<i64>>> + 'a {
+        self.rolling_int(cache, window, |value, other| value >= other)
+    }
+
+    /// Collects every entry into a vector sized up front, decoding each page
+    /// in bulk. Meant for small collections.
+    pub fn to_vec_bool(&self, cache: &mut PageCache) -> crate::Result<Vec<Option<bool>>> {
+        assert!(self.typ == Type::Bool);
+        let mut entries = Vec::with_capacity(self.size);
+        for (key, meta) in self.page_metas.iter() {
+            let page = self.load_page(cache, key, meta)?;
+            for idx in 0..meta.size {
+                entries.push(page.get_bool(idx)?);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Like `to_vec_bool`, for int collections.
+    pub fn to_vec_int(&self, cache: &mut PageCache) -> crate::Result<Vec<Option<i64>>> {
+        let mut entries = Vec::with_capacity(self.size);
+        self.scan_int_pages(cache, |values, validity| {
+            entries.extend(values.iter().zip(validity.iter()).map(|(value, valid)| {
+                if valid {
+                    Some(*value)
+                } else {
+                    None
+                }
+            }));
+        })?;
+        Ok(entries)
+    }
+
+    /// The last `n` entries, or every entry if there are fewer. Only the
+    /// pages holding them are loaded.
+    pub fn tail_int(&self, cache: &mut PageCache, n: usize) -> crate::Result<Vec<Option<i64>>> {
+        assert!(self.typ == Type::Int);
+        let start = self.size.saturating_sub(n);
+        let mut entries = Vec::with_capacity(self.size - start);
+        let starts = self.page_starts();
+        let first_page = starts
+            .partition_point(|(page_start, _)| *page_start <= start)
+            .saturating_sub(1);
+        for (page_start, key) in &starts[first_page..] {
+            let meta = &self.page_metas[key];
+            let page = self.load_page(cache, key, meta)?;
+            let first = start.saturating_sub(*page_start);
+            for idx in first..meta.size {
+                entries.push(page.get_int(idx)?);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Like `to_vec_bool`, for float collections.
+    pub fn to_vec_float(&self, cache: &mut PageCache) -> crate::Result<Vec<Option<f64>>> {
+        assert!(self.typ == Type::Float);
+        let mut entries = Vec::with_capacity(self.size);
+        let mut values = vec![];
+        for (key, meta) in self.page_metas.iter() {
+            let page = self.load_page(cache, key, meta)?;
+            page.data().read_floats(&mut values);
+            entries.extend(values.iter().enumerate().map(|(idx, value)| {
+                if page.is_null(idx) {
+                    None
+                } else {
+                    Some(*value)
+                }
+            }));
+        }
+        Ok(entries)
+    }
+
+    /// Like `to_vec_bool`, for string collections.
+    pub fn to_vec_string(&self, cache: &mut PageCache) -> crate::Result<Vec<Option<String>>> {
+        assert!(self.typ == Type::String);
+        let mut entries = Vec::with_capacity(self.size);
+        for (key, meta) in self.page_metas.iter() {
+            let page = self.load_page(cache, key, meta)?;
+            let (bytes, offsets) = page.data().string_parts();
+            entries.extend((0..meta.size).map(|idx| {
+                if page.is_null(idx) {
+                    None
+                } else {
+                    let slice = &bytes[offsets[idx]..offsets[idx + 1]];
+                    Some(String::from_utf8_lossy(slice).into_owned())
+                }
+            }));
+        }
+        Ok(entries)
+    }
+
+    /// Returns the index and value of every non-null entry between `lo` and
+    /// `hi`. Pages whose stored bound lies entirely outside the range are
+    /// skipped without being loaded.
+    pub fn between_int(
+        &self,
+        cache: &mut PageCache,
+        lo: ops::Bound<i64>,
+        hi: ops::Bound<i64>,
+    ) -> crate::Result<Vec<(usize, i64)>> {
+        let range = (lo, hi);
+        let mut matches = vec![];
+        let mut offset = 0;
+
+        for (key, meta) in self.page_metas.iter() {
+            let skip = match &meta.stats.int_bound {
+                Some(bound) => !overlaps(bound, &range),
+                None => false,
+            };
+            if !skip {
+                let page = self.load_page(cache, key, meta)?;
+                for idx in 0..meta.size {
+                    if let Some(value) = page.get_int(idx)? {
+                        if range.contains(&value) {
+                            matches.push((offset + idx, value));
+                        }
+                    }
+                }
+            }
+            offset += meta.size;
+        }
+        Ok(matches)
+    }
+
+    /// The indices of the non-null entries between `low` and `high`
+    /// inclusive. Like `between_int`, pages whose bound excludes the range
+    /// are never loaded.
+    pub fn int_range(
+        &self,
+        cache: &mut PageCache,
+        low: i64,
+        high: i64,
+    ) -> crate::Result<Vec<usize>> {
+        let matches =
+            self.between_int(cache, ops::Bound::Included(low), ops::Bound::Included(high))?;
+        Ok(matches.into_iter().map(|(idx, _)| idx).collect())
+    }
+
+    /// The `k` largest non-null entries with their indices, largest first and
+    /// equal values by index. Only the current top `k` are held, in a
+    /// min-heap, and pages whose stored bound cannot beat the smallest of
+    /// them are skipped without being loaded.
+    pub fn top_k_int(&self, cache: &mut PageCache, k: usize) -> crate::Result<Vec<(usize, i64)>> {
+        assert!(self.typ == Type::Int);
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        let mut offset = 0;
+
+        for (key, meta) in self.page_metas.iter() {
+            // Later pages hold later indices, which lose ties, so a page
+            // must hold a strictly larger value to enter a full heap.
+            let skip = match (&meta.stats.int_bound, heap.peek()) {
+                (Some(bound), Some(Reverse((smallest, _)))) if heap.len() == k => {
+                    bound.max <= *smallest
+                }
+                _ => k == 0,
+            };
+            if !skip {
+                let page = self.load_page(cache, key, meta)?;
+                for idx in 0..meta.size {
+                    if let Some(value) = page.get_int(idx)? {
+                        heap.push(Reverse((value, Reverse(offset + idx))));
+                        if heap.len() > k {
+                            heap.pop();
+                        }
+                    }
+                }
+            }
+            offset += meta.size;
+        }
+
+        Ok(heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((value, Reverse(idx)))| (idx, value))
+            .collect())
+    }
+
+    /// Calls `f` once per page with the page's decoded values and its
+    /// validity bitmap (set for non-null entries). Null slots in the value
+    /// slice hold a zero filler.
+    pub fn scan_int_pages<F>(&self, cache: &mut PageCache, mut f: F) -> crate::Result<()>
+    where
+        F: FnMut(&[i64], &BitVec<bv::LittleEndian, u8>),
+    {
+        assert!(self.typ == Type::Int);
+        let mut values = vec![];
+
+        for (key, meta) in self.page_metas.iter() {
+            let page = self.load_page(cache, key, meta)?;
+            page.data().read_ints(&mut values);
+            let validity = (0..meta.size)
+                .map(|idx| !page.is_null(idx))
+                .collect::<BitVec<bv::LittleEndian, u8>>();
+            f(&values[..meta.size], &validity);
+        }
+        Ok(())
+    }
+
+    /// The mean of the non-null entries of each float page, or `None` for a
+    /// page without any. Pages known to hold only nulls are not loaded.
+    pub fn page_averages_float(
+        &self,
+        cache: &mut PageCache,
+    ) -> crate::Result<Vec<(PageKey, Option<f64>)>> {
+        assert!(self.typ == Type::Float);
+        self.page_metas
+            .iter()
+            .map(|(key, meta)| {
+                if meta.null_count() == meta.size {
+                    return Ok((*key, None));
+                }
+                let page = self.load_page(cache, key, meta)?;
+                let (mut sum, mut count) = (0.0, 0);
+                for idx in 0..meta.size {
+                    if let Some(value) = page.get_float(idx)? {
+                        sum += value;
+                        count += 1;
+                    }
+                }
+                let average = if count > 0 {
+                    Some(sum / count as f64)
+                } else {
+                    None
+                };
+                Ok((*key, average))
+            })
+            .collect()
+    }
+
+    /// The population variance of the non-null entries, or `None` if there
+    /// are none. Computed in one pass with Welford's algorithm, which stays
+    /// accurate when the mean is large next to the spread. Pages known to
+    /// hold only nulls are not loaded.
+    pub fn variance_float(&self, cache: &mut PageCache) -> crate::Result<Option<f64>> {
+        assert!(self.typ == Type::Float);
+        let (mut count, mut mean, mut m2) = (0u64, 0.0, 0.0);
+        for (key, meta) in self.page_metas.iter() {
+            if meta.null_count() == meta.size {
+                continue;
+            }
+            let page = self.load_page(cache, key, meta)?;
+            for idx in 0..meta.size {
+                if let Some(value) = page.get_float(idx)? {
+                    count += 1;
+                    let delta = value - mean;
+                    mean += delta / count as f64;
+                    m2 += delta * (value - mean);
+                }
+            }
+        }
+        Ok(if count > 0 {
+            Some(m2 / count as f64)
+        } else {
+            None
+        })
+    }
+
+    /// The population standard deviation of the non-null entries, or `None`
+    /// if there are none.
+    pub fn stddev_float(&self, cache: &mut PageCache) -> crate::Result<Option<f64>> {
+        Ok(self.variance_float(cache)?.map(f64::sqrt))
+    }
+
+    /// Sums the non-null entries, or `None` if the sum overflows an `i64`.
+    pub fn checked_sum_int(&self, cache: &mut PageCache) -> crate::Result<Option<i64>> {
+        let mut sum = Some(0i64);
+        self.scan_int_pages(cache, |values, validity| {
+            for (value, valid) in values.iter().zip(validity.iter()) {
+                if valid {
+                    sum = sum.and_then(|sum| sum.checked_add(*value));
+                }
+            }
+        })?;
+        Ok(sum)
+    }
+
+    /// Sums the non-null entries into an `i128`, which cannot overflow for
+    /// fewer than 2^64 entries.
+    pub fn sum_i128(&self, cache: &mut PageCache) -> crate::Result<i128> {
+        let mut sum = 0;
+        self.scan_int_pages(cache, |values, validity| {
+            for (value, valid) in values.iter().zip(validity.iter()) {
+                if valid {
+                    sum += i128::from(*value);
+                }
+            }
+        })?;
+        Ok(sum)
+    }
+
+    /// Returns every value appearing more than once with the indices of all
+    /// its entries, ordered by value. Nulls are ignored. The indices of every
+    /// distinct value are held in memory until the scan ends, so large
+    /// columns may prefer `duplicate_counts_int`.
+    pub fn find_duplicates_int(
+        &self,
+        cache: &mut PageCache,
+    ) -> crate::Result<Vec<(i64, Vec<usize>)>> {
+        let mut indices: HashMap<i64, Vec<usize>> = HashMap::new();
+        let mut offset = 0;
+        self.scan_int_pages(cache, |values, validity| {
+            for (idx, (value, valid)) in values.iter().zip(validity.iter()).enumerate() {
+                if valid {
+                    indices.entry(*value).or_default().push(offset + idx);
+                }
+            }
+            offset += values.len();
+        })?;
+
+        let mut duplicates = indices
+            .into_iter()
+            .filter(|(_, indices)| indices.len() > 1)
+            .collect::<Vec<_>>();
+        duplicates.sort_unstable_by_key(|(value, _)| *value);
+        Ok(duplicates)
+    }
+
+    /// Like `find_duplicates_int`, but only counts the entries of each
+    /// duplicated value, keeping one counter per distinct value in memory.
+    pub fn duplicate_counts_int(&self, cache: &mut PageCache) -> crate::Result<Vec<(i64, usize)>> {
+        let mut counts: HashMap<i64, usize> = HashMap::new();
+        self.scan_int_pages(cache, |values, validity| {
+            for (value, valid) in values.iter().zip(validity.iter()) {
+                if valid {
+                    *counts.entry(*value).or_insert(0) += 1;
+                }
+            }
+        })?;
+
+        let mut duplicates = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .collect::<Vec<_>>();
+        duplicates.sort_unstable_by_key(|(value, _)| *value);
+        Ok(duplicates)
+    }
+
+    /// Splits the collection into its raw values, with the zero filler in
+    /// null slots, and a validity bitmap set for non-null entries, the way
+    /// Arrow lays out a column.
+    pub fn int_values_and_validity(
+        &self,
+        cache: &mut PageCache,
+    ) -> crate::Result<(impl Iterator<Item = i64>, BitVec<bv::LittleEndian, u8>)> {
+        let mut values = Vec::with_capacity(self.size);
+        let mut validity = BitVec::with_capacity(self.size);
+        self.scan_int_pages(cache, |page_values, page_validity| {
+            values.extend_from_slice(page_values);
+            validity.extend(page_validity.iter());
+        })?;
+        Ok((values.into_iter(), validity))
+    }
+
+    /// Writes the entries matching `pred` to `dir` as a new, compacted
+    /// collection with pages of `page_size` entries. Rows are renumbered.
+    pub fn retain_int<F>(
+        &self,
+        dir: &Path,
+        cache: &mut PageCache,
+        pred: F,
+        page_size: usize,
+    ) -> io::Result<Collection>
+    where
+        F: Fn(Option<i64>) -> bool,
+    {
+        Collection::try_from_ints(
+            dir,
+            self.int_iter(cache).filter(|entry| match entry {
+                Ok(entry) => pred(*entry),
+                Err(_) => true,
+            }),
+            page_size,
+        )
+    }
+
+    /// Applies `f` to each pair of entries of this and an equally sized
+    /// `other` int collection and writes the results to `dir` as a new
+    /// collection with pages of `page_size` entries, holding at most one
+    /// page in memory. Null propagation is up to `f`. Fails with
+    /// `InvalidInput` if the sizes differ.
+    pub fn zip_map_int<F>(
+        &self,
+        other: &Collection,
+        dir: &Path,
+        cache: &mut PageCache,
+        f: F,
+        page_size: usize,
+    ) -> io::Result<Collection>
+    where
+        F: Fn(Option<i64>, Option<i64>) -> Option<i64>,
+    {
+        Collection::try_from_ints(
+            dir,
+            self.zip_int_iter(other, cache)?
+                .map(|entry| entry.map(|(left, right)| f(left, right))),
+            page_size,
+        )
+    }
+
+    /// Writes the first non-null entry of each row across the equally sized
+    /// int `collections` to `dir` as a new collection with pages of
+    /// `page_size` entries, like SQL's `COALESCE`. Rows null in every input
+    /// stay null. Fails with `InvalidInput` if the sizes differ.
+    pub fn coalesce_int(
+        collections: &[&Collection],
+        dir: &Path,
+        cache: &mut PageCache,
+        page_size: usize,
+    ) -> io::Result<Collection> {
+        let size = collections.first().map_or(0, |collection| collection.size);
+        assert!(collections
+            .iter()
+            .all(|collection| collection.typ == Type::Int));
+        if let Some(other) = collections
+            .iter()
+            .find(|collection| collection.size != size)
+        {
+            return Err(size_mismatch(size, other.size));
+        }
+
+        Collection::try_from_ints(
+            dir,
+            (0..size).map(|idx| {
+                for collection in collections {
+                    if let Some(value) = collection.get_int(cache, idx)? {
+                        return Ok(Some(value));
+                    }
+                }
+                Ok(None)
+            }),
+            page_size,
+        )
+    }
+
+    /// Writes the running total of the non-null entries to `dir` as a new
+    /// collection with pages of `page_size` entries, holding at most one
+    /// page in memory. Each row holds the sum of every entry up to and
+    /// including it, carried across pages; `nulls` decides what null rows
+    /// hold. The total wraps on overflow.
+    pub fn cumsum_int(
+        &self,
+        dir: &Path,
+        cache: &mut PageCache,
+        page_size: usize,
+        nulls: NullPolicy,
+    ) -> io::Result<Collection> {
+        assert!(self.typ == Type::Int);
+        let mut total = 0i64;
+        Collection::try_from_ints(
+            dir,
+            self.int_iter(cache).map(|entry| match (entry?, nulls) {
+                (Some(value), _) => {
+                    total = total.wrapping_add(value);
+                    Ok(Some(total))
+                }
+                (None, NullPolicy::AsZero) => Ok(Some(total)),
+                (None, NullPolicy::Propagate) => Ok(None),
+            }),
+            page_size,
+        )
+    }
+
+    /// Writes the element-wise AND of this and an equally sized `other`
+    /// bool collection to `dir` as a new collection with pages of
+    /// `page_size` entries. Nulls follow Kleene logic: false and null is
+    /// false, true and null is null. Fails with `InvalidInput` if the sizes
+    /// differ.
+    pub fn and_bool(
+        &self,
+        other: &Collection,
+        dir: &Path,
+        cache: &mut PageCache,
+        page_size: usize,
+    ) -> io::Result<Collection> {
+        self.zip_bool(other, dir, cache, page_size, |left, right| {
+            match (left, right) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            }
+        })
+    }
+
+    /// Like `and_bool`, for OR: true or null is true, false or null is null.
+    pub fn or_bool(
+        &self,
+        other: &Collection,
+        dir: &Path,
+        cache: &mut PageCache,
+        page_size: usize,
+    ) -> io::Result<Collection> {
+        self.zip_bool(other, dir, cache, page_size, |left, right| {
+            match (left, right) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            }
+        })
+    }
+
+    /// Writes the entries of the int collection in ascending order to `dir`
+    /// as a new collection with pages of `page_size` entries, nulls last.
+    /// At most `page_size` values are held in memory: sorted runs of that
+    /// length are spilled to `work_dir` and merged. The runs are deleted
+    /// whether or not the sort succeeds.
+    pub fn sort_int(
+        &self,
+        dir: &Path,
+        cache: &mut PageCache,
+        page_size: usize,
+        work_dir: &WorkDir,
+    ) -> io::Result<Collection> {
+        assert!(self.typ == Type::Int);
+        assert!(page_size > 0);
+        let mut spill = SpillFiles::default();
+        let mut run_paths = vec![];
+        let mut run = Vec::with_capacity(page_size);
+        let mut nulls = 0;
+
+        let mut write_run = |run: &mut Vec<i64>| -> io::Result<()> {
+            run.sort_unstable();
+            let path = spill.add(
+                work_dir
+                    .resolve(dir)
+                    .join(format!("{}.run.tmp", Uuid::new_v4())),
+            );
+            let mut file = BufWriter::new(File::create(&path)?);
+            for value in run.drain(..) {
+                file.write_i64::<byteorder::LittleEndian>(value)?;
+            }
+            file.into_inner().map_err(|err| err.into_error())?;
+            run_paths.push(path);
+            Ok(())
+        };
+        for entry in self.int_iter(cache) {
+            match entry? {
+                Some(value) => run.push(value),
+                None => nulls += 1,
+            }
+            if run.len() == page_size {
+                write_run(&mut run)?;
+            }
+        }
+        if !run.is_empty() {
+            write_run(&mut run)?;
+        }
+
+        let mut runs = run_paths
+            .iter()
+            .map(|path| {
+                Ok((
+                    BufReader::new(File::open(path)?),
+                    fs::metadata(path)?.len() / 8,
+                ))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut heap = BinaryHeap::new();
+        for (run_idx, (reader, remaining)) in runs.iter_mut().enumerate() {
+            *remaining -= 1;
+            heap.push(Reverse((
+                reader.read_i64::<byteorder::LittleEndian>()?,
+                run_idx,
+            )));
+        }
+
+        let mut sorted = Collection::empty(Type::Int);
+        let mut page = Vec::with_capacity(page_size);
+        loop {
+            let entry = match heap.pop() {
+                Some(Reverse((value, run_idx))) => {
+                    let (reader, remaining) = &mut runs[run_idx];
+                    if *remaining > 0 {
+                        *remaining -= 1;
+                        heap.push(Reverse((
+                            reader.read_i64::<byteorder::LittleEndian>()?,
+                            run_idx,
+                        )));
+                    }
+                    Some(value)
+                }
+                None if nulls > 0 => {
+                    nulls -= 1;
+                    None
+                }
+                None => break,
+            };
+            page.push(entry);
+            if page.len() == page_size {
+                sorted.append_page(dir, PageData::from_ints(&page)?)?;
+                page.clear();
+            }
+        }
+        if !page.is_empty() {
+            sorted.append_page(dir, PageData::from_ints(&page)?)?;
+        }
+        Ok(sorted)
+    }
+
+    /// Writes `data` as a new page in `dir` and appends it after the last
+    /// page of the collection.
+    pub fn append_page(&mut self, dir: &Path, data: PageData) -> io::Result<PageMeta> {
+        self.append_page_with_id(dir, Uuid::new_v4(), data)
+    }
+
+    /// Like `append_page`, but the new page gets the caller-chosen `page_id`.
+    pub fn append_page_with_id(
+        &mut self,
+        dir: &Path,
+        page_id: Uuid,
+        data: PageData,
+    ) -> io::Result<PageMeta> {
+        self.check_unsealed()?;
+        if data.typ() != self.typ {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Cannot append {:?} page to {:?} collection",
+                    data.typ(),
+                    self.typ
+                ),
+            ));
+        }
+
+        let page_idx = self.page_metas.len();
+        let path = self.new_page_path(dir);
+        let page = Page::new(
+            &PageMeta::with_id(page_id, self.typ, &path, self.size, data.len()),
+            self.encode(data)?,
+        )?;
+        self.write_page(&page)?;
+
+        let mut meta = page.meta().clone();
+        meta.stats.match_counts = self.match_counts(&page)?;
+        match (&mut self.int_bloom, &meta.stats.int_bloom) {
+            (Some(bloom), Some(page_bloom)) => bloom.union(page_bloom),
+            _ => self.int_bloom = None,
+        }
+        self.starts.push((self.size, (self.id, page_idx)));
+        self.size += meta.size;
+        self.page_metas.insert((self.id, page_idx), meta.clone());
+        self.version += 1;
+        Ok(meta)
+    }
+
+    /// Replaces entry `idx` of the int collection with `value` by writing a
+    /// new file for its page. The old file is moved to the history
+    /// subdirectory of `dir` and stays in the page's history, so
+    /// `get_int_at` can still read the entry as of earlier versions, until
+    /// `prune_history` deletes it. Like `coalesce_null_pages`, the pages are
+    /// renumbered under a new collection id so a cached copy of the old page
+    /// is never returned.
+    pub fn overwrite_int(
+        &mut self,
+        dir: &Path,
+        cache: &mut PageCache,
+        idx: usize,
+        value: Option<i64>,
+    ) -> io::Result<()> {
+        assert!(self.typ == Type::Int);
+        self.check_unsealed()?;
+        let (key, start) = self.locate(idx).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Cannot overwrite entry {} of {}", idx, self.size),
+            )
+        })?;
+        let meta = self.page_metas[&key].clone();
+        let page = self.load_page(cache, &key, &meta)?;
+        let mut values = (0..meta.size)
+            .map(|page_idx| page.get_int(page_idx))
+            .collect::<crate::Result<Vec<_>>>()?;
+        values[idx - start] = value;
+
+        let version = self.version + 1;
+        let path = self.new_page_path(dir);
+        let page = Page::new(
+            &PageMeta::new(self.typ, &path, meta.offset, meta.size),
+            self.encode(PageData::from_ints(&values)?)?,
+        )?;
+        self.write_page(&page)?;
+
+        let history_dir = dir.join(HISTORY_DIR);
+        fs::create_dir_all(&history_dir)?;
+        let old_path = match meta.path.file_name() {
+            Some(name) => history_dir.join(name),
+            None => history_dir.join(meta.id.to_string()),
+        };
+        fs::rename(&meta.path, &old_path)?;
+
+        let mut new_meta = page.meta().clone();
+        new_meta.stats.match_counts = self.match_counts(&page)?;
+        new_meta.history = meta.history;
+        new_meta.history.push(PageVersion {
+            id: meta.id,
+            path: old_path,
+            checksum: meta.checksum,
+            replaced_at: version,
+        });
+        if let (Some(bloom), Some(page_bloom)) = (&mut self.int_bloom, &new_meta.stats.int_bloom) {
+            bloom.union(page_bloom);
+        }
+
+        let id = Uuid::new_v4();
+        self.page_metas = mem::take(&mut self.page_metas)
+            .into_iter()
+            .map(|((_, page_idx), meta)| {
+                let meta = if page_idx == key.1 {
+                    new_meta.clone()
+                } else {
+                    meta
+                };
+                ((id, page_idx), meta)
+            })
+            .collect();
+        self.id = id;
+        self.version = version;
+        self.reindex();
+        Ok(())
+    }
+
+    /// Deletes the page files replaced more than `retention` versions ago.
+    /// Afterwards `get_int_at` only sees the past of the last `retention`
+    /// versions. Returns the number of files deleted.
+    pub fn prune_history(&mut self, retention: u64) -> io::Result<usize> {
+        self.check_unsealed()?;
+        let horizon = self.version.saturating_sub(retention);
+        let mut pruned = vec![];
+        for meta in self.page_metas.values_mut() {
+            meta.history.retain(|old| {
+                if old.replaced_at <= horizon {
+                    pruned.push(old.path.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        for path in &pruned {
+            fs::remove_file(path)?;
+        }
+        Ok(pruned.len())
+    }
+
+    /// Merges every run of adjacent all-null pages into a single all-null
+    /// page and deletes the merged page files along with their history.
+    /// Pages are found from their null counts, without reading them.
+    /// Returns the number of pages removed. The remaining pages are
+    /// renumbered under a new collection id, so stale cache entries can
+    /// never be mistaken for them.
+    pub fn coalesce_null_pages(&mut self, dir: &Path) -> io::Result<usize> {
+        self.check_unsealed()?;
+        let mut runs: Vec<(bool, Vec<PageMeta>)> = vec![];
+        for meta in self.page_metas.values() {
+            let all_null = meta.size > 0 && meta.null_count() == meta.size;
+            match runs.last_mut() {
+                Some((true, run)) if all_null => run.push(meta.clone()),
+                _ => runs.push((all_null, vec![meta.clone()])),
+            }
+        }
+        if runs.len() == self.page_metas.len() {
+            return Ok(0);
+        }
+
+        let id = Uuid::new_v4();
+        let mut page_metas = BTreeMap::new();
+        let mut merged_paths = vec![];
+        let mut offset = 0;
+
+        for (all_null, run) in runs {
+            let meta = if all_null && run.len() > 1 {
+                let size = run.iter().map(|meta| meta.size).sum();
+                let path = self.new_page_path(dir);
+                let page = Page::new(
+                    &PageMeta::new(self.typ, &path, offset, size),
+                    PageData::all_null(self.typ, size),
+                )?;
+                self.write_page(&page)?;
+                for meta in run {
+                    merged_paths.push(meta.path);
+                    merged_paths.extend(meta.history.into_iter().map(|old| old.path));
+                }
+                let mut meta = page.meta().clone();
+                meta.stats.match_counts = self.match_counts(&page)?;
+                meta
+            } else {
+                run.into_iter().next().unwrap()
+            };
+            offset += meta.size;
+            page_metas.insert((id, page_metas.len()), meta);
+        }
+
+        let removed = self.page_metas.len() - page_metas.len();
+        self.id = id;
+        self.page_metas = page_metas;
+        self.reindex();
+        self.version += 1;
+
+        for path in merged_paths {
+            fs::remove_file(path)?;
+        }
+        Ok(removed)
+    }
+
+    /// Keeps only the first `n` entries. Pages past `n` are deleted along
+    /// with their history, and the page holding entry `n` is rewritten to
+    /// `dir` with just its leading entries. The pages are renumbered under a
+    /// new collection id so the cached boundary page is never returned. Does
+    /// nothing if the collection holds at most `n` entries.
+    pub fn truncate(&mut self, dir: &Path, n: usize) -> io::Result<()> {
+        self.check_unsealed()?;
+        if n >= self.size {
+            return Ok(());
+        }
+
+        let id = Uuid::new_v4();
+        let mut page_metas = BTreeMap::new();
+        let mut removed_paths = vec![];
+        let mut offset = 0;
+
+        for meta in mem::take(&mut self.page_metas).into_values() {
+            if offset >= n {
+                removed_paths.push(meta.path);
+                removed_paths.extend(meta.history.into_iter().map(|old| old.path));
+                continue;
+            }
+
+            let kept = n - offset;
+            let meta = if kept < meta.size {
+                let page = PageReader::read(&meta)?;
+                let data = match self.typ {
+                    Type::Bool => PageData::from_bools(
+                        &(0..kept)
+                            .map(|idx| page.get_bool(idx))
+                            .collect::<crate::Result<Vec<_>>>()?,
+                    )?,
+                    Type::Int => PageData::from_ints(
+                        &(0..kept)
+                            .map(|idx| page.get_int(idx))
+                            .collect::<crate::Result<Vec<_>>>()?,
+                    )?,
+                    Type::Int32 => PageData::from_i32(
+                        &(0..kept)
+                            .map(|idx| page.get_i32(idx))
+                            .collect::<crate::Result<Vec<_>>>()?,
+                    )?,
+                    Type::Timestamp => PageData::from_timestamps(
+                        &(0..kept)
+                            .map(|idx| page.get_timestamp(idx))
+                            .collect::<crate::Result<Vec<_>>>()?,
+                    )?,
+                    Type::UInt => PageData::from_u64(
+                        &(0..kept)
+                            .map(|idx| page.get_u64(idx))
+                            .collect::<crate::Result<Vec<_>>>()?,
+                    )?,
+                    Type::Float => PageData::from_floats(
+                        &(0..kept)
+                            .map(|idx| page.get_float(idx))
+                            .collect::<crate::Result<Vec<_>>>()?,
+                    )?,
+                    Type::Float32 => PageData::from_f32(
+                        &(0..kept)
+                            .map(|idx| page.get_f32(idx))
+                            .collect::<crate::Result<Vec<_>>>()?,
+                    )?,
+                    Type::String => PageData::from_strings(
+                        &(0..kept)
+                            .map(|idx| page.get_str(idx))
+                            .collect::<crate::Result<Vec<_>>>()?,
+                    )?,
+                    Type::Binary => PageData::from_binaries(
+                        &(0..kept)
+                            .map(|idx| page.get_binary(idx))
+                            .collect::<crate::Result<Vec<_>>>()?,
+                    )?,
+                    Type::Decimal => PageData::from_decimals(
+                        &(0..kept)
+                            .map(|idx| Ok(page.get_decimal(idx)?.map(|decimal| decimal.value)))
+                            .collect::<crate::Result<Vec<_>>>()?,
+                        page.data().scale(),
+                    )?,
+                };
+                let path = self.new_page_path(dir);
+                let rewritten = Page::new(&PageMeta::new(self.typ, &path, offset, kept), data)?;
+                self.write_page(&rewritten)?;
+                removed_paths.push(meta.path);
+
+                let mut rewritten_meta = rewritten.meta().clone();
+                rewritten_meta.stats.match_counts = self.match_counts(&rewritten)?;
+                rewritten_meta.history = meta.history;
+                rewritten_meta
+            } else {
+                meta
+            };
+            offset += meta.size;
+            page_metas.insert((id, page_metas.len()), meta);
+        }
+
+        self.id = id;
+        self.page_metas = page_metas;
+        self.reindex();
+        self.size = n;
+        self.int_bloom = union_int_blooms(self.typ, self.page_metas.values());
+        self.version += 1;
+
+        for path in removed_paths {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Folds the page checksums stored in the metadata into a single hash
+    /// that changes whenever any page changes. No page is loaded. Pages
+    /// without a stored checksum contribute their id instead.
+    pub fn content_hash(&self) -> u64 {
+        self.page_metas
+            .values()
+            .fold(FNV_OFFSET_BASIS, |hash, meta| {
+                let hash = fnv1a(hash, &(meta.size as u64).to_le_bytes());
+                match meta.checksum {
+                    Some(checksum) => fnv1a(hash, &checksum.to_le_bytes()),
+                    None => fnv1a(hash, meta.id.as_bytes()),
+                }
+            })
+    }
+
+    /// Measures the page files of the collection. Only file sizes and the
+    /// sections before each page's compressed values are read.
+    pub fn footprint(&self) -> io::Result<Footprint> {
+        let mut footprint = Footprint {
+            total: 0,
+            overhead: 0,
+            payload: 0,
+            pages: self.page_metas.len(),
+            size: self.size,
+        };
+        for meta in self.page_metas.values() {
+            let len = fs::metadata(&meta.path)?.len();
+            let overhead = PageReader::overhead_len(meta)?;
+            footprint.total += len;
+            footprint.overhead += overhead;
+            footprint.payload += len - overhead;
+        }
+        Ok(footprint)
+    }
+
+    /// Returns the pages whose size differs from the most common page size
+    /// in the collection, which marks collections worth repartitioning.
+    pub fn audit_page_sizes(&self) -> Vec<(PageKey, usize)> {
+        let mut counts = HashMap::new();
+        for meta in self.page_metas.values() {
+            *counts.entry(meta.size).or_insert(0) += 1;
+        }
+        let modal_size = match counts
+            .into_iter()
+            .max_by_key(|(size, count)| (*count, *size))
+        {
+            Some((size, _)) => size,
+            None => return vec![],
+        };
+
+        self.page_metas
+            .iter()
+            .filter(|(_, meta)| meta.size != modal_size)
+            .map(|(key, meta)| (*key, meta.size))
+            .collect()
+    }
+
+    /// Whether every page's stored offset starts where the previous page
+    /// ends, with the first page starting at zero.
+    pub fn has_contiguous_offsets(&self) -> bool {
+        self.page_starts()
+            .iter()
+            .all(|(start, key)| self.page_metas[key].offset == *start)
+    }
+
+    /// Checks from metadata alone that each page's stored offset is where
+    /// the previous page ends, starting at zero, and that the pages hold
+    /// exactly the collection's entries.
+    pub fn check_continuity(&self) -> Result<(), ContinuityError> {
+        let mut expected = 0;
+        for (key, meta) in self.page_metas.iter() {
+            if meta.offset > expected {
+                return Err(ContinuityError::Gap {
+                    key: *key,
+                    expected,
+                    offset: meta.offset,
+                });
+            }
+            if meta.offset < expected {
+                return Err(ContinuityError::Overlap {
+                    key: *key,
+                    expected,
+                    offset: meta.offset,
+                });
+            }
+            expected += meta.size;
+        }
+        if expected != self.size {
+            return Err(ContinuityError::SizeMismatch {
+                pages: expected,
+                size: self.size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Describes every page as a JSON object with its key, type, size,
+    /// logical offset, null flag and value bound, for use by external query
+    /// planners. Built from metadata only.
+    pub fn stats_json(&self) -> String {
+        let mut out = String::from("[");
+        for (page_idx, (offset, key)) in self.page_starts().iter().enumerate() {
+            let meta = &self.page_metas[key];
+            if page_idx > 0 {
+                out.push(',');
+            }
+            write!(
+                out,
+                "{{\"key\":[\"{}\",{}],\"typ\":\"{:?}\",\"size\":{},",
+                key.0, key.1, meta.typ, meta.size
+            )
+            .unwrap();
+            write!(
+                out,
+                "\"offset\":{},\"contains_nulls\":{},\"bound\":",
+                offset, meta.stats.contains_nulls
+            )
+            .unwrap();
+
+            let stats = &meta.stats;
+            match (
+                meta.typ,
+                &stats.int_bound,
+                &stats.float_bound,
+                &stats.string_bound,
+            ) {
+                (Type::Int | Type::Int32 | Type::Timestamp, Some(bound), _, _) => {
+                    write!(out, "{{\"min\":{},\"max\":{}}}", bound.min, bound.max).unwrap()
+                }
+                (Type::UInt, _, _, _) => match &stats.uint_bound {
+                    Some(bound) => {
+                        write!(out, "{{\"min\":{},\"max\":{}}}", bound.min, bound.max).unwrap()
+                    }
+                    None => out.push_str("null"),
+                },
+                (Type::Float | Type::Float32, _, Some(bound), _) => {
+                    out.push_str("{\"min\":");
+                    json::push_float(&mut out, bound.min);
+                    out.push_str(",\"max\":");
+                    json::push_float(&mut out, bound.max);
+                    out.push('}');
+                }
+                (Type::String, _, _, Some(bound)) => {
+                    out.push_str("{\"min\":");
+                    json::push_str(&mut out, &bound.min);
+                    out.push_str(",\"max\":");
+                    json::push_str(&mut out, &bound.max);
+                    out.push('}');
+                }
+                _ => out.push_str("null"),
+            }
+            out.push('}');
+        }
+        out.push(']');
+        out
+    }
+
+    /// ORs the null bitmaps of equally sized collections, marking every row
+    /// that is null in at least one of them. Pages already in `cache` are
+    /// used as they are, and only the null section of any other page is
+    /// read.
+    pub fn union_nulls(
+        collections: &[&Collection],
+        cache: &mut PageCache,
+    ) -> crate::Result<BitVec<bv::LittleEndian, u8>> {
+        let size = collections.first().map_or(0, |collection| collection.size);
+        assert!(collections.iter().all(|collection| collection.size == size));
+
+        let mut nulls = BitVec::new();
+        nulls.resize(size, false);
+
+        for collection in collections {
+            let mut offset = 0;
+            for (key, meta) in collection.page_metas.iter() {
+                if let Some(page) = cache.peek_in(collection.namespace, key) {
+                    for idx in (0..meta.size).filter(|&idx| page.is_null(idx)) {
+                        nulls.set(offset + idx, true);
+                    }
+                } else {
+                    let page_nulls = PageReader::read_nulls(meta)?;
+                    for (idx, null) in page_nulls.iter().enumerate() {
+                        if null {
+                            nulls.set(offset + idx, true);
+                        }
+                    }
+                }
+                offset += meta.size;
+            }
+        }
+        Ok(nulls)
+    }
+
+    /// The key of the page holding entry `idx` and the entry's index within
+    /// it, without loading any page. Lets a caller prefetch a page before
+    /// reading from it.
+    pub fn page_of(&self, idx: usize) -> Option<(PageKey, usize)> {
+        self.locate(idx).map(|(key, start)| (key, idx - start))
+    }
+
+    /// Maps row indices to the keys of the pages holding them, without
+    /// loading any page. Indices past the end of the collection are ignored.
+    pub fn pages_for_indices(&self, indices: &[usize]) -> BTreeSet<PageKey> {
+        let starts = self.page_starts();
+        indices
+            .iter()
+            .filter(|idx| **idx < self.size)
+            .map(|idx| {
+                let page_idx = starts.partition_point(|(start, _)| start <= idx) - 1;
+                starts[page_idx].1
+            })
+            .collect()
+    }
+
+    /// Sliding window extremes kept in a monotonic deque: a new value evicts
+    /// every older value it `dominates`, so the front is the extreme of the
+    /// window.
+    fn rolling_int<'a, F>(
+        &'a self,
+        cache: &'a mut PageCache,
+        window: usize,
+        dominates: F,
+    ) -> impl Iterator<Item = crate::Result<Option<i64>>> + 'a
+    where
+        F: Fn(i64, i64) -> bool + 'a,
+    {
+        assert!(window > 0);
+        let mut deque: VecDeque<(usize, i64)> = VecDeque::new();
+        self.int_iter(cache).enumerate().map(move |(idx, entry)| {
+            if let Some(value) = entry? {
+                while deque
+                    .back()
+                    .is_some_and(|(_, back)| dominates(value, *back))
+                {
+                    deque.pop_back();
+                }
+                deque.push_back((idx, value));
+            }
+            while deque
+                .front()
+                .is_some_and(|(front, _)| front + window <= idx)
+            {
+                deque.pop_front();
+            }
+            Ok(deque.front().map(|(_, value)| *value))
+        })
+    }
+
+    /// The key and first index of the page holding entry `idx`.
+    fn locate(&self, idx: usize) -> Option<(PageKey, usize)> {
+        if idx >= self.size {
+            return None;
+        }
+        let starts = self.page_starts();
+        let page_idx = starts.partition_point(|(start, _)| *start <= idx) - 1;
+        Some((starts[page_idx].1, starts[page_idx].0))
+    }
+
+    /// Writes `page` with the compression and offsets set for this
+    /// collection.
+    fn write_page(&self, page: &Page) -> io::Result<()> {
+        if self.fixed_offsets {
+            PageWriter::write_fixed_offsets(page, self.compression)
+        } else {
+            PageWriter::write(page, self.compression)
+        }
+    }
+
+    fn load_page<'a>(
+        &self,
+        cache: &'a mut PageCache,
+        key: &PageKey,
+        meta: &PageMeta,
+    ) -> crate::Result<&'a Page> {
+        Ok(cache.get_in(self.namespace, key, meta)?)
+    }
+
+    fn zip_bool<F>(
+        &self,
+        other: &Collection,
+        dir: &Path,
+        cache: &mut PageCache,
+        page_size: usize,
+        f: F,
+    ) -> io::Result<Collection>
+    where
+        F: Fn(Option<bool>, Option<bool>) -> Option<bool>,
+    {
+        assert!(self.typ == Type::Bool && other.typ == Type::Bool);
+        if self.size != other.size {
+            return Err(size_mismatch(self.size, other.size));
+        }
+        Collection::try_from_bools(
+            dir,
+            (0..self.size)
+                .map(|idx| Ok(f(self.get_bool(cache, idx)?, other.get_bool(cache, idx)?))),
+            page_size,
+        )
+    }
+
+    /// `data` in the encoding its page is written with.
+    fn encode(&self, data: PageData) -> io::Result<PageData> {
+        if self.auto_encoding {
+            data.with_recommended_encoding()
+        } else {
+            Ok(data)
+        }
+    }
+
+    fn match_counts(&self, page: &Page) -> crate::Result<BTreeMap<String, usize>> {
+        self.count_predicates
+            .iter()
+            .map(|(name, predicate)| {
+                let mut count = 0;
+                for idx in 0..page.meta().size {
+                    if predicate(&page.get_value(idx)?) {
+                        count += 1;
+                    }
+                }
+                Ok((name.clone(), count))
+            })
+            .collect()
+    }
+
+    fn check_unsealed(&self) -> io::Result<()> {
+        if self.sealed {
+            return Err(Error::Sealed(self.id).into());
+        }
+        Ok(())
+    }
+
+    fn new_page_path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}_{}", self.id, Uuid::new_v4()))
+    }
+
+    fn page_starts(&self) -> &[(usize, PageKey)] {
+        &self.starts
+    }
+
+    fn reindex(&mut self) {
+        let mut offset = 0;
+        self.starts = self
+            .page_metas
+            .iter()
+            .map(|(key, meta)| {
+                let start = offset;
+                offset += meta.size;
+                (start, *key)
+            })
+            .collect();
+    }
+
+    /// The page holding entry `idx` and the index of its first entry,
+    /// summed from the sizes of the pages before it rather than taken from
+    /// the page's stored offset, which pages built by hand may get wrong.
+    /// Fails for an index past the end or a page that fails to load.
+    fn find_page<'a>(
+        &self,
+        cache: &'a mut PageCache,
+        idx: usize,
+    ) -> crate::Result<(&'a Page, usize)> {
+        let (key, start) = self.locate(idx).ok_or(Error::OutOfBounds {
+            index: idx,
+            size: self.size,
+        })?;
+        let page = cache.get_in(self.namespace, &key, &self.page_metas[&key])?;
+        Ok((page, start))
+    }
+
+    /// Like `find_page`, for a cache shared with other threads.
+    fn find_shared_page(
+        &self,
+        cache: &SharedPageCache,
+        idx: usize,
+    ) -> crate::Result<(Arc<Page>, usize)> {
+        let (key, start) = self.locate(idx).ok_or(Error::OutOfBounds {
+            index: idx,
+            size: self.size,
+        })?;
+        let page = cache.get_in(self.namespace, &key, &self.page_metas[&key])?;
+        Ok((page, start))
+    }
+}
+
+pub struct CollectionBoolIter<'a> {
+    idx: usize,
+    cache: &'a mut PageCache,
+    collection: &'a Collection,
+}
+
+impl<'a> CollectionBoolIter<'a> {
+    fn new(cache: &'a mut PageCache, collection: &'a Collection) -> Self {
+        CollectionBoolIter {
+            idx: 0,
+            cache,
+            collection,
+        }
+    }
+}
+
+impl Iterator for CollectionBoolIter<'_> {
+    type Item = crate::Result<Option<bool>>;
+
+    fn next(&mut self) -> Option<crate::Result<Option<bool>>> {
+        if self.idx == self.collection.size {
+            return None;
+        }
+
+        let entry = self.collection.get_bool(self.cache, self.idx);
+        self.idx += 1;
+        Some(entry)
+    }
+}
+
+pub struct CollectionIntIter<'a> {
+    idx: usize,
+    cache: &'a mut PageCache,
+    collection: &'a Collection,
+}
+
+impl<'a> CollectionIntIter<'a> {
+    fn new(cache: &'a mut PageCache, collection: &'a Collection) -> Self {
+        CollectionIntIter {
+            idx: 0,
+            cache,
+            collection,
+        }
+    }
+}
+
+impl Iterator for CollectionIntIter<'_> {
+    type Item = crate::Result<Option<i64>>;
+
+    fn next(&mut self) -> Option<crate::Result<Option<i64>>> {
+        if self.idx == self.collection.size {
+            return None;
+        }
+
+        let entry = self.collection.get_int(self.cache, self.idx);
+        self.idx += 1;
+        Some(entry)
+    }
+}
+
+pub struct CollectionIntZipIter<'a> {
+    idx: usize,
+    cache: &'a mut PageCache,
+    left: &'a Collection,
+    right: &'a Collection,
+}
+
+impl<'a> CollectionIntZipIter<'a> {
+    fn new(
+        cache: &'a mut PageCache,
+        left: &'a Collection,
+        right: &'a Collection,
+    ) -> io::Result<Self> {
+        assert!(left.typ == Type::Int && right.typ == Type::Int);
+        if left.size != right.size {
+            return Err(size_mismatch(left.size, right.size));
+        }
+        Ok(CollectionIntZipIter {
+            idx: 0,
+            cache,
+            left,
+            right,
+        })
+    }
+}
+
+impl Iterator for CollectionIntZipIter<'_> {
+    type Item = crate::Result<(Option<i64>, Option<i64>)>;
+
+    fn next(&mut self) -> Option<crate::Result<(Option<i64>, Option<i64>)>> {
+        if self.idx == self.left.size {
+            return None;
+        }
+
+        let left = self.left.get_int(self.cache, self.idx);
+        let right = self.right.get_int(self.cache, self.idx);
+        self.idx += 1;
+        Some(left.and_then(|left| Ok((left, right?))))
+    }
+}
+
+pub struct CollectionI32Iter<'a> {
+    idx: usize,
+    cache: &'a mut PageCache,
+    collection: &'a Collection,
+}
+
+impl<'a> CollectionI32Iter<'a> {
+    fn new(cache: &'a mut PageCache, collection: &'a Collection) -> Self {
+        CollectionI32Iter {
+            idx: 0,
+            cache,
+            collection,
+        }
+    }
+}
+
+impl Iterator for CollectionI32Iter<'_> {
+    type Item = crate::Result<Option<i32>>;
+
+    fn next(&mut self) -> Option<crate::Result<Option<i32>>> {
+        if self.idx == self.collection.size {
+            return None;
+        }
+
+        let entry = self.collection.get_i32(self.cache, self.idx);
+        self.idx += 1;
+        Some(entry)
+    }
+}
+
+pub struct CollectionTimestampIter<'a> {
+    idx: usize,
+    cache: &'a mut PageCache,
+    collection: &'a Collection,
+}
+
+impl<'a> CollectionTimestampIter<'a> {
+    fn new(cache: &'a mut PageCache, collection: &'a Collection) -> Self {
+        CollectionTimestampIter {
+            idx: 0,
+            cache,
+            collection,
+        }
+    }
+}
+
+impl Iterator for CollectionTimestampIter<'_> {
+    type Item = crate::Result<Option<i64>>;
+
+    fn next(&mut self) -> Option<crate::Result<Option<i64>>> {
+        if self.idx == self.collection.size {
+            return None;
+        }
+
+        let entry = self.collection.get_timestamp(self.cache, self.idx);
+        self.idx += 1;
+        Some(entry)
+    }
+}
+
+pub struct CollectionUIntIter<'a> {
+    idx: usize,
+    cache: &'a mut PageCache,
+    collection: &'a Collection,
+}
+
+impl<'a> CollectionUIntIter<'a> {
+    fn new(cache: &'a mut PageCache, collection: &'a Collection) -> Self {
+        CollectionUIntIter {
+            idx: 0,
+            cache,
+            collection,
+        }
+    }
+}
+
+impl Iterator for CollectionUIntIter<'_> {
+    type Item = crate::Result<Option<u64>>;
+
+    fn next(&mut self) -> Option<crate::Result<Option<u64>>> {
+        if self.idx == self.collection.size {
+            return None;
+        }
+
+        let entry = self.collection.get_u64(self.cache, self.idx);
+        self.idx += 1;
+        Some(entry)
+    }
+}
+
+pub struct CollectionFloatIter<'a> {
+    idx: usize,
+    cache: &'a mut PageCache,
+    collection: &'a Collection,
+}
+
+impl<'a> CollectionFloatIter<'a> {
+    fn new(cache: &'a mut PageCache, collection: &'a Collection) -> Self {
+        CollectionFloatIter {
+            idx: 0,
+            cache,
+            collection,
+        }
+    }
+}
+
+impl Iterator for CollectionFloatIter<'_> {
+    type Item = crate::Result<Option<f64>>;
+
+    fn next(&mut self) -> Option<crate::Result<Option<f64>>> {
+        if self.idx == self.collection.size {
+            return None;
+        }
+
+        let entry = self.collection.get_float(self.cache, self.idx);
+        self.idx += 1;
+        Some(entry)
+    }
+}
+
+pub struct CollectionFloat32Iter<'a> {
+    idx: usize,
+    cache: &'a mut PageCache,
+    collection: &'a Collection,
+}
+
+impl<'a> CollectionFloat32Iter<'a> {
+    fn new(cache: &'a mut PageCache, collection: &'a Collection) -> Self {
+        CollectionFloat32Iter {
+            idx: 0,
+            cache,
+            collection,
+        }
+    }
+}
+
+impl Iterator for CollectionFloat32Iter<'_> {
+    type Item = crate::Result<Option<f32>>;
+
+    fn next(&mut self) -> Option<crate::Result<Option<f32>>> {
+        if self.idx == self.collection.size {
+            return None;
+        }
+
+        let entry = self.collection.get_f32(self.cache, self.idx);
+        self.idx += 1;
+        Some(entry)
+    }
+}
+
+pub struct CollectionStringIter<'a> {
+    idx: usize,
+    cache: &'a mut PageCache,
+    collection: &'a Collection,
+}
+
+impl<'a> CollectionStringIter<'a> {
+    fn new(cache: &'a mut PageCache, collection: &'a Collection) -> Self {
+        CollectionStringIter {
+            idx: 0,
+            cache,
+            collection,
+        }
+    }
+}
+
+impl Iterator for CollectionStringIter<'_> {
+    type Item = crate::Result<Option<String>>;
+
+    fn next(&mut self) -> Option<crate::Result<Option<String>>> {
+        if self.idx == self.collection.size {
+            return None;
+        }
+
+        let entry = self.collection.get_string(self.cache, self.idx);
+        self.idx += 1;
+        Some(entry)
+    }
+}
@@ -0,0 +1,149 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BinaryArray, BooleanArray, Decimal128Array, Float32Array, Float64Array, Int32Array,
+    Int64Array, StringArray, TimestampMillisecondArray, UInt64Array,
+};
+use arrow_buffer::{Buffer, NullBuffer, OffsetBuffer, ScalarBuffer};
+use arrow_schema::{DataType, TimeUnit, DECIMAL128_MAX_PRECISION};
+
+use super::Collection;
+use crate::cache::PageCache;
+use crate::error::Result;
+use crate::page::Type;
+
+/// Decimal pages each have their own scale, so a decimal collection only
+/// learns the scale of its Arrow type from its pages in `to_arrow`. This
+/// gives it scale 0.
+impl From<Type> for DataType {
+    fn from(typ: Type) -> DataType {
+        match typ {
+            Type::Bool => DataType::Boolean,
+            Type::Int => DataType::Int64,
+            Type::Int32 => DataType::Int32,
+            Type::UInt => DataType::UInt64,
+            Type::Timestamp => DataType::Timestamp(TimeUnit::Millisecond, None),
+            Type::Float => DataType::Float64,
+            Type::Float32 => DataType::Float32,
+            Type::String => DataType::Utf8,
+            Type::Binary => DataType::Binary,
+            Type::Decimal => DataType::Decimal128(DECIMAL128_MAX_PRECISION, 0),
+        }
+    }
+}
+
+impl Collection {
+    /// Converts the whole collection into a single Arrow array of the
+    /// matching data type, concatenating the values and validity of every
+    /// page.
+    pub fn to_arrow(&self, cache: &mut PageCache) -> Result<ArrayRef> {
+        let mut validity = Vec::with_capacity(self.size);
+        for (key, meta) in self.page_metas.iter() {
+            let page = self.load_page(cache, key, meta)?;
+            validity.extend((0..meta.size).map(|idx| !page.is_null(idx)));
+        }
+        let nulls = Some(NullBuffer::from(validity));
+
+        Ok(match self.typ {
+            Type::Bool => Arc::new(BooleanArray::from(
+                self.bool_iter(cache).collect::<Result<Vec<_>>>()?,
+            )),
+            Type::Int => {
+                let mut values = Vec::with_capacity(self.size);
+                let mut page_values = vec![];
+                for (key, meta) in self.page_metas.iter() {
+                    let page = self.load_page(cache, key, meta)?;
+                    page.data().read_ints(&mut page_values);
+                    values.extend_from_slice(&page_values[..meta.size]);
+                }
+                Arc::new(Int64Array::new(ScalarBuffer::from(values), nulls))
+            }
+            Type::Int32 => {
+                let values = (0..self.size)
+                    .map(|idx| Ok(self.get_i32(cache, idx)?.unwrap_or(0)))
+                    .collect::<Result<Vec<_>>>()?;
+                Arc::new(Int32Array::new(ScalarBuffer::from(values), nulls))
+            }
+            Type::UInt => {
+                let values = (0..self.size)
+                    .map(|idx| Ok(self.get_u64(cache, idx)?.unwrap_or(0)))
+                    .collect::<Result<Vec<_>>>()?;
+                Arc::new(UInt64Array::new(ScalarBuffer::from(values), nulls))
+            }
+            Type::Timestamp => {
+                let values = (0..self.size)
+                    .map(|idx| Ok(self.get_timestamp(cache, idx)?.unwrap_or(0)))
+                    .collect::<Result<Vec<_>>>()?;
+                Arc::new(TimestampMillisecondArray::new(
+                    ScalarBuffer::from(values),
+                    nulls,
+                ))
+            }
+            Type::Float => {
+                let mut values = Vec::with_capacity(self.size);
+                let mut page_values = vec![];
+                for (key, meta) in self.page_metas.iter() {
+                    let page = self.load_page(cache, key, meta)?;
+                    page.data().read_floats(&mut page_values);
+                    values.extend_from_slice(&page_values[..meta.size]);
+                }
+                Arc::new(Float64Array::new(ScalarBuffer::from(values), nulls))
+            }
+            Type::Float32 => {
+                let values = (0..self.size)
+                    .map(|idx| Ok(self.get_f32(cache, idx)?.unwrap_or(0.0)))
+                    .collect::<Result<Vec<_>>>()?;
+                Arc::new(Float32Array::new(ScalarBuffer::from(values), nulls))
+            }
+            Type::Decimal => {
+                // Pages may differ in scale, so every value is scaled up to
+                // the largest one.
+                let mut scales = Vec::with_capacity(self.page_metas.len());
+                for (key, meta) in self.page_metas.iter() {
+                    scales.push(self.load_page(cache, key, meta)?.data().scale());
+                }
+                let scale = scales.iter().copied().max().unwrap_or(0);
+
+                let mut values = Vec::with_capacity(self.size);
+                for ((key, meta), page_scale) in self.page_metas.iter().zip(scales) {
+                    let page = self.load_page(cache, key, meta)?;
+                    let factor = 10i128.pow((scale - page_scale) as u32);
+                    for idx in 0..meta.size {
+                        values.push(page.get_decimal(idx)?.map_or(0, |decimal| {
+                            decimal
+                                .value
+                                .checked_mul(factor)
+                                .expect("Decimal too large to rescale for Arrow")
+                        }));
+                    }
+                }
+                Arc::new(
+                    Decimal128Array::new(ScalarBuffer::from(values), nulls)
+                        .with_precision_and_scale(DECIMAL128_MAX_PRECISION, scale as i8)
+                        .unwrap(),
+                )
+            }
+            Type::String | Type::Binary => {
+                let mut bytes = vec![];
+                let mut offsets = vec![0];
+                for (key, meta) in self.page_metas.iter() {
+                    let page = self.load_page(cache, key, meta)?;
+                    let (page_bytes, page_offsets) = page.data().string_parts();
+                    let base = bytes.len();
+                    offsets.extend(page_offsets[1..].iter().map(|offset| {
+                        i32::try_from(base + offset)
+                            .expect("String column too large for 32-bit Arrow offsets")
+                    }));
+                    bytes.extend_from_slice(page_bytes);
+                }
+                let offsets = OffsetBuffer::new(ScalarBuffer::from(offsets));
+                if self.typ == Type::String {
+                    Arc::new(StringArray::new(offsets, Buffer::from_vec(bytes), nulls))
+                } else {
+                    Arc::new(BinaryArray::new(offsets, Buffer::from_vec(bytes), nulls))
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use super::Collection;
+use crate::cache::SharedPageCache;
+use crate::error::{Error, Result};
+use crate::page::{Decimal, MappedPage, Page, PageKey, PageReader, Type, Value};
+
+/// A read-only collection for reading from many threads at once. The pages
+/// written uncompressed are mapped into memory up front and read in place,
+/// while the others are decoded into the `SharedPageCache` passed to each
+/// lookup.
+///
+/// Lookups take `&self`, so threads share one collection by reference or
+/// through an `Arc`. A lookup landing on a mapped page takes no lock and
+/// copies nothing but the value it returns: threads reading mapped pages
+/// never wait on each other, nor on threads using the cache. A lookup
+/// landing on any other page locks the cache like
+/// `SharedPageCache::get_in` does.
+pub struct MappedCollection {
+    collection: Collection,
+    /// The pages read in place. Pages missing here are read through the
+    /// cache.
+    mapped: HashMap<PageKey, MappedPage>,
+}
+
+impl Collection {
+    /// Makes this collection read-only and maps its pages that can be read
+    /// in place: those written uncompressed with the `Plain` encoding and,
+    /// for string and binary pages, with `set_fixed_offsets`.
+    pub fn into_mapped(self) -> Result<MappedCollection> {
+        let mut mapped = HashMap::new();
+        for (key, meta) in &self.page_metas {
+            if let Some(page) = PageReader::map_in_place(meta)? {
+                mapped.insert(*key, page);
+            }
+        }
+        Ok(MappedCollection {
+            collection: self,
+            mapped,
+        })
+    }
+}
+
+impl MappedCollection {
+    pub fn collection(&self) -> &Collection {
+        &self.collection
+    }
+
+    pub fn size(&self) -> usize {
+        self.collection.size
+    }
+
+    pub fn typ(&self) -> Type {
+        self.collection.typ
+    }
+
+    /// Number of pages read in place rather than through the cache.
+    pub fn mapped_pages(&self) -> usize {
+        self.mapped.len()
+    }
+
+    pub fn get_bool(&self, cache: &SharedPageCache, idx: usize) -> Result<Option<bool>> {
+        self.get(cache, idx, MappedPage::get_bool, Page::get_bool)
+    }
+
+    pub fn get_int(&self, cache: &SharedPageCache, idx: usize) -> Result<Option<i64>> {
+        self.get(cache, idx, MappedPage::get_int, Page::get_int)
+    }
+
+    pub fn get_i32(&self, cache: &SharedPageCache, idx: usize) -> Result<Option<i32>> {
+        self.get(cache, idx, MappedPage::get_i32, Page::get_i32)
+    }
+
+    pub fn get_u64(&self, cache: &SharedPageCache, idx: usize) -> Result<Option<u64>> {
+        self.get(cache, idx, MappedPage::get_u64, Page::get_u64)
+    }
+
+    /// Milliseconds since the Unix epoch.
+    pub fn get_timestamp(&self, cache: &SharedPageCache, idx: usize) -> Result<Option<i64>> {
+        self.get(cache, idx, MappedPage::get_timestamp, Page::get_timestamp)
+    }
+
+    pub fn get_float(&self, cache: &SharedPageCache, idx: usize) -> Result<Option<f64>> {
+        self.get(cache, idx, MappedPage::get_float, Page::get_float)
+    }
+
+    pub fn get_f32(&self, cache: &SharedPageCache, idx: usize) -> Result<Option<f32>> {
+        self.get(cache, idx, MappedPage::get_f32, Page::get_f32)
+    }
+
+    pub fn get_decimal(&self, cache: &SharedPageCache, idx: usize) -> Result<Option<Decimal>> {
+        self.get(cache, idx, MappedPage::get_decimal, Page::get_decimal)
+    }
+
+    pub fn get_string(&self, cache: &SharedPageCache, idx: usize) -> Result<Option<String>> {
+        self.get(cache, idx, MappedPage::get_string, Page::get_string)
+    }
+
+    pub fn get_binary(&self, cache: &SharedPageCache, idx: usize) -> Result<Option<Vec<u8>>> {
+        self.get(
+            cache,
+            idx,
+            |page, idx| Ok(page.get_binary(idx)?.map(<[u8]>::to_vec)),
+            |page, idx| Ok(page.get_binary(idx)?.map(<[u8]>::to_vec)),
+        )
+    }
+
+    /// Reads an entry of any type, dispatching on the collection's type.
+    pub fn get_value(&self, cache: &SharedPageCache, idx: usize) -> Result<Value> {
+        self.get(cache, idx, MappedPage::get_value, Page::get_value)
+    }
+
+    /// Reads entry `idx` with `mapped` from its mapped page, or else with
+    /// `cached` from its page in `cache`.
+    fn get<'a, T, M, C>(
+        &'a self,
+        cache: &SharedPageCache,
+        idx: usize,
+        mapped: M,
+        cached: C,
+    ) -> Result<T>
+    where
+        M: FnOnce(&'a MappedPage, usize) -> Result<T>,
+        C: FnOnce(&Page, usize) -> Result<T>,
+    {
+        let (key, start) = self.collection.locate(idx).ok_or(Error::OutOfBounds {
+            index: idx,
+            size: self.collection.size,
+        })?;
+        match self.mapped.get(&key) {
+            Some(page) => mapped(page, idx - start),
+            None => {
+                let meta = &self.collection.page_metas[&key];
+                let page = cache.get_in(self.collection.namespace, &key, meta)?;
+                cached(&page, idx - start)
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::io;
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use super::Collection;
+use crate::page::{PageData, PageReader, Type};
+
+impl Collection {
+    /// Like `retain_int` and `zip_map_int`, writes `f` of each entry to `dir`
+    /// as a new collection with pages of `page_size` entries, but maps the
+    /// input pages on the rayon thread pool. The output keeps the input
+    /// order. Input pages are read directly rather than through a cache, a
+    /// batch of one page per thread at a time.
+    pub fn par_map_int<F>(&self, dir: &Path, f: F, page_size: usize) -> io::Result<Collection>
+    where
+        F: Fn(Option<i64>) -> Option<i64> + Sync,
+    {
+        assert!(self.typ == Type::Int);
+        assert!(page_size > 0);
+        let metas = self.page_metas.values().collect::<Vec<_>>();
+        let mut output = Collection::empty(Type::Int);
+        let mut page = Vec::with_capacity(page_size);
+
+        for batch in metas.chunks(rayon::current_num_threads()) {
+            let mapped = batch
+                .par_iter()
+                .map(|meta| {
+                    let input = PageReader::read(meta)?;
+                    (0..meta.size)
+                        .map(|idx| Ok(f(input.get_int(idx)?)))
+                        .collect::<io::Result<Vec<_>>>()
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+
+            for value in mapped.into_iter().flatten() {
+                page.push(value);
+                if page.len() == page_size {
+                    output.append_page(dir, PageData::from_ints(&page)?)?;
+                    page.clear();
+                }
+            }
+        }
+        if !page.is_empty() {
+            output.append_page(dir, PageData::from_ints(&page)?)?;
+        }
+        Ok(output)
+    }
+}
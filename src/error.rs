@@ -0,0 +1,98 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::str;
+
+use uuid::Uuid;
+
+use crate::page::Type;
+
+/// Errors of reading pages and their values.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read or written.
+    Io(io::Error),
+    /// A page file is malformed: truncated, failing its checksum or holding
+    /// sections that contradict each other.
+    Corrupt(String),
+    /// A string value of a page is not valid UTF-8.
+    Utf8(str::Utf8Error),
+    /// Values of one type were read as another.
+    TypeMismatch { expected: Type, found: Type },
+    /// An index at or past the end of a page or collection of `size`
+    /// entries.
+    OutOfBounds { index: usize, size: usize },
+    /// A change was attempted on the collection of this id after `seal`.
+    Sealed(Uuid),
+    /// A table has no column of this name.
+    UnknownColumn(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Corrupt(message) => write!(f, "Corrupt page: {}", message),
+            Error::Utf8(err) => write!(f, "String is not UTF-8: {}", err),
+            Error::TypeMismatch { expected, found } => {
+                write!(f, "Expected {:?} values but found {:?}", expected, found)
+            }
+            Error::OutOfBounds { index, size } => {
+                write!(f, "Index {} out of bounds for {} entries", index, size)
+            }
+            Error::Sealed(id) => write!(f, "Collection {} is sealed", id),
+            Error::UnknownColumn(name) => write!(f, "Unknown column {:?}", name),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Utf8(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The page decoders report malformed input as `InvalidData` or, when a
+/// file ends early, `UnexpectedEof`; both become `Corrupt`. An `Error`
+/// carried inside an `io::Error` is unwrapped.
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        if err.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            // Checked above, so neither step can fail.
+            return *err.into_inner().unwrap().downcast::<Error>().unwrap();
+        }
+        match err.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+                Error::Corrupt(err.to_string())
+            }
+            _ => Error::Io(err),
+        }
+    }
+}
+
+impl From<str::Utf8Error> for Error {
+    fn from(err: str::Utf8Error) -> Self {
+        Error::Utf8(err)
+    }
+}
+
+/// Lets functions returning `io::Result` propagate an `Error` with `?`. It
+/// is carried inside the `io::Error` and recovered by converting back.
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            Error::Corrupt(_) | Error::Utf8(_) => io::Error::new(io::ErrorKind::InvalidData, err),
+            Error::TypeMismatch { .. } | Error::OutOfBounds { .. } | Error::UnknownColumn(_) => {
+                io::Error::new(io::ErrorKind::InvalidInput, err)
+            }
+            Error::Sealed(_) => io::Error::new(io::ErrorKind::PermissionDenied, err),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt::Write;
+
+use crate::page::Value;
+
+/// Appends `value` to `out` as a quoted, escaped JSON string.
+pub(crate) fn push_str(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Appends `value` to `out` as a JSON number. JSON has no representation
+/// for NaN or the infinities, so those become `null`.
+pub(crate) fn push_float(out: &mut String, value: f64) {
+    if value.is_finite() {
+        write!(out, "{}", value).unwrap();
+    } else {
+        out.push_str("null");
+    }
+}
+
+/// Appends `value` to `out` as a JSON string of lowercase hex digits.
+pub(crate) fn push_hex(out: &mut String, value: &[u8]) {
+    out.push('"');
+    for byte in value {
+        write!(out, "{:02x}", byte).unwrap();
+    }
+    out.push('"');
+}
+
+/// Appends `value` to `out` as the JSON value of its type.
+pub(crate) fn push_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(value) => write!(out, "{}", value).unwrap(),
+        Value::Int(value) => write!(out, "{}", value).unwrap(),
+        Value::UInt(value) => write!(out, "{}", value).unwrap(),
+        Value::Timestamp(value) => write!(out, "{}", value).unwrap(),
+        Value::Float(value) => push_float(out, *value),
+        Value::String(value) => push_str(out, value),
+        Value::Binary(value) => push_hex(out, value),
+        Value::Decimal(value) => write!(out, "{}", value).unwrap(),
+    }
+}
@@ -0,0 +1,13 @@
+pub mod bloom;
+pub mod builder;
+pub mod cache;
+pub mod collection;
+pub mod error;
+mod json;
+mod manifest;
+pub mod mvcc;
+pub mod page;
+pub mod table;
+pub mod work;
+
+pub use error::{Error, Result};
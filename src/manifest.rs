@@ -0,0 +1,315 @@
+use std::fs::{self, File};
+use std::io;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use uuid::Uuid;
+
+use crate::bloom::BloomFilter;
+use crate::page::{PageMeta, PageReader, PageStats, PageVersion, Type};
+
+/// Name of the manifest file inside a collection directory.
+pub(crate) const MANIFEST: &str = "MANIFEST";
+
+/// Name of the empty file marking a sealed collection directory. It is kept
+/// beside the manifest so the seal survives `Collection::rebuild_manifest`.
+pub(crate) const SEALED: &str = "SEALED";
+
+/// Version of the manifest format. Version 2 added page bloom filters,
+/// version 3 page history, version 4 the sealed flag, version 5 page
+/// match counts and version 6 page stats.
+const FORMAT_VERSION: u8 = 6;
+
+const HEADER_PREFIX: &str = "eadb-manifest ";
+
+/// Starts the lines listing the replaced files of the page above them.
+const HISTORY_PREFIX: &str = "~\t";
+
+/// Starts the lines holding the match counts of the page above them.
+const COUNT_PREFIX: &str = "=\t";
+
+/// The summary line of a manifest, readable without the page list.
+pub(crate) struct Summary {
+    pub typ: Type,
+    pub size: usize,
+    pub page_count: usize,
+    pub version: u64,
+    pub sealed: bool,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn parse<T: std::str::FromStr>(field: Option<&str>, name: &str) -> io::Result<T> {
+    field
+        .and_then(|field| field.parse().ok())
+        .ok_or_else(|| invalid(format!("Invalid manifest {}: {:?}", name, field)))
+}
+
+fn parse_checksum(field: Option<&str>) -> io::Result<Option<u32>> {
+    match field {
+        Some("-") => Ok(None),
+        field => Ok(Some(parse(field, "page checksum")?)),
+    }
+}
+
+fn format_checksum(checksum: Option<u32>) -> String {
+    match checksum {
+        Some(checksum) => checksum.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        hex.push_str(&format!("{:02x}", byte));
+        hex
+    })
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&hex[idx..idx + 2], 16).ok())
+        .collect()
+}
+
+fn parse_bloom(field: Option<&str>) -> io::Result<Option<BloomFilter>> {
+    match field {
+        Some("-") => Ok(None),
+        Some(hex) => from_hex(hex)
+            .and_then(|bytes| BloomFilter::from_bytes(&bytes))
+            .map(Some)
+            .ok_or_else(|| invalid("Invalid manifest bloom filter".to_string())),
+        None => Err(invalid("Missing manifest bloom filter".to_string())),
+    }
+}
+
+/// Parses the stats of a page of type `typ` with `size` entries, stored as
+/// the page file's own stats section in hex.
+fn parse_stats(field: Option<&str>, typ: Type, size: usize) -> io::Result<PageStats> {
+    let bytes = field
+        .and_then(from_hex)
+        .ok_or_else(|| invalid(format!("Invalid manifest page stats: {:?}", field)))?;
+    PageStats::read_from(&mut bytes.as_slice(), typ, size)
+        .map_err(|err| invalid(format!("Invalid manifest page stats: {}", err)))
+}
+
+fn format_stats(stats: &PageStats, typ: Type) -> io::Result<String> {
+    let mut bytes = vec![];
+    stats.write_to(&mut bytes, typ)?;
+    Ok(to_hex(&bytes))
+}
+
+fn parse_type(field: Option<&str>) -> io::Result<Type> {
+    match field {
+        Some("Bool") => Ok(Type::Bool),
+        Some("Int") => Ok(Type::Int),
+        Some("Int32") => Ok(Type::Int32),
+        Some("UInt") => Ok(Type::UInt),
+        Some("Float") => Ok(Type::Float),
+        Some("Float32") => Ok(Type::Float32),
+        Some("Timestamp") => Ok(Type::Timestamp),
+        Some("String") => Ok(Type::String),
+        Some("Binary") => Ok(Type::Binary),
+        Some("Decimal") => Ok(Type::Decimal),
+        _ => Err(invalid(format!("Invalid manifest type: {:?}", field))),
+    }
+}
+
+/// Writes the manifest of `dir` to a temporary file that is renamed into
+/// place, so a crash never leaves a half-written manifest behind. Page paths
+/// inside `dir` are stored relative to it.
+///
+/// The manifest is a text file: a header line, a tab-separated summary line
+/// `type size page_count version sealed`, then one `id offset size checksum
+/// bloom stats file` line per page in order, with the bloom filter and the
+/// page stats section in hex and `-` for a missing checksum or bloom filter.
+/// Each page line is followed by a `~ id replaced_at checksum file` line per
+/// file in its history and a `= name count` line per predicate counted in
+/// the page.
+pub(crate) fn write<'a, I>(dir: &Path, summary: &Summary, pages: I) -> io::Result<()>
+where
+    I: IntoIterator<Item = &'a PageMeta>,
+{
+    let tmp_path = dir.join(format!("{}.tmp", MANIFEST));
+    let mut file = BufWriter::new(File::create(&tmp_path)?);
+
+    writeln!(file, "{}{}", HEADER_PREFIX, FORMAT_VERSION)?;
+    writeln!(
+        file,
+        "{:?}\t{}\t{}\t{}\t{}",
+        summary.typ, summary.size, summary.page_count, summary.version, summary.sealed
+    )?;
+    for meta in pages {
+        let bloom = match &meta.stats.int_bloom {
+            Some(bloom) => to_hex(&bloom.to_bytes()),
+            None => "-".to_string(),
+        };
+        let path = meta.path.strip_prefix(dir).unwrap_or(&meta.path);
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            meta.id,
+            meta.offset,
+            meta.size,
+            format_checksum(meta.checksum),
+            bloom,
+            format_stats(&meta.stats, meta.typ)?,
+            path.display()
+        )?;
+        for old in &meta.history {
+            let path = old.path.strip_prefix(dir).unwrap_or(&old.path);
+            writeln!(
+                file,
+                "{}{}\t{}\t{}\t{}",
+                HISTORY_PREFIX,
+                old.id,
+                old.replaced_at,
+                format_checksum(old.checksum),
+                path.display()
+            )?;
+        }
+        for (name, count) in &meta.stats.match_counts {
+            writeln!(file, "{}{}\t{}", COUNT_PREFIX, name, count)?;
+        }
+    }
+
+    file.into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()?;
+    let marker = dir.join(SEALED);
+    if summary.sealed {
+        File::create(marker)?;
+    } else if marker.exists() {
+        fs::remove_file(marker)?;
+    }
+    fs::rename(tmp_path, dir.join(MANIFEST))
+}
+
+/// Reads the summary, returning it with the manifest format version.
+fn read_summary<R: BufRead>(lines: &mut io::Lines<R>) -> io::Result<(Summary, u8)> {
+    let mut next_line = || {
+        lines
+            .next()
+            .unwrap_or_else(|| Err(invalid("Truncated manifest".to_string())))
+    };
+    let format = next_line()?
+        .strip_prefix(HEADER_PREFIX)
+        .and_then(|version| version.parse::<u8>().ok())
+        .filter(|version| (1..=FORMAT_VERSION).contains(version))
+        .ok_or_else(|| invalid("Missing manifest header".to_string()))?;
+
+    let line = next_line()?;
+    let mut fields = line.split('\t');
+    let summary = Summary {
+        typ: parse_type(fields.next())?,
+        size: parse(fields.next(), "size")?,
+        page_count: parse(fields.next(), "page count")?,
+        version: parse(fields.next(), "version")?,
+        sealed: if format >= 4 {
+            parse(fields.next(), "sealed flag")?
+        } else {
+            false
+        },
+    };
+    Ok((summary, format))
+}
+
+/// Reads only the summary line of the manifest in `dir`.
+pub(crate) fn summary(dir: &Path) -> io::Result<Summary> {
+    let file = BufReader::new(File::open(dir.join(MANIFEST))?);
+    Ok(read_summary(&mut file.lines())?.0)
+}
+
+/// Reads the summary and every page of the manifest in `dir`. Manifests
+/// written before format version 6 hold no page stats, so they are rebuilt
+/// by reading each page.
+pub(crate) fn read(dir: &Path) -> io::Result<(Summary, Vec<PageMeta>)> {
+    let file = BufReader::new(File::open(dir.join(MANIFEST))?);
+    let mut lines = file.lines();
+    let (summary, format) = read_summary(&mut lines)?;
+
+    let mut pages: Vec<PageMeta> = Vec::with_capacity(summary.page_count);
+    for line in lines {
+        let line = line?;
+        if let Some(history) = line.strip_prefix(HISTORY_PREFIX) {
+            let mut fields = history.splitn(4, '\t');
+            let old = PageVersion {
+                id: parse(fields.next(), "replaced page id")?,
+                replaced_at: parse(fields.next(), "replaced page version")?,
+                checksum: parse_checksum(fields.next())?,
+                path: match fields.next() {
+                    Some(path) => dir.join(path),
+                    None => return Err(invalid("Missing manifest page path".to_string())),
+                },
+            };
+            match pages.last_mut() {
+                Some(meta) => meta.history.push(old),
+                None => return Err(invalid("Page history before any page".to_string())),
+            }
+            continue;
+        }
+        if let Some(count) = line.strip_prefix(COUNT_PREFIX) {
+            let mut fields = count.split('\t');
+            let name = match fields.next() {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => return Err(invalid("Missing manifest count name".to_string())),
+            };
+            let count = parse(fields.next(), "match count")?;
+            match pages.last_mut() {
+                Some(meta) => meta.stats.match_counts.insert(name, count),
+                None => return Err(invalid("Match count before any page".to_string())),
+            };
+            continue;
+        }
+
+        let field_count = match format {
+            1 => 5,
+            2..=5 => 6,
+            _ => 7,
+        };
+        let mut fields = line.splitn(field_count, '\t');
+        let id: Uuid = parse(fields.next(), "page id")?;
+        let offset = parse(fields.next(), "page offset")?;
+        let size = parse(fields.next(), "page size")?;
+        let checksum = parse_checksum(fields.next())?;
+        let int_bloom = if format >= 2 {
+            parse_bloom(fields.next())?
+        } else {
+            None
+        };
+        let stats = if format >= 6 {
+            Some(parse_stats(fields.next(), summary.typ, size)?)
+        } else {
+            None
+        };
+        let path = match fields.next() {
+            Some(path) => dir.join(path),
+            None => return Err(invalid("Missing manifest page path".to_string())),
+        };
+
+        let mut meta = PageMeta::with_id(id, summary.typ, &path, offset, size);
+        let stats = match stats {
+            Some(stats) => stats,
+            None => PageReader::read(&meta)?.meta().stats.clone(),
+        };
+        meta.checksum = checksum;
+        meta.stats = PageStats { int_bloom, ..stats };
+        pages.push(meta);
+    }
+
+    if pages.len() != summary.page_count {
+        return Err(invalid(format!(
+            "Manifest lists {} pages but expects {}",
+            pages.len(),
+            summary.page_count
+        )));
+    }
+    Ok((summary, pages))
+}
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::collection::Collection;
+use crate::page::{PageData, PageMeta};
+
+struct Shared {
+    collection: Collection,
+    pins: BTreeMap<u64, usize>,
+}
+
+/// Shares a collection between a single appending writer and any number of
+/// readers. Readers pin the current version and keep seeing exactly the
+/// pages that existed at that version while the writer moves on.
+#[derive(Clone)]
+pub struct SharedCollection {
+    shared: Arc<RwLock<Shared>>,
+}
+
+impl SharedCollection {
+    pub fn new(collection: Collection) -> Self {
+        SharedCollection {
+            shared: Arc::new(RwLock::new(Shared {
+                collection,
+                pins: BTreeMap::new(),
+            })),
+        }
+    }
+
+    pub fn version(&self) -> u64 {
+        self.shared.read().unwrap().collection.version()
+    }
+
+    /// Appends a page, publishing it to readers as a new version.
+    pub fn append_page(&self, dir: &Path, data: PageData) -> io::Result<PageMeta> {
+        self.shared
+            .write()
+            .unwrap()
+            .collection
+            .append_page(dir, data)
+    }
+
+    /// Pins the current version. It stays readable until the snapshot is
+    /// dropped.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut shared = self.shared.write().unwrap();
+        let collection = shared.collection.clone();
+        *shared.pins.entry(collection.version()).or_insert(0) += 1;
+        Snapshot {
+            collection,
+            shared: self.clone(),
+        }
+    }
+
+    /// The oldest version still pinned by a reader. Pages belonging to it
+    /// must not be reclaimed.
+    pub fn oldest_pinned_version(&self) -> Option<u64> {
+        self.shared.read().unwrap().pins.keys().next().cloned()
+    }
+
+    fn release(&self, version: u64) {
+        let mut shared = self.shared.write().unwrap();
+        let remaining = {
+            let count = shared.pins.get_mut(&version).unwrap();
+            *count -= 1;
+            *count
+        };
+        if remaining == 0 {
+            shared.pins.remove(&version);
+        }
+    }
+}
+
+/// A reader's pinned view of a `SharedCollection`.
+pub struct Snapshot {
+    collection: Collection,
+    shared: SharedCollection,
+}
+
+impl Snapshot {
+    pub fn version(&self) -> u64 {
+        self.collection.version()
+    }
+
+    pub fn collection(&self) -> &Collection {
+        &self.collection
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        self.shared.release(self.collection.version());
+    }
+}
@@ -1,4 +1,6 @@
-use std::fs::File;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
 use std::io;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
@@ -6,21 +8,422 @@ use std::path::{Path, PathBuf};
 use bitvec::prelude as bv;
 use bitvec::vec::BitVec;
 use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, TimeZone, Utc};
 use log::debug;
+use roaring::RoaringBitmap;
 use uuid::Uuid;
 
-#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+use crate::bloom::BloomFilter;
+use crate::error::{Error, Result};
+
+#[cfg(feature = "memmap2")]
+mod mmap;
+
+#[cfg(feature = "memmap2")]
+pub use self::mmap::MappedPage;
+
+/// Marks the start of every page file written with a format header.
+pub const MAGIC: [u8; 4] = *b"EADB";
+
+pub const FORMAT_VERSION: u8 = 9;
+
+/// Version implied for pages written before the header existed.
+const LEGACY_VERSION: u8 = 0;
+
+/// Bytes of a current page file with nulls that precede its values and do
+/// not depend on its length: the magic number, version, page descriptor,
+/// null encoding, null section size, the stats section without its bound,
+/// a plain value encoding and the compression. Decimal pages add a byte
+/// for their scale.
+pub const PAGE_HEADER_LEN: u64 = 4 + 1 + 33 + 1 + 8 + 10 + 1 + 1;
+
+/// Bytes of the footer ending page files from format version 9: a CRC32 of
+/// every byte before it.
+pub const PAGE_FOOTER_LEN: u64 = 4;
+
+/// Version of the stats section written after the null section.
+const STATS_VERSION: u8 = 1;
+
+/// Bytes of each run of a run-length encoded int page: its end and value.
+const RLE_INT_RUN_LEN: usize = 16;
+
+/// Bytes of each run of a run-length encoded bool page.
+const RLE_BOOL_RUN_LEN: usize = 9;
+
+/// Entries between the checkpoints of a delta encoded page. Reading an
+/// entry decodes at most this many differences.
+pub const DELTA_CHECKPOINT_INTERVAL: usize = 128;
+
+/// Bytes of each checkpoint of a delta encoded page: a value and a position.
+const DELTA_CHECKPOINT_LEN: usize = 16;
+
+/// Longest prefix, in bytes, kept of the strings in a page's string bound.
+pub const STRING_BOUND_LEN: usize = 64;
+
+/// How the null section of a page is stored. Sparse null sets are written
+/// as a roaring bitmap whenever that is smaller than the plain bitmap.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum NullEncoding {
+    Bitmap = 0,
+    Roaring = 1,
+    /// Every entry is null, so neither the null section nor the values are
+    /// stored.
+    AllNull = 2,
+    /// No entry is null, so the null section is left out and only the
+    /// values are stored. Used from format version 8.
+    NoNulls = 3,
+}
+
+impl NullEncoding {
+    fn from_byte(byte: u8) -> io::Result<Self> {
+        match byte {
+            0 => Ok(NullEncoding::Bitmap),
+            1 => Ok(NullEncoding::Roaring),
+            2 => Ok(NullEncoding::AllNull),
+            3 => Ok(NullEncoding::NoNulls),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown null encoding {}", byte),
+            )),
+        }
+    }
+}
+
+/// How the offsets of a string page are stored.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum OffsetEncoding {
+    /// `len() + 1` little-endian u64 offsets. Written by
+    /// `PageWriter::write_fixed_offsets`, so mapped pages can read them in
+    /// place.
+    Absolute = 0,
+    /// The byte length of every string as a LEB128 varint. The offsets are
+    /// rebuilt as the prefix sums of the lengths.
+    Delta = 1,
+    /// The `Delta` varints compressed with raw snappy, behind their
+    /// compressed size as a u64. Used when that is smaller.
+    CompressedDelta = 2,
+}
+
+impl OffsetEncoding {
+    fn from_byte(byte: u8) -> io::Result<Self> {
+        match byte {
+            0 => Ok(OffsetEncoding::Absolute),
+            1 => Ok(OffsetEncoding::Delta),
+            2 => Ok(OffsetEncoding::CompressedDelta),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown offset encoding {}", byte),
+            )),
+        }
+    }
+}
+
+/// How the values of a page are laid out, both on disk and in memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    /// Fixed-width little-endian values, one bitmap for bools and the raw
+    /// bytes for strings.
+    Plain,
+    /// Int values stored as `value - base` in `width` bits each.
+    BitPacked { base: i64, width: u8 },
+    /// Int or bool values stored as `runs` runs of equal values, each the
+    /// little-endian index one past its last entry followed by its value.
+    /// Null entries continue the run before them.
+    Rle { runs: usize },
+    /// Int values stored as `len` bytes: a checkpoint every
+    /// `DELTA_CHECKPOINT_INTERVAL` entries holding the entry's value and the
+    /// position of the rest of its block, then the zigzag varint difference
+    /// of every other entry from the one before it. Null entries repeat the
+    /// value before them.
+    Delta { len: usize },
+    /// Int values stored as a dictionary of `values` distinct little-endian
+    /// values in ascending order, then the index of every entry's value in
+    /// `width` bits each. Null entries hold index zero.
+    Dictionary { values: usize, width: u8 },
+}
+
+impl Encoding {
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        match reader.read_u8()? {
+            0 => Ok(Encoding::Plain),
+            1 => {
+                let base = reader.read_i64::<byteorder::LittleEndian>()?;
+                let width = reader.read_u8()?;
+                if width > 64 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid bit width {}", width),
+                    ));
+                }
+                Ok(Encoding::BitPacked { base, width })
+            }
+            2 => {
+                let runs = reader.read_u64::<byteorder::LittleEndian>()?;
+                Ok(Encoding::Rle {
+                    runs: runs as usize,
+                })
+            }
+            3 => {
+                let len = reader.read_u64::<byteorder::LittleEndian>()?;
+                Ok(Encoding::Delta { len: len as usize })
+            }
+            4 => {
+                let values = reader.read_u64::<byteorder::LittleEndian>()?;
+                let width = reader.read_u8()?;
+                if width > 64 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid bit width {}", width),
+                    ));
+                }
+                Ok(Encoding::Dictionary {
+                    values: values as usize,
+                    width,
+                })
+            }
+            byte => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown value encoding {}", byte),
+            )),
+        }
+    }
+
+    fn write_to<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Encoding::Plain => writer.write_u8(0),
+            Encoding::BitPacked { base, width } => {
+                writer.write_u8(1)?;
+                writer.write_i64::<byteorder::LittleEndian>(base)?;
+                writer.write_u8(width)
+            }
+            Encoding::Rle { runs } => {
+                writer.write_u8(2)?;
+                writer.write_u64::<byteorder::LittleEndian>(runs as u64)
+            }
+            Encoding::Delta { len } => {
+                writer.write_u8(3)?;
+                writer.write_u64::<byteorder::LittleEndian>(len as u64)
+            }
+            Encoding::Dictionary { values, width } => {
+                writer.write_u8(4)?;
+                writer.write_u64::<byteorder::LittleEndian>(values as u64)?;
+                writer.write_u8(width)
+            }
+        }
+    }
+}
+
+/// How the values of a page are compressed. Every page records its own, so
+/// the pages of a collection may differ.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Compression {
+    /// The snappy frame format, used by pages written before the
+    /// compression was recorded.
+    Snappy,
+    /// The values are stored as is.
+    Uncompressed,
+    /// The zstd frame format, slower than snappy but usually smaller. The
+    /// level only affects writing and is not stored, so pages read back
+    /// report 0, which stands for zstd's default level.
+    Zstd { level: i32 },
+    /// The lz4 frame format.
+    Lz4,
+}
+
+impl Compression {
+    fn from_byte(byte: u8) -> io::Result<Self> {
+        match byte {
+            0 => Ok(Compression::Snappy),
+            1 => Ok(Compression::Uncompressed),
+            2 => Ok(Compression::Zstd { level: 0 }),
+            3 => Ok(Compression::Lz4),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown compression {}", byte),
+            )),
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Compression::Snappy => 0,
+            Compression::Uncompressed => 1,
+            Compression::Zstd { .. } => 2,
+            Compression::Lz4 => 3,
+        }
+    }
+
+    /// Wraps the compressed values read from `reader` in a reader of the
+    /// decompressed values.
+    fn decoder<'a, R: Read + 'a>(self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::Snappy => Box::new(snap::read::FrameDecoder::new(reader)),
+            Compression::Uncompressed => Box::new(reader),
+            Compression::Zstd { .. } => {
+                Box::new(zstd::stream::read::Decoder::new(reader)?.single_frame())
+            }
+            Compression::Lz4 => Box::new(lz4_flex::frame::FrameDecoder::new(reader)),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Type {
     Bool,
     Int,
     Float,
     String,
+    /// 32-bit ints, stored in 4 bytes each.
+    Int32,
+    /// Unsigned 64-bit ints.
+    UInt,
+    /// 32-bit floats, stored in 4 bytes each.
+    Float32,
+    /// Points in time, stored as milliseconds since the Unix epoch.
+    Timestamp,
+    /// Arbitrary bytes, laid out like strings but never checked for UTF-8.
+    Binary,
+    /// Exact fixed-point numbers, stored as 16-byte unscaled ints with one
+    /// scale per page.
+    Decimal,
 }
 
-#[derive(Clone)]
+impl Type {
+    fn from_byte(byte: u8) -> io::Result<Self> {
+        match byte {
+            0 => Ok(Type::Bool),
+            1 => Ok(Type::Int),
+            2 => Ok(Type::Float),
+            3 => Ok(Type::String),
+            4 => Ok(Type::Int32),
+            5 => Ok(Type::UInt),
+            6 => Ok(Type::Float32),
+            7 => Ok(Type::Timestamp),
+            8 => Ok(Type::Binary),
+            9 => Ok(Type::Decimal),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown page type {}", byte),
+            )),
+        }
+    }
+
+    /// Whether pages of the type delimit their values with offsets.
+    fn has_offsets(self) -> bool {
+        self == Type::String || self == Type::Binary
+    }
+}
+
+/// A single entry of a collection of any type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    /// Milliseconds since the Unix epoch.
+    Timestamp(i64),
+    Float(f64),
+    String(String),
+    Binary(Vec<u8>),
+    Decimal(Decimal),
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        *self == Value::Null
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_uint(&self) -> Option<u64> {
+        match self {
+            Value::UInt(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_timestamp(&self) -> Option<i64> {
+        match self {
+            Value::Timestamp(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Binary(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Decimal(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// A fixed-point number: `value` divided by 10 to the power of `scale`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Decimal {
+    pub value: i128,
+    pub scale: u8,
+}
+
+impl Decimal {
+    /// The largest scale, the number of digits of `i128::MAX` less one.
+    pub const MAX_SCALE: u8 = 38;
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scale = self.scale as usize;
+        let digits = format!("{:0width$}", self.value.unsigned_abs(), width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        if self.value < 0 {
+            f.write_str("-")?;
+        }
+        if scale == 0 {
+            write!(f, "{}", whole)
+        } else {
+            write!(f, "{}.{}", whole, fraction)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Bound<T: PartialOrd> {
-    min: T,
-    max: T,
+    pub min: T,
+    pub max: T,
 }
 
 pub struct PageData {
@@ -28,9 +431,18 @@ pub struct PageData {
     nulls: BitVec<bv::LittleEndian, u8>,
     offsets: Vec<usize>,
     typ: Type,
+    encoding: Encoding,
+    /// Digits after the decimal point of every value of a decimal page.
+    scale: u8,
 }
 
 impl PageData {
+    /// A bool page holding one value bit per entry, with nulls left as a
+    /// `false` filler. Pages without nulls are written with only those
+    /// bits. Nullable pages keep their null bitmap beside them rather than
+    /// packing each entry in a tri-state pair of bits: both take two bits
+    /// an entry, and the separate bitmap can still shrink to a roaring
+    /// bitmap when nulls are sparse.
     pub fn from_bools(data: &[Option<bool>]) -> io::Result<PageData> {
         let mut bits = BitVec::<bv::LittleEndian, u8>::new();
         let mut nulls = BitVec::new();
@@ -41,9 +453,11 @@ impl PageData {
         }
         Ok(PageData {
             bytes: bits.as_slice().to_vec(),
-            nulls: nulls,
+            nulls,
             offsets: vec![],
             typ: Type::Bool,
+            encoding: Encoding::Plain,
+            scale: 0,
         })
     }
 
@@ -56,10 +470,12 @@ impl PageData {
             nulls.push(entry.is_none());
         }
         Ok(PageData {
-            bytes: bytes,
-            nulls: nulls,
+            bytes,
+            nulls,
             offsets: vec![],
             typ: Type::Int,
+            encoding: Encoding::Plain,
+            scale: 0,
         })
     }
 
@@ -71,215 +487,2436 @@ impl PageData {
             bytes.write_f64::<byteorder::LittleEndian>(entry.unwrap_or(0.0))?;
         }
         Ok(PageData {
-            bytes: bytes,
-            nulls: nulls,
+            bytes,
+            nulls,
             offsets: vec![],
             typ: Type::Float,
+            encoding: Encoding::Plain,
+            scale: 0,
         })
     }
 
-    pub fn from_strings(data: &[Option<&str>]) -> io::Result<PageData> {
+    /// Like `from_floats`, for 32-bit floats, which take half the space.
+    /// Values are stored by their bits, so NaN payloads survive.
+    pub fn from_f32(data: &[Option<f32>]) -> io::Result<PageData> {
+        let mut nulls = BitVec::new();
+        let mut bytes = vec![];
+        for entry in data.iter() {
+            nulls.push(entry.is_none());
+            bytes.write_f32::<byteorder::LittleEndian>(entry.unwrap_or(0.0))?;
+        }
+        Ok(PageData {
+            bytes,
+            nulls,
+            offsets: vec![],
+            typ: Type::Float32,
+            encoding: Encoding::Plain,
+            scale: 0,
+        })
+    }
+
+    /// Like `from_ints`, for 32-bit ints, which take half the space.
+    pub fn from_i32(data: &[Option<i32>]) -> io::Result<PageData> {
         let mut bytes = vec![];
         let mut nulls = BitVec::new();
-        let mut offset = 0;
-        let mut offsets = vec![];
 
         for entry in data.iter() {
-            let value = entry.unwrap_or("");
-            bytes.extend(value.bytes());
+            bytes.write_i32::<byteorder::LittleEndian>(entry.unwrap_or(0))?;
             nulls.push(entry.is_none());
-            offsets.push(offset);
-            offset += value.len();
         }
-        offsets.push(offset);
-
         Ok(PageData {
-            bytes: bytes,
-            nulls: nulls,
-            offsets: offsets,
-            typ: Type::String,
+            bytes,
+            nulls,
+            offsets: vec![],
+            typ: Type::Int32,
+            encoding: Encoding::Plain,
+            scale: 0,
         })
     }
 
-    pub fn get_bool(&self, idx: usize) -> Option<bool> {
-        if self.nulls[idx] {
-            None
-        } else {
-            let bits = BitVec::<bv::LittleEndian, u8>::from_slice(&self.bytes);
-            bits.get(idx)
-        }
+    /// Like `from_ints`, for milliseconds since the Unix epoch.
+    pub fn from_timestamps(data: &[Option<i64>]) -> io::Result<PageData> {
+        let mut page = PageData::from_ints(data)?;
+        page.typ = Type::Timestamp;
+        Ok(page)
     }
 
-    pub fn get_int(&self, idx: usize) -> Option<i64> {
-        if self.nulls[idx] {
-            None
-        } else {
-            let mut slice = self.bytes.get(idx * 8..(idx + 1) * 8).unwrap();
-            Some(slice.read_i64::<byteorder::LittleEndian>().unwrap())
-        }
+    /// Like `from_timestamps`, stored to the millisecond.
+    #[cfg(feature = "chrono")]
+    pub fn from_datetimes(data: &[Option<DateTime<Utc>>]) -> io::Result<PageData> {
+        PageData::from_timestamps(
+            &data
+                .iter()
+                .map(|entry| entry.map(|time| time.timestamp_millis()))
+                .collect::<Vec<_>>(),
+        )
     }
 
-    pub fn get_float(&self, idx: usize) -> Option<f64> {
-        if self.nulls[idx] {
-            None
-        } else {
-            let mut slice = self.bytes.get(idx * 8..(idx + 1) * 8).unwrap();
-            Some(slice.read_f64::<byteorder::LittleEndian>().unwrap())
+    /// A page of decimals, each the unscaled `value` of a `Decimal` with the
+    /// given `scale`.
+    pub fn from_decimals(data: &[Option<i128>], scale: u8) -> io::Result<PageData> {
+        assert!(scale <= Decimal::MAX_SCALE);
+        let mut bytes = vec![];
+        let mut nulls = BitVec::new();
+
+        for entry in data.iter() {
+            bytes.write_i128::<byteorder::LittleEndian>(entry.unwrap_or(0))?;
+            nulls.push(entry.is_none());
         }
+        Ok(PageData {
+            bytes,
+            nulls,
+            offsets: vec![],
+            typ: Type::Decimal,
+            encoding: Encoding::Plain,
+            scale,
+        })
     }
 
-    pub fn get_string(&self, idx: usize) -> Option<String> {
-        if self.nulls[idx] {
-            None
-        } else {
-            let slice = self
-                .bytes
-                .get(self.offsets[idx]..self.offsets[idx + 1])
-                .unwrap();
-            Some(String::from_utf8(slice.to_vec()).unwrap())
+    /// Like `from_ints`, for unsigned ints past `i64::MAX`.
+    pub fn from_u64(data: &[Option<u64>]) -> io::Result<PageData> {
+        let mut bytes = vec![];
+        let mut nulls = BitVec::new();
+
+        for entry in data.iter() {
+            bytes.write_u64::<byteorder::LittleEndian>(entry.unwrap_or(0))?;
+            nulls.push(entry.is_none());
         }
+        Ok(PageData {
+            bytes,
+            nulls,
+            offsets: vec![],
+            typ: Type::UInt,
+            encoding: Encoding::Plain,
+            scale: 0,
+        })
     }
-}
 
-#[derive(Clone, Default)]
-pub struct PageStats {
-    contains_nulls: bool,
-    int_bound: Option<Bound<usize>>,
-    float_bound: Option<Bound<f64>>,
-    string_bound: Option<Bound<String>>,
-}
+    /// Like `from_ints`, but stores each value as its distance from the page
+    /// minimum, packed into just enough bits to hold the largest distance.
+    /// Pages whose values span a small range shrink to a fraction of their
+    /// plain size.
+    pub fn from_ints_bitpacked(data: &[Option<i64>]) -> io::Result<PageData> {
+        let (base, width) = bit_packing(data);
 
-#[derive(Clone)]
-pub struct PageMeta {
-    pub id: Uuid,
-    pub path: PathBuf,
-    pub size: usize,
-    pub typ: Type,
-    offset: usize,
-    stats: PageStats,
-}
+        let mut bytes = vec![0; (data.len() * width as usize).div_ceil(8)];
+        let mut nulls = BitVec::new();
+        for (idx, entry) in data.iter().enumerate() {
+            if let Some(value) = entry {
+                pack_bits(&mut bytes, idx, width, value.wrapping_sub(base) as u64);
+            }
+            nulls.push(entry.is_none());
+        }
+        Ok(PageData {
+            bytes,
+            nulls,
+            offsets: vec![],
+            typ: Type::Int,
+            encoding: Encoding::BitPacked { base, width },
+            scale: 0,
+        })
+    }
 
-impl PageMeta {
-    pub fn new(typ: Type, path: &Path, offset: usize, size: usize) -> Self {
-        PageMeta {
-            id: Uuid::new_v4(),
-            offset: offset,
-            path: path.to_path_buf(),
-            size: size,
-            stats: PageStats::default(),
-            typ: typ,
+    /// Like `from_ints`, but stores each run of equal values once with the
+    /// index ending it. Sorted or clustered columns shrink to a few runs,
+    /// and reading an entry binary searches the runs.
+    pub fn from_ints_rle(data: &[Option<i64>]) -> io::Result<PageData> {
+        let mut bytes = vec![];
+        let runs = runs(data, 0);
+        for (end, value) in &runs {
+            bytes.write_u64::<byteorder::LittleEndian>(*end as u64)?;
+            bytes.write_i64::<byteorder::LittleEndian>(*value)?;
         }
+        Ok(PageData {
+            bytes,
+            nulls: data.iter().map(Option::is_none).collect(),
+            offsets: vec![],
+            typ: Type::Int,
+            encoding: Encoding::Rle { runs: runs.len() },
+            scale: 0,
+        })
     }
-}
 
-pub type PageKey = (Uuid, usize);
+    /// Like `from_ints`, but stores each value as its difference from the
+    /// one before it in a zigzag varint, so slowly growing columns such as
+    /// ids and timestamps take a byte or two per entry. A checkpoint every
+    /// `DELTA_CHECKPOINT_INTERVAL` entries bounds the differences summed to
+    /// read one entry.
+    pub fn from_ints_delta(data: &[Option<i64>]) -> io::Result<PageData> {
+        let mut bytes = vec![];
+        let mut deltas = vec![];
+        let mut prev = 0i64;
+        for (idx, entry) in data.iter().enumerate() {
+            let value = entry.unwrap_or(prev);
+            if idx % DELTA_CHECKPOINT_INTERVAL == 0 {
+                bytes.write_i64::<byteorder::LittleEndian>(value)?;
+                bytes.write_u64::<byteorder::LittleEndian>(deltas.len() as u64)?;
+            } else {
+                write_varint(&mut deltas, zigzag(value.wrapping_sub(prev)))?;
+            }
+            prev = value;
+        }
+        bytes.extend(deltas);
+        Ok(PageData {
+            encoding: Encoding::Delta { len: bytes.len() },
+            scale: 0,
+            bytes,
+            nulls: data.iter().map(Option::is_none).collect(),
+            offsets: vec![],
+            typ: Type::Int,
+        })
+    }
 
-pub struct Page {
-    data: PageData,
-    meta: PageMeta,
-}
+    /// Like `from_ints`, but stores each distinct value once and every entry
+    /// as the index of its value, packed into just enough bits to hold the
+    /// largest index. Pages holding a handful of widely spread values, such
+    /// as status codes or foreign keys, shrink to a few bits per entry.
+    pub fn from_ints_dictionary(data: &[Option<i64>]) -> io::Result<PageData> {
+        let dictionary = dictionary(data);
+        let width = dictionary_width(dictionary.len());
 
-impl Page {
-    pub fn new(meta: &PageMeta, data: PageData) -> Self {
-        Page {
-            data: data,
-            meta: meta.clone(),
+        let mut bytes = vec![];
+        for value in &dictionary {
+            bytes.write_i64::<byteorder::LittleEndian>(*value)?;
+        }
+        let mut indexes = vec![0; (data.len() * width as usize).div_ceil(8)];
+        for (idx, entry) in data.iter().enumerate() {
+            if let Some(value) = entry {
+                let index = dictionary.binary_search(value).unwrap();
+                pack_bits(&mut indexes, idx, width, index as u64);
+            }
         }
+        bytes.extend(indexes);
+        Ok(PageData {
+            bytes,
+            nulls: data.iter().map(Option::is_none).collect(),
+            offsets: vec![],
+            typ: Type::Int,
+            encoding: Encoding::Dictionary {
+                values: dictionary.len(),
+                width,
+            },
+            scale: 0,
+        })
     }
 
-    pub fn get_bool(&self, idx: usize) -> Option<bool> {
-        assert!(self.meta.typ == Type::Bool);
-        self.data.get_bool(idx)
+    /// Like `from_ints_rle`, for bools.
+    pub fn from_bools_rle(data: &[Option<bool>]) -> io::Result<PageData> {
+        let mut bytes = vec![];
+        let runs = runs(data, false);
+        for (end, value) in &runs {
+            bytes.write_u64::<byteorder::LittleEndian>(*end as u64)?;
+            bytes.write_u8(*value as u8)?;
+        }
+        Ok(PageData {
+            bytes,
+            nulls: data.iter().map(Option::is_none).collect(),
+            offsets: vec![],
+            typ: Type::Bool,
+            encoding: Encoding::Rle { runs: runs.len() },
+            scale: 0,
+        })
     }
 
-    pub fn get_int(&self, idx: usize) -> Option<i64> {
-        assert!(self.meta.typ == Type::Int);
-        self.data.get_int(idx)
+    /// The encoding expected to store `sample` in the fewest bytes: run
+    /// lengths when the values repeat enough, deltas when the non-null
+    /// values are sorted either way, a dictionary when few distinct values
+    /// are spread too far apart to bit pack, bit packing when their range
+    /// leaves enough bits unused to pay for its header, plain otherwise.
+    pub fn recommend_encoding(sample: &[Option<i64>]) -> Encoding {
+        let (base, width) = bit_packing(sample);
+        let runs = runs(sample, 0).len();
+        let values = dictionary(sample).len();
+        let dictionary_width = dictionary_width(values);
+        let plain_len = sample.len() * 8;
+        let packed_len = (sample.len() * width as usize).div_ceil(8) + 9;
+        let rle_len = runs * 16 + 8;
+        let dictionary_len =
+            values * 8 + (sample.len() * dictionary_width as usize).div_ceil(8) + 9;
+        match monotonic_delta_len(sample) {
+            Some(len) if len + 8 < plain_len && len + 8 <= rle_len => Encoding::Delta { len },
+            _ if rle_len < packed_len.min(plain_len).min(dictionary_len) => Encoding::Rle { runs },
+            _ if dictionary_len < packed_len.min(plain_len) => Encoding::Dictionary {
+                values,
+                width: dictionary_width,
+            },
+            _ if packed_len < plain_len => Encoding::BitPacked { base, width },
+            _ => Encoding::Plain,
+        }
     }
 
-    pub fn get_float(&self, idx: usize) -> Option<f64> {
-        assert!(self.meta.typ == Type::Float);
-        self.data.get_float(idx)
+    /// Re-encodes a plain int page with the encoding `recommend_encoding`
+    /// picks for all of its entries. Other pages are returned unchanged.
+    pub fn with_recommended_encoding(self) -> io::Result<PageData> {
+        if self.typ != Type::Int || self.encoding != Encoding::Plain {
+            return Ok(self);
+        }
+        let entries = self.int_entries().collect::<Vec<_>>();
+        match PageData::recommend_encoding(&entries) {
+            Encoding::BitPacked { .. } => PageData::from_ints_bitpacked(&entries),
+            Encoding::Rle { .. } => PageData::from_ints_rle(&entries),
+            Encoding::Delta { .. } => PageData::from_ints_delta(&entries),
+            Encoding::Dictionary { .. } => PageData::from_ints_dictionary(&entries),
+            Encoding::Plain => Ok(self),
+        }
     }
 
-    pub fn get_string(&self, idx: usize) -> Option<String> {
-        assert!(self.meta.typ == Type::String);
-        self.data.get_string(idx)
+    /// A page of `len` nulls, with the same zero filler in the value slots
+    /// that the `from_*` constructors use for nulls.
+    pub fn all_null(typ: Type, len: usize) -> PageData {
+        let mut nulls = BitVec::new();
+        nulls.resize(len, true);
+        let (bytes, offsets) = match typ {
+            Type::Bool => (vec![0; len.div_ceil(8)], vec![]),
+            Type::Int | Type::UInt | Type::Timestamp | Type::Float => (vec![0; len * 8], vec![]),
+            Type::Int32 | Type::Float32 => (vec![0; len * 4], vec![]),
+            Type::Decimal => (vec![0; len * 16], vec![]),
+            Type::String | Type::Binary => (vec![], vec![0; len + 1]),
+        };
+        PageData {
+            bytes,
+            nulls,
+            offsets,
+            typ,
+            encoding: Encoding::Plain,
+            scale: 0,
+        }
     }
-}
-
-pub struct PageReader {}
 
-impl PageReader {
-    pub fn read(meta: &PageMeta) -> io::Result<Page> {
-        debug!("loading page: {:?}", meta.path);
-        let mut file = File::open(&meta.path)?;
+    pub fn from_strings(data: &[Option<&str>]) -> io::Result<PageData> {
+        let mut bytes = vec![];
+        let mut nulls = BitVec::new();
+        let mut offset = 0;
+        let mut offsets = vec![];
 
-        let mut size_bytes = [0; 8];
-        file.read(&mut size_bytes)?;
-        let size = byteorder::LittleEndian::read_u64(&size_bytes);
+        for entry in data.iter() {
+            let value = entry.unwrap_or("");
+            bytes.extend(value.bytes());
+            nulls.push(entry.is_none());
+            offsets.push(offset);
+            offset += value.len();
+        }
+        offsets.push(offset);
 
-        let mut null_bytes = vec![0; size as usize];
-        file.read(&mut null_bytes)?;
-        let nulls = BitVec::from_slice(&null_bytes);
+        Ok(PageData {
+            bytes,
+            nulls,
+            offsets,
+            typ: Type::String,
+            encoding: Encoding::Plain,
+            scale: 0,
+        })
+    }
 
+    /// Like `from_strings`, for values that need not be UTF-8.
+    pub fn from_binaries(data: &[Option<&[u8]>]) -> io::Result<PageData> {
+        let mut bytes = vec![];
+        let mut nulls = BitVec::new();
+        let mut offset = 0;
         let mut offsets = vec![];
-        if meta.typ == Type::String {
-            let mut offset_bytes = vec![0; (meta.size + 1) * 8];
-            file.read(&mut offset_bytes)?;
-            offsets = offset_bytes
-                .chunks(8)
-                .map(|word| byteorder::LittleEndian::read_u64(word) as usize)
-                .collect();
+
+        for entry in data.iter() {
+            let value = entry.unwrap_or(&[]);
+            bytes.extend_from_slice(value);
+            nulls.push(entry.is_none());
+            offsets.push(offset);
+            offset += value.len();
         }
+        offsets.push(offset);
 
-        let mut bytes = vec![];
-        let mut decompressed_file = snap::Reader::new(file);
-        decompressed_file.read_to_end(&mut bytes)?;
-
-        Ok(Page::new(
-            meta,
-            PageData {
-                bytes: bytes,
-                nulls: nulls,
-                offsets: offsets,
-                typ: meta.typ,
-            },
-        ))
+        Ok(PageData {
+            bytes,
+            nulls,
+            offsets,
+            typ: Type::Binary,
+            encoding: Encoding::Plain,
+            scale: 0,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.nulls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nulls.is_empty()
+    }
+
+    /// Bytes the page takes in memory: its values, null bitmap and string
+    /// offsets.
+    pub fn decoded_len(&self) -> usize {
+        self.bytes.len()
+            + self.nulls.as_slice().len()
+            + self.offsets.len() * std::mem::size_of::<usize>()
+    }
+
+    pub fn typ(&self) -> Type {
+        self.typ
+    }
+
+    pub fn is_null(&self, idx: usize) -> bool {
+        self.nulls[idx]
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Decodes every int value of the page into `values`, with nulls left as
+    /// their zero filler.
+    pub fn read_ints(&self, values: &mut Vec<i64>) {
+        assert!(self.typ == Type::Int);
+        values.clear();
+        match self.encoding {
+            Encoding::Plain => values.extend(
+                self.bytes
+                    .chunks_exact(8)
+                    .map(byteorder::LittleEndian::read_i64),
+            ),
+            Encoding::BitPacked { .. } | Encoding::Rle { .. } | Encoding::Dictionary { .. } => {
+                values.extend((0..self.len()).map(|idx| self.int_at(idx).unwrap_or(0)))
+            }
+            Encoding::Delta { .. } => {
+                // Decoded in one pass rather than from a checkpoint per
+                // entry. Pages are checked to decode when they are loaded.
+                values.extend(self.decode_deltas().unwrap());
+                for (value, null) in values.iter_mut().zip(self.nulls.iter()) {
+                    if null {
+                        *value = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The entries of an int page, decoded in one pass.
+    fn int_entries(&self) -> impl Iterator<Item = Option<i64>> + '_ {
+        let mut values = vec![];
+        self.read_ints(&mut values);
+        values
+            .into_iter()
+            .zip(self.nulls.iter())
+            .map(|(value, null)| if null { None } else { Some(value) })
+    }
+
+    /// Decodes every float value of the page into `values`, with nulls left
+    /// as their zero filler.
+    pub fn read_floats(&self, values: &mut Vec<f64>) {
+        assert!(self.typ == Type::Float);
+        values.clear();
+        values.extend(
+            self.bytes
+                .chunks_exact(8)
+                .map(byteorder::LittleEndian::read_f64),
+        );
+    }
+
+    /// A bloom filter over the non-null values of an int page.
+    pub fn int_bloom(&self) -> BloomFilter {
+        assert!(self.typ == Type::Int);
+        let mut bloom = BloomFilter::new();
+        for value in self.int_entries().flatten() {
+            bloom.insert(value);
+        }
+        bloom
+    }
+
+    /// The min and max non-null value of an int, 32-bit int or timestamp
+    /// page, or `None` if it has none.
+    pub fn int_bound(&self) -> Option<Bound<i64>> {
+        match self.typ {
+            Type::Int32 => bound_of(
+                (0..self.len())
+                    .filter_map(|idx| self.i32_at(idx))
+                    .map(i64::from),
+            ),
+            Type::Timestamp => bound_of((0..self.len()).filter_map(|idx| self.timestamp_at(idx))),
+            _ => {
+                assert!(self.typ == Type::Int);
+                bound_of(self.int_entries().flatten())
+            }
+        }
+    }
+
+    /// The min and max non-null value of an unsigned int page, or `None` if
+    /// it has none.
+    pub fn uint_bound(&self) -> Option<Bound<u64>> {
+        assert!(self.typ == Type::UInt);
+        bound_of((0..self.len()).filter_map(|idx| self.u64_at(idx)))
+    }
+
+    /// The min and max non-null value of a float or 32-bit float page, or
+    /// `None` if it has none. NaN values are left out rather than poisoning
+    /// the bound.
+    pub fn float_bound(&self) -> Option<Bound<f64>> {
+        let values: Box<dyn Iterator<Item = f64>> = match self.typ {
+            Type::Float32 => Box::new(
+                (0..self.len())
+                    .filter_map(|idx| self.f32_at(idx))
+                    .map(f64::from),
+            ),
+            _ => {
+                assert!(self.typ == Type::Float);
+                Box::new((0..self.len()).filter_map(|idx| self.float_at(idx)))
+            }
+        };
+        values
+            .filter(|value| !value.is_nan())
+            .fold(None, |bound, value| match bound {
+                None => Some(Bound {
+                    min: value,
+                    max: value,
+                }),
+                Some(Bound { min, max }) => Some(Bound {
+                    min: min.min(value),
+                    max: max.max(value),
+                }),
+            })
+    }
+
+    /// The min and max non-null value of a string page, or `None` if it
+    /// has none. Both are cut to at most `prefix_len` bytes, and a cut max
+    /// is rounded up so it still bounds every value of the page.
+    pub fn string_bound(&self, prefix_len: usize) -> Option<Bound<String>> {
+        assert!(self.typ == Type::String);
+        let bound =
+            (0..self.len())
+                .filter_map(|idx| self.string_at(idx))
+                .fold(None, |bound, value| match bound {
+                    None => Some(Bound {
+                        min: value.clone(),
+                        max: value,
+                    }),
+                    Some(Bound { min, max }) => Some(if value < min {
+                        Bound { min: value, max }
+                    } else if value > max {
+                        Bound { min, max: value }
+                    } else {
+                        Bound { min, max }
+                    }),
+                })?;
+        Some(truncate_bound(&bound, prefix_len))
+    }
+
+    /// Whether the non-null values of an int page never decrease.
+    pub fn is_sorted_int(&self) -> bool {
+        assert!(self.typ == Type::Int);
+        let mut values = self.int_entries().flatten();
+        let mut prev = match values.next() {
+            Some(value) => value,
+            None => return true,
+        };
+        values.all(|value| {
+            let sorted = prev <= value;
+            prev = value;
+            sorted
+        })
+    }
+
+    /// The concatenated bytes of every string or binary value of the page,
+    /// and the `len() + 1` offsets delimiting them.
+    pub fn string_parts(&self) -> (&[u8], &[usize]) {
+        assert!(self.typ.has_offsets());
+        (&self.bytes, &self.offsets)
+    }
+
+    /// CRC32 over the decoded nulls, offsets and values of the page.
+    pub fn checksum(&self) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(self.nulls.as_slice());
+        for offset in &self.offsets {
+            hasher.update(&(*offset as u64).to_le_bytes());
+        }
+        match (self.typ, self.encoding) {
+            (_, Encoding::Plain) => hasher.update(&self.bytes),
+            (Type::Bool, _) => {
+                let bits = (0..self.len())
+                    .map(|idx| self.bool_at(idx).unwrap_or(false))
+                    .collect::<BitVec<bv::LittleEndian, u8>>();
+                hasher.update(bits.as_slice());
+            }
+            _ => {
+                for value in self.int_entries() {
+                    hasher.update(&value.unwrap_or(0).to_le_bytes());
+                }
+            }
+        }
+        hasher.finalize()
+    }
+
+    fn bool_at(&self, idx: usize) -> Option<bool> {
+        if self.nulls[idx] {
+            None
+        } else {
+            match self.encoding {
+                Encoding::Rle { .. } => {
+                    let start = self.run_of(idx) * RLE_BOOL_RUN_LEN;
+                    Some(self.bytes[start + 8] == 1)
+                }
+                // Read the single bit in place: decoding the whole slice on
+                // every call made iterating a page quadratic.
+                _ => Some(unpack_bits(&self.bytes, idx, 1) == 1),
+            }
+        }
+    }
+
+    fn int_at(&self, idx: usize) -> Option<i64> {
+        if self.nulls[idx] {
+            None
+        } else {
+            Some(self.unpack_int(idx))
+        }
+    }
+
+    fn i32_at(&self, idx: usize) -> Option<i32> {
+        if self.nulls[idx] {
+            None
+        } else {
+            let mut slice = self.bytes.get(idx * 4..(idx + 1) * 4).unwrap();
+            Some(slice.read_i32::<byteorder::LittleEndian>().unwrap())
+        }
+    }
+
+    fn timestamp_at(&self, idx: usize) -> Option<i64> {
+        self.int_at(idx)
+    }
+
+    fn decimal_at(&self, idx: usize) -> Option<Decimal> {
+        if self.nulls[idx] {
+            None
+        } else {
+            let mut slice = self.bytes.get(idx * 16..(idx + 1) * 16).unwrap();
+            Some(Decimal {
+                value: slice.read_i128::<byteorder::LittleEndian>().unwrap(),
+                scale: self.scale,
+            })
+        }
+    }
+
+    /// Digits after the decimal point of the values of a decimal page, and
+    /// 0 for pages of other types.
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    fn u64_at(&self, idx: usize) -> Option<u64> {
+        if self.nulls[idx] {
+            None
+        } else {
+            let mut slice = self.bytes.get(idx * 8..(idx + 1) * 8).unwrap();
+            Some(slice.read_u64::<byteorder::LittleEndian>().unwrap())
+        }
+    }
+
+    fn float_at(&self, idx: usize) -> Option<f64> {
+        if self.nulls[idx] {
+            None
+        } else {
+            let mut slice = self.bytes.get(idx * 8..(idx + 1) * 8).unwrap();
+            Some(slice.read_f64::<byteorder::LittleEndian>().unwrap())
+        }
+    }
+
+    fn f32_at(&self, idx: usize) -> Option<f32> {
+        if self.nulls[idx] {
+            None
+        } else {
+            let mut slice = self.bytes.get(idx * 4..(idx + 1) * 4).unwrap();
+            Some(slice.read_f32::<byteorder::LittleEndian>().unwrap())
+        }
+    }
+
+    fn string_at(&self, idx: usize) -> Option<String> {
+        self.str_at(idx).map(str::to_owned)
+    }
+
+    fn str_at(&self, idx: usize) -> Option<&str> {
+        if self.nulls[idx] {
+            None
+        } else {
+            let slice = &self.bytes[self.offsets[idx]..self.offsets[idx + 1]];
+            // SAFETY: string pages are built from `&str` values or checked
+            // by `validate_strings` when loaded, and lazy pages check each
+            // string with `validate_string` before reading it.
+            Some(unsafe { std::str::from_utf8_unchecked(slice) })
+        }
+    }
+
+    fn binary_at(&self, idx: usize) -> Option<&[u8]> {
+        if self.nulls[idx] {
+            None
+        } else {
+            Some(&self.bytes[self.offsets[idx]..self.offsets[idx + 1]])
+        }
+    }
+
+    /// Fails with `TypeMismatch` on a page of another type and with
+    /// `OutOfBounds` when `idx` is past the end of the page.
+    pub fn get_bool(&self, idx: usize) -> Result<Option<bool>> {
+        self.check_type(Type::Bool)?;
+        self.check_index(idx)?;
+        Ok(self.bool_at(idx))
+    }
+
+    pub fn get_int(&self, idx: usize) -> Result<Option<i64>> {
+        self.check_type(Type::Int)?;
+        self.check_index(idx)?;
+        Ok(self.int_at(idx))
+    }
+
+    pub fn get_i32(&self, idx: usize) -> Result<Option<i32>> {
+        self.check_type(Type::Int32)?;
+        self.check_index(idx)?;
+        Ok(self.i32_at(idx))
+    }
+
+    pub fn get_u64(&self, idx: usize) -> Result<Option<u64>> {
+        self.check_type(Type::UInt)?;
+        self.check_index(idx)?;
+        Ok(self.u64_at(idx))
+    }
+
+    pub fn get_float(&self, idx: usize) -> Result<Option<f64>> {
+        self.check_type(Type::Float)?;
+        self.check_index(idx)?;
+        Ok(self.float_at(idx))
+    }
+
+    pub fn get_f32(&self, idx: usize) -> Result<Option<f32>> {
+        self.check_type(Type::Float32)?;
+        self.check_index(idx)?;
+        Ok(self.f32_at(idx))
+    }
+
+    /// Milliseconds since the Unix epoch.
+    pub fn get_timestamp(&self, idx: usize) -> Result<Option<i64>> {
+        self.check_type(Type::Timestamp)?;
+        self.check_index(idx)?;
+        Ok(self.timestamp_at(idx))
+    }
+
+    pub fn get_decimal(&self, idx: usize) -> Result<Option<Decimal>> {
+        self.check_type(Type::Decimal)?;
+        self.check_index(idx)?;
+        Ok(self.decimal_at(idx))
+    }
+
+    pub fn get_string(&self, idx: usize) -> Result<Option<String>> {
+        self.check_type(Type::String)?;
+        self.check_index(idx)?;
+        Ok(self.string_at(idx))
+    }
+
+    /// Like `get_string`, but borrows the string from the page instead of
+    /// copying it.
+    pub fn get_str(&self, idx: usize) -> Result<Option<&str>> {
+        self.check_type(Type::String)?;
+        self.check_index(idx)?;
+        Ok(self.str_at(idx))
+    }
+
+    pub fn get_binary(&self, idx: usize) -> Result<Option<&[u8]>> {
+        self.check_type(Type::Binary)?;
+        self.check_index(idx)?;
+        Ok(self.binary_at(idx))
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn get_datetime(&self, idx: usize) -> Result<Option<DateTime<Utc>>> {
+        Ok(self
+            .get_timestamp(idx)?
+            .and_then(|millis| Utc.timestamp_millis_opt(millis).single()))
+    }
+
+    fn check_type(&self, expected: Type) -> Result<()> {
+        if self.typ != expected {
+            return Err(Error::TypeMismatch {
+                expected,
+                found: self.typ,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_index(&self, idx: usize) -> Result<()> {
+        if idx >= self.len() {
+            return Err(Error::OutOfBounds {
+                index: idx,
+                size: self.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The int in slot `idx`, ignoring the null bitmap.
+    fn unpack_int(&self, idx: usize) -> i64 {
+        match self.encoding {
+            Encoding::Plain => {
+                let mut slice = self.bytes.get(idx * 8..(idx + 1) * 8).unwrap();
+                slice.read_i64::<byteorder::LittleEndian>().unwrap()
+            }
+            Encoding::BitPacked { base, width } => {
+                base.wrapping_add(unpack_bits(&self.bytes, idx, width) as i64)
+            }
+            Encoding::Rle { .. } => {
+                let start = self.run_of(idx) * RLE_INT_RUN_LEN;
+                byteorder::LittleEndian::read_i64(&self.bytes[start + 8..start + 16])
+            }
+            Encoding::Delta { .. } => {
+                let block = idx / DELTA_CHECKPOINT_INTERVAL;
+                let checkpoint = &self.bytes[block * DELTA_CHECKPOINT_LEN..];
+                let mut value = byteorder::LittleEndian::read_i64(&checkpoint[..8]);
+                let position = byteorder::LittleEndian::read_u64(&checkpoint[8..16]) as usize;
+                let mut deltas = &self.bytes[self.delta_checkpoints_len() + position..];
+                for _ in block * DELTA_CHECKPOINT_INTERVAL..idx {
+                    let delta = read_varint(&mut deltas).map_or(0, unzigzag);
+                    value = value.wrapping_add(delta);
+                }
+                value
+            }
+            Encoding::Dictionary { values, width } => {
+                let index = unpack_bits(&self.bytes[values * 8..], idx, width) as usize;
+                let start = index * 8;
+                byteorder::LittleEndian::read_i64(&self.bytes[start..start + 8])
+            }
+        }
+    }
+
+    /// Bytes of the checkpoints starting the values of a delta encoded page.
+    fn delta_checkpoints_len(&self) -> usize {
+        self.len().div_ceil(DELTA_CHECKPOINT_INTERVAL) * DELTA_CHECKPOINT_LEN
+    }
+
+    /// Decodes every value of a delta encoded page, failing if the
+    /// checkpoints disagree with the differences or any bytes are left over.
+    fn decode_deltas(&self) -> io::Result<Vec<i64>> {
+        let checkpoints_len = self.delta_checkpoints_len();
+        if self.bytes.len() < checkpoints_len {
+            return Err(invalid_data(format!(
+                "Delta values of {} bytes hold fewer than {} checkpoints",
+                self.bytes.len(),
+                self.len().div_ceil(DELTA_CHECKPOINT_INTERVAL)
+            )));
+        }
+        let (checkpoints, deltas) = self.bytes.split_at(checkpoints_len);
+        let mut reader = deltas;
+        let mut values = Vec::with_capacity(self.len());
+        let mut value = 0i64;
+        for idx in 0..self.len() {
+            if idx % DELTA_CHECKPOINT_INTERVAL == 0 {
+                let checkpoint =
+                    &checkpoints[idx / DELTA_CHECKPOINT_INTERVAL * DELTA_CHECKPOINT_LEN..];
+                value = byteorder::LittleEndian::read_i64(&checkpoint[..8]);
+                let position = byteorder::LittleEndian::read_u64(&checkpoint[8..16]);
+                if position != (deltas.len() - reader.len()) as u64 {
+                    return Err(invalid_data(format!(
+                        "Delta checkpoint of entry {} points at byte {} of {}",
+                        idx,
+                        position,
+                        deltas.len() - reader.len()
+                    )));
+                }
+            } else {
+                value = value.wrapping_add(unzigzag(read_varint(&mut reader)?));
+            }
+            values.push(value);
+        }
+        if !reader.is_empty() {
+            return Err(invalid_data(
+                "Trailing bytes after delta values".to_string(),
+            ));
+        }
+        Ok(values)
+    }
+
+    /// The run holding slot `idx` of a run-length encoded page, found by
+    /// binary search over the run ends.
+    fn run_of(&self, idx: usize) -> usize {
+        let (run_len, runs) = match (self.typ, self.encoding) {
+            (Type::Bool, Encoding::Rle { runs }) => (RLE_BOOL_RUN_LEN, runs),
+            (_, Encoding::Rle { runs }) => (RLE_INT_RUN_LEN, runs),
+            _ => unreachable!(),
+        };
+        let (mut low, mut high) = (0, runs);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let start = mid * run_len;
+            let end = byteorder::LittleEndian::read_u64(&self.bytes[start..start + 8]);
+            if end as usize <= idx {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// Checks that the runs of a run-length encoded page end in ascending
+    /// order at the end of the page and hold valid values, so that every
+    /// slot falls in a run.
+    fn validate_runs(&self) -> Result<()> {
+        let run_len = match self.typ {
+            Type::Bool => RLE_BOOL_RUN_LEN,
+            _ => RLE_INT_RUN_LEN,
+        };
+        let mut prev = 0;
+        for run in self.bytes.chunks_exact(run_len) {
+            let end = byteorder::LittleEndian::read_u64(&run[..8]);
+            if end <= prev || end > self.len() as u64 {
+                return Err(Error::Corrupt(format!(
+                    "Run ending at {} after {} in a page of {} entries",
+                    end,
+                    prev,
+                    self.len()
+                )));
+            }
+            if self.typ == Type::Bool && run[8] > 1 {
+                return Err(Error::Corrupt(format!("Invalid bool value {}", run[8])));
+            }
+            prev = end;
+        }
+        if prev != self.len() as u64 {
+            return Err(Error::Corrupt(format!(
+                "Runs end at {} in a page of {} entries",
+                prev,
+                self.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks that every slot of a dictionary encoded page, null or not,
+    /// indexes a value of the dictionary.
+    fn validate_dictionary(&self) -> Result<()> {
+        if let Encoding::Dictionary { values, width } = self.encoding {
+            let indexes = &self.bytes[values * 8..];
+            for idx in 0..self.len() {
+                let index = unpack_bits(indexes, idx, width);
+                if index >= values as u64 {
+                    return Err(Error::Corrupt(format!(
+                        "Entry {} indexes value {} of a dictionary of {}",
+                        idx, index, values
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of value bytes the page holds once fully decompressed.
+    fn values_len(&self) -> usize {
+        match (self.typ, self.encoding) {
+            (Type::Bool, Encoding::Rle { runs }) => runs * RLE_BOOL_RUN_LEN,
+            (_, Encoding::Rle { runs }) => runs * RLE_INT_RUN_LEN,
+            (_, Encoding::Delta { len }) => len,
+            (_, Encoding::Dictionary { values, width }) => {
+                values * 8 + (self.len() * width as usize).div_ceil(8)
+            }
+            (Type::Bool, _) => self.len().div_ceil(8),
+            (Type::String | Type::Binary, _) => self.offsets.last().copied().unwrap_or(0),
+            (Type::Int32 | Type::Float32, _) => self.len() * 4,
+            (Type::Decimal, _) => self.len() * 16,
+            (_, Encoding::Plain) => self.len() * 8,
+            (_, Encoding::BitPacked { width, .. }) => (self.len() * width as usize).div_ceil(8),
+        }
+    }
+
+    /// Checks that the values of a fully loaded string page are UTF-8 and
+    /// that every string starts and ends on a character boundary, which
+    /// lets `get_str` skip validation. One pass over the whole buffer is
+    /// cheaper than validating each string on its own.
+    fn validate_strings(&self) -> Result<()> {
+        let text = std::str::from_utf8(&self.bytes).map_err(Error::Utf8)?;
+        if let Some(idx) = self
+            .offsets
+            .iter()
+            .position(|offset| !text.is_char_boundary(*offset))
+        {
+            return Err(Error::Corrupt(format!(
+                "String offset {} splits a character",
+                idx
+            )));
+        }
+        Ok(())
+    }
+
+    /// Like `validate_strings`, for string slot `idx` alone, as pages
+    /// loaded lazily only hold the values decompressed so far.
+    fn validate_string(&self, idx: usize) -> Result<()> {
+        if !self.nulls[idx] {
+            let slice = &self.bytes[self.offsets[idx]..self.offsets[idx + 1]];
+            std::str::from_utf8(slice).map_err(Error::Utf8)?;
+        }
+        Ok(())
+    }
+
+    /// Number of value bytes needed to decode bool or int slot `idx`. Run
+    /// lengths and deltas need them all, to find the run of a slot or the
+    /// differences after its checkpoint, and dictionaries are checked whole.
+    fn value_end(&self, idx: usize) -> usize {
+        match (self.typ, self.encoding) {
+            (_, Encoding::Rle { .. })
+            | (_, Encoding::Delta { .. })
+            | (_, Encoding::Dictionary { .. }) => self.values_len(),
+            (Type::Bool, _) => idx / 8 + 1,
+            (Type::Int32 | Type::Float32, _) => (idx + 1) * 4,
+            (Type::Decimal, _) => (idx + 1) * 16,
+            (_, Encoding::Plain) => (idx + 1) * 8,
+            (_, Encoding::BitPacked { width, .. }) => ((idx + 1) * width as usize).div_ceil(8),
+        }
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Explains a failure to read a file without the magic number as a legacy
+/// page: most likely it is not a page file at all.
+fn not_a_page(path: &Path, err: io::Error) -> io::Error {
+    invalid_data(format!(
+        "{:?} does not start with the {:?} magic number and is not a valid \
+         headerless page either: {}",
+        path,
+        String::from_utf8_lossy(&MAGIC),
+        err
+    ))
+}
+
+/// Names the page file in errors caused by it ending early, which the
+/// underlying reads report without any context.
+fn truncated(path: &Path, err: io::Error) -> io::Error {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("Page file {:?} is truncated: {}", path, err),
+        )
+    } else {
+        err
+    }
+}
+
+pub(crate) fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    while value >= 0x80 {
+        writer.write_u8(value as u8 | 0x80)?;
+        value >>= 7;
+    }
+    writer.write_u8(value as u8)
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = reader.read_u8()?;
+        if shift == 63 && byte > 1 {
+            break;
+        }
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("Varint overflows 64 bits".to_string()))
+}
+
+/// The offsets delimiting strings of the given byte `lengths`.
+fn prefix_sums(lengths: &[usize]) -> io::Result<Vec<usize>> {
+    let mut offsets = Vec::with_capacity(lengths.len() + 1);
+    let mut offset: usize = 0;
+    offsets.push(offset);
+    for length in lengths {
+        offset = offset
+            .checked_add(*length)
+            .ok_or_else(|| invalid_data("String offsets overflow".to_string()))?;
+        offsets.push(offset);
+    }
+    Ok(offsets)
+}
+
+/// The smallest and largest of `values`, or `None` if there are none.
+fn bound_of<T: PartialOrd + Copy, I: Iterator<Item = T>>(values: I) -> Option<Bound<T>> {
+    values.fold(None, |bound, value| match bound {
+        None => Some(Bound {
+            min: value,
+            max: value,
+        }),
+        Some(Bound { min, max }) => Some(Bound {
+            min: if value < min { value } else { min },
+            max: if value > max { value } else { max },
+        }),
+    })
+}
+
+/// Maps signed to unsigned ints so that values near zero, of either sign,
+/// take few varint bytes.
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// The runs of equal values of `data` as the index ending each run and its
+/// value. Nulls continue the run before them, and leading nulls take
+/// `filler`.
+fn runs<T: Copy + PartialEq>(data: &[Option<T>], filler: T) -> Vec<(usize, T)> {
+    let mut runs: Vec<(usize, T)> = vec![];
+    for (idx, entry) in data.iter().enumerate() {
+        match (runs.last_mut(), entry) {
+            (Some(run), None) => run.0 = idx + 1,
+            (Some(run), Some(value)) if run.1 == *value => run.0 = idx + 1,
+            (_, entry) => runs.push((idx + 1, entry.unwrap_or(filler))),
+        }
+    }
+    runs
+}
+
+/// The base and bit width that bit-pack the non-null values of `data`.
+fn bit_packing(data: &[Option<i64>]) -> (i64, u8) {
+    let present = data.iter().filter_map(|entry| *entry);
+    let base = present.clone().min().unwrap_or(0);
+    let max = present.max().unwrap_or(0);
+    let range = max.wrapping_sub(base) as u64;
+    (base, (64 - range.leading_zeros()) as u8)
+}
+
+/// The distinct non-null values of `data` in ascending order, with a lone
+/// zero standing in for the values of an all-null page so null entries
+/// still index a value.
+fn dictionary(data: &[Option<i64>]) -> Vec<i64> {
+    let mut values = data.iter().filter_map(|entry| *entry).collect::<Vec<_>>();
+    values.sort_unstable();
+    values.dedup();
+    if values.is_empty() && !data.is_empty() {
+        values.push(0);
+    }
+    values
+}
+
+/// Bits needed for the indexes into a dictionary of `values` values.
+fn dictionary_width(values: usize) -> u8 {
+    (64 - values.saturating_sub(1).leading_zeros()) as u8
+}
+
+/// The number of value bytes `PageData::from_ints_delta` writes for
+/// `data`, or `None` unless its non-null values never decrease or never
+/// increase.
+fn monotonic_delta_len(data: &[Option<i64>]) -> Option<usize> {
+    let present = data.iter().filter_map(|entry| *entry).collect::<Vec<_>>();
+    let ascending = present.windows(2).all(|pair| pair[0] <= pair[1]);
+    let descending = present.windows(2).all(|pair| pair[0] >= pair[1]);
+    if !ascending && !descending {
+        return None;
+    }
+
+    let mut len = 0;
+    let mut prev = 0i64;
+    for (idx, entry) in data.iter().enumerate() {
+        let value = entry.unwrap_or(prev);
+        if idx % DELTA_CHECKPOINT_INTERVAL == 0 {
+            len += DELTA_CHECKPOINT_LEN;
+        } else {
+            let delta = zigzag(value.wrapping_sub(prev));
+            len += ((64 - delta.leading_zeros() as usize).max(1)).div_ceil(7);
+        }
+        prev = value;
+    }
+    Some(len)
+}
+
+fn bit_mask(width: u8) -> u64 {
+    if width == 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+/// ORs `value` into the `width`-bit slot `idx` of `bytes`.
+fn pack_bits(bytes: &mut [u8], idx: usize, width: u8, value: u64) {
+    let bit = idx * width as usize;
+    let mut shifted = ((value & bit_mask(width)) as u128) << (bit % 8);
+    let mut byte = bit / 8;
+    while shifted != 0 {
+        bytes[byte] |= shifted as u8;
+        shifted >>= 8;
+        byte += 1;
+    }
+}
+
+/// Cuts both ends of a string bound to at most `len` bytes, rounding the
+/// max up so the result still bounds every value `bound` did.
+pub(crate) fn truncate_bound(bound: &Bound<String>, len: usize) -> Bound<String> {
+    Bound {
+        min: truncate_str(&bound.min, len).to_string(),
+        max: round_up_str(&bound.max, len),
+    }
+}
+
+/// The longest prefix of `value` of at most `len` bytes that ends on a char
+/// boundary.
+fn truncate_str(value: &str, len: usize) -> &str {
+    let mut end = len.min(value.len());
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+/// A string of at most `len` bytes that is no less than `value`: `value`
+/// itself if short enough, otherwise a prefix with its last char bumped.
+/// Falls back to `value` when no char of the prefix can be bumped.
+fn round_up_str(value: &str, len: usize) -> String {
+    let prefix = truncate_str(value, len);
+    if prefix.len() == value.len() {
+        return value.to_string();
+    }
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        let next = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32);
+        if let Some(next) = next {
+            chars.push(next);
+            return chars.into_iter().collect();
+        }
+    }
+    value.to_string()
+}
+
+fn unpack_bits(bytes: &[u8], idx: usize, width: u8) -> u64 {
+    if width == 0 {
+        return 0;
+    }
+    let bit = idx * width as usize;
+    let start = bit / 8;
+    let end = (bit + width as usize).div_ceil(8);
+    let mut value: u128 = 0;
+    for (shift, byte) in bytes[start..end].iter().enumerate() {
+        value |= (*byte as u128) << (shift * 8);
+    }
+    (value >> (bit % 8)) as u64 & bit_mask(width)
+}
+
+#[derive(Clone, Default)]
+pub struct PageStats {
+    pub contains_nulls: bool,
+    /// Number of null entries in the page.
+    pub null_count: usize,
+    pub int_bound: Option<Bound<i64>>,
+    pub uint_bound: Option<Bound<u64>>,
+    pub float_bound: Option<Bound<f64>>,
+    pub string_bound: Option<Bound<String>>,
+    /// Filter over the non-null values of an int page.
+    pub int_bloom: Option<BloomFilter>,
+    /// Whether the non-null values of an int page are known to never
+    /// decrease.
+    pub sorted: bool,
+    /// Number of entries matching each predicate registered with
+    /// `Collection::register_count` when the page was written, by name.
+    pub match_counts: BTreeMap<String, usize>,
+}
+
+impl PageStats {
+    const SORTED: u8 = 1;
+    const BOUNDED: u8 = 2;
+
+    /// Reads a stats section of a page of type `typ` with `size` entries.
+    /// The section stores `null_count`, from which `contains_nulls` is
+    /// derived, the sorted flag and the bound of the page's type.
+    pub(crate) fn read_from<R: Read>(reader: &mut R, typ: Type, size: usize) -> io::Result<Self> {
+        let version = reader.read_u8()?;
+        if version != STATS_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported page stats version {}",
+                version
+            )));
+        }
+        let flags = reader.read_u8()?;
+        let null_count = reader.read_u64::<byteorder::LittleEndian>()? as usize;
+        if null_count > size {
+            return Err(invalid_data(format!(
+                "{} nulls in a page of {} entries",
+                null_count, size
+            )));
+        }
+
+        let mut stats = PageStats {
+            contains_nulls: null_count > 0,
+            null_count,
+            sorted: flags & PageStats::SORTED != 0,
+            ..PageStats::default()
+        };
+        if flags & PageStats::BOUNDED == 0 {
+            return Ok(stats);
+        }
+        match typ {
+            Type::Bool | Type::Binary | Type::Decimal => {}
+            Type::Int | Type::Int32 | Type::Timestamp => {
+                stats.int_bound = Some(Bound {
+                    min: reader.read_i64::<byteorder::LittleEndian>()?,
+                    max: reader.read_i64::<byteorder::LittleEndian>()?,
+                })
+            }
+            Type::UInt => {
+                stats.uint_bound = Some(Bound {
+                    min: reader.read_u64::<byteorder::LittleEndian>()?,
+                    max: reader.read_u64::<byteorder::LittleEndian>()?,
+                })
+            }
+            Type::Float | Type::Float32 => {
+                stats.float_bound = Some(Bound {
+                    min: reader.read_f64::<byteorder::LittleEndian>()?,
+                    max: reader.read_f64::<byteorder::LittleEndian>()?,
+                })
+            }
+            Type::String => {
+                stats.string_bound = Some(Bound {
+                    min: read_stats_string(reader)?,
+                    max: read_stats_string(reader)?,
+                })
+            }
+        }
+        Ok(stats)
+    }
+
+    pub(crate) fn write_to<W: Write>(&self, writer: &mut W, typ: Type) -> io::Result<()> {
+        let bounded = match typ {
+            Type::Bool | Type::Binary | Type::Decimal => false,
+            Type::Int | Type::Int32 | Type::Timestamp => self.int_bound.is_some(),
+            Type::UInt => self.uint_bound.is_some(),
+            Type::Float | Type::Float32 => self.float_bound.is_some(),
+            Type::String => self.string_bound.is_some(),
+        };
+        let mut flags = 0;
+        if self.sorted {
+            flags |= PageStats::SORTED;
+        }
+        if bounded {
+            flags |= PageStats::BOUNDED;
+        }
+        writer.write_u8(STATS_VERSION)?;
+        writer.write_u8(flags)?;
+        writer.write_u64::<byteorder::LittleEndian>(self.null_count as u64)?;
+
+        if let (Type::Int | Type::Int32 | Type::Timestamp, Some(bound)) = (typ, &self.int_bound) {
+            writer.write_i64::<byteorder::LittleEndian>(bound.min)?;
+            writer.write_i64::<byteorder::LittleEndian>(bound.max)?;
+        }
+        if let (Type::UInt, Some(bound)) = (typ, &self.uint_bound) {
+            writer.write_u64::<byteorder::LittleEndian>(bound.min)?;
+            writer.write_u64::<byteorder::LittleEndian>(bound.max)?;
+        }
+        if let (Type::Float | Type::Float32, Some(bound)) = (typ, &self.float_bound) {
+            writer.write_f64::<byteorder::LittleEndian>(bound.min)?;
+            writer.write_f64::<byteorder::LittleEndian>(bound.max)?;
+        }
+        if let (Type::String, Some(bound)) = (typ, &self.string_bound) {
+            for value in &[&bound.min, &bound.max] {
+                write_varint(writer, value.len() as u64)?;
+                writer.write_all(value.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_stats_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let len = read_varint(reader)?;
+    let mut bytes = vec![];
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Page stats end early",
+        ));
+    }
+    String::from_utf8(bytes).map_err(|err| invalid_data(err.to_string()))
+}
+
+/// An earlier file of a page, replaced by an overwrite that produced
+/// collection version `replaced_at`.
+#[derive(Clone, Debug)]
+pub struct PageVersion {
+    pub id: Uuid,
+    pub path: PathBuf,
+    pub checksum: Option<u32>,
+    pub replaced_at: u64,
+}
+
+#[derive(Clone)]
+pub struct PageMeta {
+    pub id: Uuid,
+    pub path: PathBuf,
+    pub size: usize,
+    pub typ: Type,
+    pub checksum: Option<u32>,
+    pub(crate) offset: usize,
+    pub stats: PageStats,
+    /// Files this page replaced, oldest first.
+    pub history: Vec<PageVersion>,
+}
+
+impl PageMeta {
+    pub fn new(typ: Type, path: &Path, offset: usize, size: usize) -> Self {
+        PageMeta::with_id(Uuid::new_v4(), typ, path, offset, size)
+    }
+
+    /// Index of the page's first entry within its collection.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Number of null entries in the page. Pages hold no value for them, so
+    /// a page whose count equals its size can be skipped by any scan looking
+    /// for values.
+    pub fn null_count(&self) -> usize {
+        self.stats.null_count
+    }
+
+    /// Like `new`, but with a caller-chosen page id instead of a random one.
+    pub fn with_id(id: Uuid, typ: Type, path: &Path, offset: usize, size: usize) -> Self {
+        PageMeta {
+            id,
+            checksum: None,
+            history: vec![],
+            offset,
+            path: path.to_path_buf(),
+            size,
+            stats: PageStats::default(),
+            typ,
+        }
+    }
+}
+
+pub type PageKey = (Uuid, usize);
+
+pub struct Page {
+    data: PageData,
+    meta: PageMeta,
+}
+
+impl Page {
+    /// Fails with `TypeMismatch` if `data` holds another type than `meta`.
+    pub fn new(meta: &PageMeta, data: PageData) -> Result<Self> {
+        data.check_type(meta.typ)?;
+        let mut meta = meta.clone();
+        meta.checksum = Some(data.checksum());
+        meta.stats.null_count = data.nulls.count_ones();
+        meta.stats.contains_nulls = meta.stats.null_count > 0;
+        if data.typ == Type::Float || data.typ == Type::Float32 {
+            meta.stats.float_bound = data.float_bound();
+        }
+        if data.typ == Type::String {
+            meta.stats.string_bound = data.string_bound(STRING_BOUND_LEN);
+        }
+        if data.typ == Type::Int32 || data.typ == Type::Timestamp {
+            meta.stats.int_bound = data.int_bound();
+        }
+        if data.typ == Type::UInt {
+            meta.stats.uint_bound = data.uint_bound();
+        }
+        if data.typ == Type::Int {
+            meta.stats.int_bound = data.int_bound();
+            meta.stats.int_bloom = Some(data.int_bloom());
+            meta.stats.sorted = data.is_sorted_int();
+        }
+        Ok(Page { data, meta })
+    }
+
+    /// Like `new`, but takes the stats read from the page file instead of
+    /// scanning `data` for them. Only the checksum and the bloom filter of
+    /// an int page, neither of which the file stores, are computed.
+    fn with_stats(meta: &PageMeta, data: PageData, stats: PageStats) -> Result<Self> {
+        data.check_type(meta.typ)?;
+        let mut meta = meta.clone();
+        meta.checksum = Some(data.checksum());
+        meta.stats = PageStats {
+            int_bloom: match data.typ {
+                Type::Int => Some(data.int_bloom()),
+                _ => None,
+            },
+            ..stats
+        };
+        Ok(Page { data, meta })
+    }
+
+    pub fn meta(&self) -> &PageMeta {
+        &self.meta
+    }
+
+    pub fn data(&self) -> &PageData {
+        &self.data
+    }
+
+    pub fn is_null(&self, idx: usize) -> bool {
+        self.data.is_null(idx)
+    }
+
+    /// Fails with `TypeMismatch` if the page holds another type and with
+    /// `OutOfBounds` when `idx` is past the end of the page.
+    pub fn get_bool(&self, idx: usize) -> Result<Option<bool>> {
+        self.data.get_bool(idx)
+    }
+
+    pub fn get_int(&self, idx: usize) -> Result<Option<i64>> {
+        self.data.get_int(idx)
+    }
+
+    pub fn get_i32(&self, idx: usize) -> Result<Option<i32>> {
+        self.data.get_i32(idx)
+    }
+
+    /// Milliseconds since the Unix epoch.
+    pub fn get_timestamp(&self, idx: usize) -> Result<Option<i64>> {
+        self.data.get_timestamp(idx)
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn get_datetime(&self, idx: usize) -> Result<Option<DateTime<Utc>>> {
+        self.data.get_datetime(idx)
+    }
+
+    pub fn get_decimal(&self, idx: usize) -> Result<Option<Decimal>> {
+        self.data.get_decimal(idx)
+    }
+
+    pub fn get_u64(&self, idx: usize) -> Result<Option<u64>> {
+        self.data.get_u64(idx)
+    }
+
+    pub fn get_float(&self, idx: usize) -> Result<Option<f64>> {
+        self.data.get_float(idx)
+    }
+
+    pub fn get_f32(&self, idx: usize) -> Result<Option<f32>> {
+        self.data.get_f32(idx)
+    }
+
+    pub fn get_string(&self, idx: usize) -> Result<Option<String>> {
+        self.data.get_string(idx)
+    }
+
+    pub fn get_str(&self, idx: usize) -> Result<Option<&str>> {
+        self.data.get_str(idx)
+    }
+
+    pub fn get_binary(&self, idx: usize) -> Result<Option<&[u8]>> {
+        self.data.get_binary(idx)
+    }
+
+    /// Reads an entry of any type, dispatching on the page's type.
+    pub fn get_value(&self, idx: usize) -> Result<Value> {
+        let value = match self.meta.typ {
+            Type::Bool => self.get_bool(idx)?.map(Value::Bool),
+            Type::Int => self.get_int(idx)?.map(Value::Int),
+            Type::Int32 => self.get_i32(idx)?.map(|value| Value::Int(value.into())),
+            Type::UInt => self.get_u64(idx)?.map(Value::UInt),
+            Type::Timestamp => self.get_timestamp(idx)?.map(Value::Timestamp),
+            Type::Decimal => self.get_decimal(idx)?.map(Value::Decimal),
+            Type::Float => self.get_float(idx)?.map(Value::Float),
+            Type::Float32 => self.get_f32(idx)?.map(|value| Value::Float(value.into())),
+            Type::String => self.get_string(idx)?.map(Value::String),
+            Type::Binary => self
+                .get_binary(idx)?
+                .map(|value| Value::Binary(value.to_vec())),
+        };
+        Ok(value.unwrap_or(Value::Null))
+    }
+}
+
+/// Computes the CRC32 of the bytes written through it, for the footer of a
+/// page file.
+pub(crate) struct HashingWriter<W> {
+    inner: W,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Counts the bytes read through it.
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+pub struct PageReader {}
+
+impl PageReader {
+    pub fn read(meta: &PageMeta) -> Result<Page> {
+        debug!("loading page: {:?}", meta.path);
+        let bytes = fs::read(&meta.path)?;
+        PageReader::from_bytes(&bytes, meta)
+    }
+
+    /// Decodes a page from the contents of its file held in memory, such
+    /// as those written by `PageWriter::write_to`. `meta.path` is only used
+    /// in error messages.
+    pub fn from_bytes(bytes: &[u8], meta: &PageMeta) -> Result<Page> {
+        PageReader::verify_footer(bytes, &meta.path)
+            .and_then(|body| PageReader::open_reader(io::Cursor::new(body), &meta.path))
+            .and_then(|(reader, version)| {
+                PageReader::read_page(meta, reader, version).map_err(|err| {
+                    if version == LEGACY_VERSION {
+                        not_a_page(&meta.path, err)
+                    } else {
+                        err
+                    }
+                })
+            })
+            .map_err(|err| truncated(&meta.path, err).into())
+    }
+
+    /// Checks the CRC32 footer of the page file contents `bytes` and returns
+    /// them without it. Files written before format version 9 have no
+    /// footer and are returned whole.
+    fn verify_footer<'a>(bytes: &'a [u8], path: &Path) -> io::Result<&'a [u8]> {
+        let has_footer =
+            bytes.len() > MAGIC.len() && bytes[..MAGIC.len()] == MAGIC && bytes[MAGIC.len()] >= 9;
+        if !has_footer {
+            return Ok(bytes);
+        }
+        if bytes.len() < MAGIC.len() + 1 + PAGE_FOOTER_LEN as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Page file ends before its checksum",
+            ));
+        }
+        let (body, footer) = bytes.split_at(bytes.len() - PAGE_FOOTER_LEN as usize);
+        let expected = byteorder::LittleEndian::read_u32(footer);
+        let actual = crc32fast::hash(body);
+        if actual != expected {
+            return Err(invalid_data(format!(
+                "Page file {:?} checksum {:08x} does not match {:08x}",
+                path, actual, expected
+            )));
+        }
+        Ok(body)
+    }
+
+    fn read_page<R: Read>(meta: &PageMeta, mut reader: R, version: u8) -> io::Result<Page> {
+        let scale = if version >= 4 {
+            PageReader::check_descriptor(meta, &mut reader)?
+        } else {
+            0
+        };
+
+        let (mut data, compression, stats) =
+            match PageReader::read_sections(meta, &mut reader, version)? {
+                Some(mut sections) => {
+                    if let Some(lengths) = sections.lengths {
+                        sections.data.offsets = prefix_sums(&lengths)?;
+                    }
+                    (sections.data, sections.compression, sections.stats)
+                }
+                None => {
+                    let mut data = PageData::all_null(meta.typ, meta.size);
+                    data.scale = scale;
+                    return Ok(Page::new(meta, data)?);
+                }
+            };
+        data.scale = scale;
+        let values_len = data.values_len();
+        compression
+            .decoder(reader)?
+            .take(values_len as u64 + 1)
+            .read_to_end(&mut data.bytes)?;
+        if data.bytes.len() < values_len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "Page values end after {} of {} bytes",
+                    data.bytes.len(),
+                    values_len
+                ),
+            ));
+        }
+        if data.bytes.len() != values_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Page values hold {} bytes but expect {}",
+                    data.bytes.len(),
+                    values_len
+                ),
+            ));
+        }
+        if data.typ == Type::String {
+            data.validate_strings()?;
+        }
+        match data.encoding {
+            Encoding::Rle { .. } => data.validate_runs()?,
+            Encoding::Delta { .. } => {
+                data.decode_deltas()?;
+            }
+            Encoding::Dictionary { .. } => data.validate_dictionary()?,
+            _ => {}
+        }
+
+        Ok(match stats {
+            Some(stats) => Page::with_stats(meta, data, stats)?,
+            None => Page::new(meta, data)?,
+        })
+    }
+
+    /// Reads the nulls and offsets of a page but leaves its values to be
+    /// decompressed on demand by the returned `LazyPage`. Delta-encoded
+    /// offsets are only summed into an index by the first string access.
+    pub fn read_lazy(meta: &PageMeta) -> Result<LazyPage> {
+        debug!("lazily loading page: {:?}", meta.path);
+        let (mut reader, version) = PageReader::open(&meta.path)?;
+        let scale = if version >= 4 {
+            PageReader::check_descriptor(meta, &mut reader)?
+        } else {
+            0
+        };
+
+        let mut page = match PageReader::read_sections(meta, &mut reader, version)? {
+            Some(sections) => LazyPage {
+                data: sections.data,
+                lengths: sections.lengths,
+                payload: Some(sections.compression.decoder(reader)?),
+            },
+            None => LazyPage {
+                data: PageData::all_null(meta.typ, meta.size),
+                lengths: None,
+                payload: None,
+            },
+        };
+        page.data.scale = scale;
+        Ok(page)
+    }
+
+    /// Reads only the null section of a page, never touching its offsets or
+    /// decompressing its values. Set bits mark null entries.
+    pub fn read_nulls(meta: &PageMeta) -> Result<BitVec<bv::LittleEndian, u8>> {
+        let (mut reader, version) = PageReader::open(&meta.path)?;
+        if version >= 4 {
+            PageReader::check_descriptor(meta, &mut reader)?;
+        }
+        match PageReader::read_null_section(meta, &mut reader, version)? {
+            Some(nulls) => Ok(nulls),
+            None => {
+                let mut nulls = BitVec::new();
+                nulls.resize(meta.size, true);
+                Ok(nulls)
+            }
+        }
+    }
+
+    /// Rebuilds the metadata of the page at `path` from the descriptor in
+    /// its header, without reading the rest of the file. Fails for pages
+    /// written before headers described their page.
+    pub fn read_meta(path: &Path) -> Result<PageMeta> {
+        let (mut reader, version) = PageReader::open(path)?;
+        if version < 4 {
+            return Err(Error::Corrupt(format!(
+                "Page format version {} of {:?} has no page descriptor",
+                version, path
+            )));
+        }
+        Ok(PageReader::read_descriptor(path, &mut reader)?)
+    }
+
+    /// Number of bytes of the page file other than its compressed values:
+    /// the header, null section, offsets and checksum footer. Only the
+    /// sections before the values are read.
+    pub fn overhead_len(meta: &PageMeta) -> Result<u64> {
+        let (reader, version) = PageReader::open(&meta.path)?;
+        // A legacy reader replays the bytes taken for the magic number.
+        let consumed = if version == LEGACY_VERSION { 0 } else { 5 };
+        let mut reader = CountingReader {
+            inner: reader,
+            count: consumed,
+        };
+        if version >= 4 {
+            PageReader::check_descriptor(meta, &mut reader)?;
+        }
+        PageReader::read_sections(meta, &mut reader, version)?;
+        if version >= 9 {
+            reader.count += PAGE_FOOTER_LEN;
+        }
+        Ok(reader.count)
+    }
+
+    /// Opens a page file and consumes its magic number and version, returning
+    /// the format version of the rest of the file.
+    fn open(path: &Path) -> io::Result<(Box<dyn Read>, u8)> {
+        PageReader::open_reader(File::open(path)?, path)
+    }
+
+    /// Like `open`, for the contents of the page file at `path` read from
+    /// `reader`.
+    fn open_reader<'a, R: Read + 'a>(
+        mut reader: R,
+        path: &Path,
+    ) -> io::Result<(Box<dyn Read + 'a>, u8)> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic == MAGIC {
+            let version = reader.read_u8()?;
+            if version == LEGACY_VERSION || version > FORMAT_VERSION {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unsupported page format version {} in {:?}", version, path),
+                ));
+            }
+            Ok((Box::new(reader), version))
+        } else {
+            // Pages written before the header was introduced start directly
+            // with the null bitmap size, so the bytes read as a magic number
+            // belong to the body.
+            debug!("legacy page: {:?}", path);
+            Ok((
+                Box::new(io::Cursor::new(magic).chain(reader)),
+                LEGACY_VERSION,
+            ))
+        }
+    }
+
+    /// Reads the descriptor in the header and fails if it records another
+    /// type or size than `meta`, which decide how the rest is decoded. Pages
+    /// keep the size they were written with, whatever page size the code
+    /// reading them would pick today. Returns the scale following the
+    /// descriptor of a decimal page, or 0 for other pages.
+    fn check_descriptor<R: Read>(meta: &PageMeta, reader: &mut R) -> io::Result<u8> {
+        let stored = PageReader::read_descriptor(&meta.path, reader)?;
+        if stored.typ != meta.typ {
+            return Err(Error::TypeMismatch {
+                expected: meta.typ,
+                found: stored.typ,
+            }
+            .into());
+        }
+        if stored.size != meta.size {
+            return Err(invalid_data(format!(
+                "Page {:?} holds {} entries but was read as {}",
+                meta.path, stored.size, meta.size
+            )));
+        }
+        if meta.typ != Type::Decimal {
+            return Ok(0);
+        }
+        let scale = reader.read_u8()?;
+        if scale > Decimal::MAX_SCALE {
+            return Err(invalid_data(format!(
+                "Decimal page {:?} has scale {}",
+                meta.path, scale
+            )));
+        }
+        Ok(scale)
+    }
+
+    /// Reads the type, id, logical offset and size recorded in the header.
+    fn read_descriptor<R: Read>(path: &Path, reader: &mut R) -> io::Result<PageMeta> {
+        let typ = Type::from_byte(reader.read_u8()?)?;
+        let mut id = [0; 16];
+        reader.read_exact(&mut id)?;
+        let offset = reader.read_u64::<byteorder::LittleEndian>()?;
+        let size = reader.read_u64::<byteorder::LittleEndian>()?;
+        Ok(PageMeta::with_id(
+            Uuid::from_bytes(id),
+            typ,
+            path,
+            offset as usize,
+            size as usize,
+        ))
+    }
+
+    /// Reads every section before the compressed values, or returns `None`
+    /// for an all-null page, which stores neither offsets nor values.
+    fn read_sections<R: Read>(
+        meta: &PageMeta,
+        reader: &mut R,
+        version: u8,
+    ) -> io::Result<Option<Sections>> {
+        let nulls = match PageReader::read_null_section(meta, reader, version)? {
+            Some(nulls) => nulls,
+            None => return Ok(None),
+        };
+        let stats = if version >= 7 {
+            let stats = PageStats::read_from(reader, meta.typ, meta.size)?;
+            if stats.null_count != nulls.count_ones() {
+                return Err(invalid_data(format!(
+                    "Page stats count {} nulls but the null section {}",
+                    stats.null_count,
+                    nulls.count_ones()
+                )));
+            }
+            Some(stats)
+        } else {
+            None
+        };
+
+        let mut offsets = vec![];
+        let mut lengths = None;
+        let offset_encoding = if meta.typ.has_offsets() && version >= 5 {
+            OffsetEncoding::from_byte(reader.read_u8()?)?
+        } else {
+            OffsetEncoding::Absolute
+        };
+        if meta.typ.has_offsets() && offset_encoding == OffsetEncoding::Delta {
+            lengths = Some(PageReader::read_lengths(meta, reader)?);
+        } else if meta.typ.has_offsets() && offset_encoding == OffsetEncoding::CompressedDelta {
+            let size = reader.read_u64::<byteorder::LittleEndian>()?;
+            let mut compressed = vec![];
+            reader.take(size).read_to_end(&mut compressed)?;
+            if compressed.len() as u64 != size {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Compressed offsets end early",
+                ));
+            }
+            // A varint takes at most 10 bytes.
+            let max_len = meta.size.saturating_mul(10);
+            let len = snap::raw::decompress_len(&compressed)?;
+            if len > max_len {
+                return Err(invalid_data(format!(
+                    "Compressed offsets of {} bytes for a page of {} entries",
+                    len, meta.size
+                )));
+            }
+            let varints = snap::raw::Decoder::new().decompress_vec(&compressed)?;
+            let mut varints = &varints[..];
+            lengths = Some(PageReader::read_lengths(meta, &mut varints)?);
+            if !varints.is_empty() {
+                return Err(invalid_data(
+                    "Trailing bytes after string offsets".to_string(),
+                ));
+            }
+        } else if meta.typ.has_offsets() {
+            let mut offset_bytes = vec![0; (meta.size + 1) * 8];
+            reader.read_exact(&mut offset_bytes)?;
+            offsets = offset_bytes
+                .chunks(8)
+                .map(|word| byteorder::LittleEndian::read_u64(word) as usize)
+                .collect::<Vec<_>>();
+            if offsets[0] != 0 || offsets.windows(2).any(|pair| pair[0] > pair[1]) {
+                return Err(invalid_data("String offsets are not ascending".to_string()));
+            }
+        }
+
+        let encoding = if version >= 3 {
+            Encoding::read_from(reader)?
+        } else {
+            Encoding::Plain
+        };
+        let supported = match encoding {
+            Encoding::Plain => true,
+            Encoding::BitPacked { .. } => meta.typ == Type::Int,
+            Encoding::Rle { runs } => {
+                matches!(meta.typ, Type::Int | Type::Bool)
+                    && runs <= meta.size
+                    && (runs > 0 || meta.size == 0)
+            }
+            // A varint takes at most 10 bytes.
+            Encoding::Delta { len } => {
+                meta.typ == Type::Int
+                    && len
+                        <= meta.size.div_ceil(DELTA_CHECKPOINT_INTERVAL) * DELTA_CHECKPOINT_LEN
+                            + meta.size.saturating_mul(10)
+            }
+            Encoding::Dictionary { values, width } => {
+                meta.typ == Type::Int
+                    && values <= meta.size
+                    && (values > 0 || meta.size == 0)
+                    && width == dictionary_width(values)
+            }
+        };
+        if !supported {
+            return Err(invalid_data(format!(
+                "{:?} encoding on a {:?} page of {} entries",
+                encoding, meta.typ, meta.size
+            )));
+        }
+
+        let compression = if version >= 6 {
+            Compression::from_byte(reader.read_u8()?)?
+        } else {
+            Compression::Snappy
+        };
+
+        let data = PageData {
+            bytes: vec![],
+            nulls,
+            offsets,
+            typ: meta.typ,
+            encoding,
+            scale: 0,
+        };
+        Ok(Some(Sections {
+            data,
+            lengths,
+            compression,
+            stats,
+        }))
+    }
+
+    /// Reads the varint length of every string of the page.
+    fn read_lengths<R: Read>(meta: &PageMeta, reader: &mut R) -> io::Result<Vec<usize>> {
+        (0..meta.size)
+            .map(|_| read_varint(reader).map(|length| length as usize))
+            .collect()
+    }
+
+    /// Reads the null section into a bitmap of `meta.size` entries, or
+    /// `None` for an all-null page, which stores no null section.
+    fn read_null_section<R: Read>(
+        meta: &PageMeta,
+        reader: &mut R,
+        version: u8,
+    ) -> io::Result<Option<BitVec<bv::LittleEndian, u8>>> {
+        let null_encoding = if version >= 2 {
+            NullEncoding::from_byte(reader.read_u8()?)?
+        } else {
+            NullEncoding::Bitmap
+        };
+        if null_encoding == NullEncoding::AllNull {
+            return Ok(None);
+        }
+        if null_encoding == NullEncoding::NoNulls {
+            let mut nulls = BitVec::new();
+            nulls.resize(meta.size, false);
+            return Ok(Some(nulls));
+        }
+
+        let mut size_bytes = [0; 8];
+        reader.read_exact(&mut size_bytes)?;
+        let size = byteorder::LittleEndian::read_u64(&size_bytes);
+
+        // Neither null encoding is ever written larger than the plain bitmap.
+        let bitmap_len = meta.size.div_ceil(8);
+        if size > bitmap_len as u64
+            || (null_encoding == NullEncoding::Bitmap && size != bitmap_len as u64)
+        {
+            return Err(invalid_data(format!(
+                "Null section of {} bytes for a page of {} entries",
+                size, meta.size
+            )));
+        }
+
+        let mut null_bytes = vec![0; size as usize];
+        reader.read_exact(&mut null_bytes)?;
+        let nulls = match null_encoding {
+            NullEncoding::Bitmap => {
+                let mut nulls = BitVec::from_slice(&null_bytes);
+                nulls.truncate(meta.size);
+                nulls
+            }
+            NullEncoding::Roaring => {
+                let sparse = RoaringBitmap::deserialize_from(&null_bytes[..])?;
+                if sparse.max().is_some_and(|max| max as usize >= meta.size) {
+                    return Err(invalid_data(format!(
+                        "Null index out of bounds for a page of {} entries",
+                        meta.size
+                    )));
+                }
+                let mut nulls = BitVec::new();
+                nulls.resize(meta.size, false);
+                for idx in sparse.iter() {
+                    nulls.set(idx as usize, true);
+                }
+                nulls
+            }
+            NullEncoding::AllNull | NullEncoding::NoNulls => unreachable!(),
+        };
+        Ok(Some(nulls))
+    }
+}
+
+/// The sections of a page file before its compressed values.
+struct Sections {
+    /// The nulls, offsets and encoding of the page, with its values still
+    /// empty.
+    data: PageData,
+    /// Delta-encoded string offsets as the string lengths, left out of
+    /// `data` until the caller sums them.
+    lengths: Option<Vec<usize>>,
+    compression: Compression,
+    /// The stats stored by pages of format version 7 and later.
+    stats: Option<PageStats>,
+}
+
+/// A page whose values are decompressed only as far as the highest index
+/// requested so far, for scans that peek at the first rows of each page.
+pub struct LazyPage {
+    data: PageData,
+    /// String lengths not yet summed into `data.offsets`.
+    lengths: Option<Vec<usize>>,
+    payload: Option<Box<dyn Read>>,
+}
+
+impl LazyPage {
+    /// Number of value bytes decompressed so far.
+    pub fn decompressed_len(&self) -> usize {
+        self.data.bytes.len()
+    }
+
+    pub fn is_null(&self, idx: usize) -> bool {
+        self.data.is_null(idx)
+    }
+
+    /// Sums delta-encoded string lengths into the offset index that gives
+    /// `get_string` constant-time access. `get_string` does this on its
+    /// first call, so calling it early only moves the cost up front.
+    pub fn rebuild_offset_index(&mut self) -> Result<()> {
+        if let Some(lengths) = self.lengths.take() {
+            self.data.offsets = prefix_sums(&lengths)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_bool(&mut self, idx: usize) -> Result<Option<bool>> {
+        self.data.check_type(Type::Bool)?;
+        self.data.check_index(idx)?;
+        if !self.data.is_null(idx) {
+            self.decompress_to(self.data.value_end(idx))?;
+        }
+        Ok(self.data.bool_at(idx))
+    }
+
+    pub fn get_int(&mut self, idx: usize) -> Result<Option<i64>> {
+        self.data.check_type(Type::Int)?;
+        self.data.check_index(idx)?;
+        if !self.data.is_null(idx) {
+            self.decompress_to(self.data.value_end(idx))?;
+        }
+        Ok(self.data.int_at(idx))
+    }
+
+    pub fn get_i32(&mut self, idx: usize) -> Result<Option<i32>> {
+        self.data.check_type(Type::Int32)?;
+        self.data.check_index(idx)?;
+        if !self.data.is_null(idx) {
+            self.decompress_to((idx + 1) * 4)?;
+        }
+        Ok(self.data.i32_at(idx))
+    }
+
+    pub fn get_decimal(&mut self, idx: usize) -> Result<Option<Decimal>> {
+        self.data.check_type(Type::Decimal)?;
+        self.data.check_index(idx)?;
+        if !self.data.is_null(idx) {
+            self.decompress_to((idx + 1) * 16)?;
+        }
+        Ok(self.data.decimal_at(idx))
+    }
+
+    pub fn get_timestamp(&mut self, idx: usize) -> Result<Option<i64>> {
+        self.data.check_type(Type::Timestamp)?;
+        self.data.check_index(idx)?;
+        if !self.data.is_null(idx) {
+            self.decompress_to((idx + 1) * 8)?;
+        }
+        Ok(self.data.timestamp_at(idx))
+    }
+
+    pub fn get_u64(&mut self, idx: usize) -> Result<Option<u64>> {
+        self.data.check_type(Type::UInt)?;
+        self.data.check_index(idx)?;
+        if !self.data.is_null(idx) {
+            self.decompress_to((idx + 1) * 8)?;
+        }
+        Ok(self.data.u64_at(idx))
+    }
+
+    pub fn get_float(&mut self, idx: usize) -> Result<Option<f64>> {
+        self.data.check_type(Type::Float)?;
+        self.data.check_index(idx)?;
+        if !self.data.is_null(idx) {
+            self.decompress_to((idx + 1) * 8)?;
+        }
+        Ok(self.data.float_at(idx))
+    }
+
+    pub fn get_f32(&mut self, idx: usize) -> Result<Option<f32>> {
+        self.data.check_type(Type::Float32)?;
+        self.data.check_index(idx)?;
+        if !self.data.is_null(idx) {
+            self.decompress_to((idx + 1) * 4)?;
+        }
+        Ok(self.data.f32_at(idx))
+    }
+
+    pub fn get_string(&mut self, idx: usize) -> Result<Option<String>> {
+        self.data.check_type(Type::String)?;
+        self.data.check_index(idx)?;
+        self.rebuild_offset_index()?;
+        if !self.data.is_null(idx) {
+            self.decompress_to(self.data.offsets[idx + 1])?;
+            self.data.validate_string(idx)?;
+        }
+        Ok(self.data.string_at(idx))
+    }
+
+    pub fn get_binary(&mut self, idx: usize) -> Result<Option<Vec<u8>>> {
+        self.data.check_type(Type::Binary)?;
+        self.data.check_index(idx)?;
+        self.rebuild_offset_index()?;
+        if !self.data.is_null(idx) {
+            self.decompress_to(self.data.offsets[idx + 1])?;
+        }
+        Ok(self.data.binary_at(idx).map(<[u8]>::to_vec))
+    }
+
+    fn decompress_to(&mut self, len: usize) -> io::Result<()> {
+        let missing = len.saturating_sub(self.data.bytes.len());
+        if missing > 0 {
+            if let Some(payload) = &mut self.payload {
+                payload
+                    .by_ref()
+                    .take(missing as u64)
+                    .read_to_end(&mut self.data.bytes)?;
+            }
+            if self.data.bytes.len() < len {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Page values end before the requested index",
+                ));
+            }
+            // Runs and deltas are decompressed all at once, so they are
+            // checked once.
+            match self.data.encoding {
+                Encoding::Rle { .. } => self.data.validate_runs()?,
+                Encoding::Delta { .. } => {
+                    self.data.decode_deltas()?;
+                }
+                Encoding::Dictionary { .. } => self.data.validate_dictionary()?,
+                _ => {}
+            }
+        }
+        Ok(())
     }
 }
 
 pub struct PageWriter {}
 
 impl PageWriter {
-    pub fn write(page: &Page) -> io::Result<()> {
-        let mut file = File::create(&page.meta.path)?;
+    /// Writes `page` to the path in its metadata, compressing its values
+    /// with `compression`.
+    pub fn write(page: &Page, compression: Compression) -> io::Result<()> {
+        let file = File::create(&page.meta.path)?;
+        PageWriter::write_to(page, file, compression)
+    }
 
-        PageWriter::write_nulls(&mut file, &page.data)?;
-        PageWriter::write_offsets(&mut file, &page.data)?;
+    /// Writes the page file contents of `page`, values compressed with
+    /// `compression`, to `writer` instead of to the path in its metadata,
+    /// for pages kept outside the file system. The CRC32 footer covering
+    /// the page is written last.
+    pub fn write_to<W: Write>(page: &Page, writer: W, compression: Compression) -> io::Result<()> {
+        PageWriter::write_page(page, writer, compression, false)
+    }
 
-        let mut compressed_file = snap::Writer::new(file);
-        compressed_file.write_all(&page.data.bytes).unwrap();
-        Ok(())
+    /// Like `write`, but stores the offsets of a string or binary page as
+    /// fixed-width words rather than varint lengths, so `PageReader::map`
+    /// can look up single entries without decoding every offset.
+    pub fn write_fixed_offsets(page: &Page, compression: Compression) -> io::Result<()> {
+        let file = File::create(&page.meta.path)?;
+        PageWriter::write_page(page, file, compression, true)
+    }
+
+    /// Writes the page and the CRC32 footer covering it.
+    fn write_page<W: Write>(
+        page: &Page,
+        writer: W,
+        compression: Compression,
+        fixed_offsets: bool,
+    ) -> io::Result<()> {
+        let mut writer = HashingWriter::new(writer);
+        PageWriter::write_body(page, &mut writer, compression, fixed_offsets)?;
+        PageWriter::write_footer(writer)
     }
 
-    fn write_nulls(file: &mut File, data: &PageData) -> io::Result<()> {
-        let nulls_slice = data.nulls.as_slice();
+    /// Finishes a page written through a `HashingWriter` with the checksum
+    /// of everything written so far.
+    pub(crate) fn write_footer<W: Write>(writer: HashingWriter<W>) -> io::Result<()> {
+        let HashingWriter { mut inner, hasher } = writer;
+        inner.write_u32::<byteorder::LittleEndian>(hasher.finalize())?;
+        inner.flush()
+    }
+
+    fn write_body<W: Write>(
+        page: &Page,
+        mut writer: W,
+        compression: Compression,
+        fixed_offsets: bool,
+    ) -> io::Result<()> {
+        PageWriter::write_header(&mut writer, &page.meta)?;
+        PageWriter::write_scale(&mut writer, &page.data)?;
+        if !PageWriter::write_nulls(&mut writer, &page.data.nulls)? {
+            return Ok(());
+        }
+        PageWriter::write_stats(&mut writer, &page.meta)?;
+        PageWriter::write_offsets(&mut writer, &page.data, fixed_offsets)?;
+        PageWriter::write_encoding(&mut writer, page.data.encoding)?;
+        PageWriter::write_compression(&mut writer, compression)?;
+
+        // The encoder buffers its last frame, so it is finished explicitly:
+        // dropping it would swallow any error flushing that frame.
+        let mut writer = match compression {
+            Compression::Snappy => {
+                let mut compressed_file = snap::write::FrameEncoder::new(writer);
+                compressed_file.write_all(&page.data.bytes)?;
+                compressed_file
+                    .into_inner()
+                    .map_err(|err| io::Error::new(err.error().kind(), err.to_string()))?
+            }
+            Compression::Uncompressed => {
+                writer.write_all(&page.data.bytes)?;
+                writer
+            }
+            Compression::Zstd { level } => {
+                let mut compressed_file = zstd::stream::write::Encoder::new(writer, level)?;
+                compressed_file.write_all(&page.data.bytes)?;
+                compressed_file.finish()?
+            }
+            Compression::Lz4 => {
+                let mut compressed_file = lz4_flex::frame::FrameEncoder::new(writer);
+                compressed_file.write_all(&page.data.bytes)?;
+                compressed_file.finish()?
+            }
+        };
+        writer.flush()
+    }
+
+    /// Writes the magic number, format version and a descriptor of the
+    /// page, so pages can be found again without the collection manifest.
+    pub(crate) fn write_header<W: Write>(file: &mut W, meta: &PageMeta) -> io::Result<()> {
+        file.write_all(&MAGIC)?;
+        file.write_u8(FORMAT_VERSION)?;
+        file.write_u8(meta.typ as u8)?;
+        file.write_all(meta.id.as_bytes())?;
+        file.write_u64::<byteorder::LittleEndian>(meta.offset as u64)?;
+        file.write_u64::<byteorder::LittleEndian>(meta.size as u64)
+    }
+
+    /// Writes the scale of a decimal page right after its descriptor. Pages
+    /// of other types have none.
+    fn write_scale<W: Write>(file: &mut W, data: &PageData) -> io::Result<()> {
+        if data.typ != Type::Decimal {
+            return Ok(());
+        }
+        file.write_u8(data.scale)
+    }
+
+    /// Writes the null section, returning whether the offsets and values
+    /// still need to follow. They are omitted for all-null pages.
+    pub(crate) fn write_nulls<W: Write>(
+        file: &mut W,
+        nulls: &BitVec<bv::LittleEndian, u8>,
+    ) -> io::Result<bool> {
+        if !nulls.is_empty() && nulls.all() {
+            file.write_u8(NullEncoding::AllNull as u8)?;
+            return Ok(false);
+        }
+        if !nulls.any() {
+            file.write_u8(NullEncoding::NoNulls as u8)?;
+            return Ok(true);
+        }
+
+        let sparse = nulls
+            .iter()
+            .enumerate()
+            .filter(|(_, is_null)| *is_null)
+            .map(|(idx, _)| idx as u32)
+            .collect::<RoaringBitmap>();
+
+        if sparse.serialized_size() < nulls.as_slice().len() {
+            let mut sparse_bytes = Vec::with_capacity(sparse.serialized_size());
+            sparse.serialize_into(&mut sparse_bytes)?;
+            file.write_u8(NullEncoding::Roaring as u8)?;
+            PageWriter::write_null_bytes(file, &sparse_bytes)?;
+        } else {
+            file.write_u8(NullEncoding::Bitmap as u8)?;
+            PageWriter::write_null_bytes(file, nulls.as_slice())?;
+        }
+        Ok(true)
+    }
 
+    fn write_null_bytes<W: Write>(file: &mut W, null_bytes: &[u8]) -> io::Result<()> {
         let mut size_bytes = [0; 8];
-        byteorder::LittleEndian::write_u64(&mut size_bytes, nulls_slice.len() as u64);
+        byteorder::LittleEndian::write_u64(&mut size_bytes, null_bytes.len() as u64);
 
         file.write_all(&size_bytes)?;
-        file.write_all(data.nulls.as_slice())?;
+        file.write_all(null_bytes)?;
         Ok(())
     }
 
-    fn write_offsets(file: &mut File, data: &PageData) -> io::Result<()> {
-        let mut bytes = [0; 8];
-        for offset in &data.offsets {
-            byteorder::LittleEndian::write_u64(&mut bytes, *offset as u64);
-            file.write(&bytes)?;
+    /// Writes the stats of the page, which readers take instead of
+    /// recomputing them.
+    pub(crate) fn write_stats<W: Write>(file: &mut W, meta: &PageMeta) -> io::Result<()> {
+        meta.stats.write_to(file, meta.typ)
+    }
+
+    pub(crate) fn write_encoding<W: Write>(file: &mut W, encoding: Encoding) -> io::Result<()> {
+        encoding.write_to(file)
+    }
+
+    pub(crate) fn write_compression<W: Write>(
+        file: &mut W,
+        compression: Compression,
+    ) -> io::Result<()> {
+        file.write_u8(compression.to_byte())
+    }
+
+    /// Marks the string lengths that follow as delta-encoded offsets.
+    pub(crate) fn write_offset_encoding<W: Write>(file: &mut W) -> io::Result<()> {
+        file.write_u8(OffsetEncoding::Delta as u8)
+    }
+
+    /// Writes the string or binary offsets as fixed-width words if `fixed`,
+    /// or else the value lengths as varints, compressed if that makes them
+    /// smaller.
+    fn write_offsets<W: Write>(file: &mut W, data: &PageData, fixed: bool) -> io::Result<()> {
+        if !data.typ.has_offsets() {
+            return Ok(());
+        }
+        if fixed {
+            file.write_u8(OffsetEncoding::Absolute as u8)?;
+            for offset in &data.offsets {
+                file.write_u64::<byteorder::LittleEndian>(*offset as u64)?;
+            }
+            return Ok(());
+        }
+        let mut varints = vec![];
+        for pair in data.offsets.windows(2) {
+            write_varint(&mut varints, (pair[1] - pair[0]) as u64)?;
+        }
+
+        let compressed = snap::raw::Encoder::new().compress_vec(&varints)?;
+        if compressed.len() + 8 < varints.len() {
+            file.write_u8(OffsetEncoding::CompressedDelta as u8)?;
+            file.write_u64::<byteorder::LittleEndian>(compressed.len() as u64)?;
+            file.write_all(&compressed)
+        } else {
+            PageWriter::write_offset_encoding(file)?;
+            file.write_all(&varints)
         }
-        Ok(())
     }
 }
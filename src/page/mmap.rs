@@ -0,0 +1,365 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::str;
+
+use bitvec::prelude as bv;
+use bitvec::vec::BitVec;
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
+use log::debug;
+use memmap2::Mmap;
+
+use super::{
+    invalid_data, truncated, unpack_bits, Compression, Decimal, Encoding, OffsetEncoding, PageMeta,
+    PageReader, PageStats, Type, Value, FORMAT_VERSION, MAGIC, PAGE_FOOTER_LEN,
+};
+use crate::error::{Error, Result};
+
+/// A page read through a memory map of its file. Only the null bitmap is
+/// decoded up front: a lookup reads its value from the mapped payload, or
+/// from the payload decompressed once by `PageReader::map` if the page is
+/// compressed. A string or binary lookup first reads the two offset words
+/// of its entry from the map, so such pages must be written by
+/// `PageWriter::write_fixed_offsets`. Pages of any encoding but `Plain`
+/// cannot be mapped.
+///
+/// The checksum footer is not verified, since that would read the whole
+/// file. Lookups check the offsets they read against the payload instead.
+pub struct MappedPage {
+    typ: Type,
+    scale: u8,
+    map: Mmap,
+    nulls: BitVec<bv::LittleEndian, u8>,
+    /// Where the `len() + 1` offset words of a string or binary page lie in
+    /// the map.
+    offsets: Range<usize>,
+    payload: Payload,
+}
+
+/// The scale, null bitmap, offset words and payload of a mapped page.
+type Layout = (u8, BitVec<bv::LittleEndian, u8>, Range<usize>, Payload);
+
+enum Payload {
+    /// Where the uncompressed payload lies in the map.
+    Mapped(Range<usize>),
+    Decompressed(Vec<u8>),
+}
+
+impl PageReader {
+    /// Maps the page of `meta` into memory. Fails for string and binary
+    /// pages storing their offsets as varint lengths, which is how pages are
+    /// written unless `PageWriter::write_fixed_offsets` is used.
+    pub fn map(meta: &PageMeta) -> Result<MappedPage> {
+        Ok(MappedPage::open(meta, false)?.unwrap())
+    }
+
+    /// Like `map`, but returns `None` rather than decompressing a page or
+    /// failing on a page that cannot be mapped, so the page is only mapped
+    /// if it can be read in place.
+    pub(crate) fn map_in_place(meta: &PageMeta) -> Result<Option<MappedPage>> {
+        MappedPage::open(meta, true)
+    }
+}
+
+impl MappedPage {
+    /// Maps the page of `meta`, decompressing its payload unless `in_place`,
+    /// in which case pages that cannot be read in place give `None`.
+    fn open(meta: &PageMeta, in_place: bool) -> Result<Option<MappedPage>> {
+        debug!("mapping page: {:?}", meta.path);
+        let file = File::open(&meta.path)?;
+        // SAFETY: the map is only read, and page files are never modified in
+        // place: collections write changed pages to new files and only ever
+        // delete old ones, which leaves existing maps intact.
+        let map = unsafe { Mmap::map(&file)? };
+        let layout =
+            MappedPage::layout(meta, &map, in_place).map_err(|err| truncated(&meta.path, err))?;
+        Ok(layout.map(|(scale, nulls, offsets, payload)| MappedPage {
+            typ: meta.typ,
+            scale,
+            map,
+            nulls,
+            offsets,
+            payload,
+        }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.nulls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nulls.is_empty()
+    }
+
+    pub fn typ(&self) -> Type {
+        self.typ
+    }
+
+    pub fn is_null(&self, idx: usize) -> bool {
+        self.nulls[idx]
+    }
+
+    /// Bytes the page holds in memory rather than in the map: the null
+    /// bitmap and, if the page is compressed, its decompressed payload.
+    pub fn resident_len(&self) -> usize {
+        let decompressed = match &self.payload {
+            Payload::Mapped(_) => 0,
+            Payload::Decompressed(bytes) => bytes.len(),
+        };
+        self.nulls.as_slice().len() + decompressed
+    }
+
+    pub fn get_bool(&self, idx: usize) -> Result<Option<bool>> {
+        self.check_type(Type::Bool)?;
+        if !self.check_index(idx)? {
+            return Ok(None);
+        }
+        Ok(Some(unpack_bits(self.payload(), idx, 1) == 1))
+    }
+
+    pub fn get_int(&self, idx: usize) -> Result<Option<i64>> {
+        self.check_type(Type::Int)?;
+        Ok(self.fixed(idx, 8)?.map(LittleEndian::read_i64))
+    }
+
+    pub fn get_i32(&self, idx: usize) -> Result<Option<i32>> {
+        self.check_type(Type::Int32)?;
+        Ok(self.fixed(idx, 4)?.map(LittleEndian::read_i32))
+    }
+
+    pub fn get_u64(&self, idx: usize) -> Result<Option<u64>> {
+        self.check_type(Type::UInt)?;
+        Ok(self.fixed(idx, 8)?.map(LittleEndian::read_u64))
+    }
+
+    /// Milliseconds since the Unix epoch.
+    pub fn get_timestamp(&self, idx: usize) -> Result<Option<i64>> {
+        self.check_type(Type::Timestamp)?;
+        Ok(self.fixed(idx, 8)?.map(LittleEndian::read_i64))
+    }
+
+    pub fn get_float(&self, idx: usize) -> Result<Option<f64>> {
+        self.check_type(Type::Float)?;
+        Ok(self.fixed(idx, 8)?.map(LittleEndian::read_f64))
+    }
+
+    pub fn get_f32(&self, idx: usize) -> Result<Option<f32>> {
+        self.check_type(Type::Float32)?;
+        Ok(self.fixed(idx, 4)?.map(LittleEndian::read_f32))
+    }
+
+    pub fn get_decimal(&self, idx: usize) -> Result<Option<Decimal>> {
+        self.check_type(Type::Decimal)?;
+        Ok(self.fixed(idx, 16)?.map(|bytes| Decimal {
+            value: LittleEndian::read_i128(bytes),
+            scale: self.scale,
+        }))
+    }
+
+    pub fn get_string(&self, idx: usize) -> Result<Option<String>> {
+        Ok(self.get_str(idx)?.map(str::to_owned))
+    }
+
+    /// Like `get_string`, but borrows the string from the page instead of
+    /// copying it.
+    pub fn get_str(&self, idx: usize) -> Result<Option<&str>> {
+        self.check_type(Type::String)?;
+        match self.variable(idx)? {
+            Some(bytes) => Ok(Some(str::from_utf8(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_binary(&self, idx: usize) -> Result<Option<&[u8]>> {
+        self.check_type(Type::Binary)?;
+        self.variable(idx)
+    }
+
+    /// Reads an entry of any type, dispatching on the page's type.
+    pub fn get_value(&self, idx: usize) -> Result<Value> {
+        let value = match self.typ {
+            Type::Bool => self.get_bool(idx)?.map(Value::Bool),
+            Type::Int => self.get_int(idx)?.map(Value::Int),
+            Type::Int32 => self.get_i32(idx)?.map(|value| Value::Int(value.into())),
+            Type::UInt => self.get_u64(idx)?.map(Value::UInt),
+            Type::Timestamp => self.get_timestamp(idx)?.map(Value::Timestamp),
+            Type::Decimal => self.get_decimal(idx)?.map(Value::Decimal),
+            Type::Float => self.get_float(idx)?.map(Value::Float),
+            Type::Float32 => self.get_f32(idx)?.map(|value| Value::Float(value.into())),
+            Type::String => self.get_string(idx)?.map(Value::String),
+            Type::Binary => self
+                .get_binary(idx)?
+                .map(|value| Value::Binary(value.to_vec())),
+        };
+        Ok(value.unwrap_or(Value::Null))
+    }
+
+    fn check_type(&self, expected: Type) -> Result<()> {
+        if self.typ != expected {
+            return Err(Error::TypeMismatch {
+                expected,
+                found: self.typ,
+            });
+        }
+        Ok(())
+    }
+
+    /// Fails if `idx` is past the end, or else returns whether entry `idx`
+    /// holds a value.
+    fn check_index(&self, idx: usize) -> Result<bool> {
+        if idx >= self.len() {
+            return Err(Error::OutOfBounds {
+                index: idx,
+                size: self.len(),
+            });
+        }
+        Ok(!self.nulls[idx])
+    }
+
+    fn payload(&self) -> &[u8] {
+        match &self.payload {
+            Payload::Mapped(range) => &self.map[range.clone()],
+            Payload::Decompressed(bytes) => bytes,
+        }
+    }
+
+    /// The `width` bytes of entry `idx` of a page of fixed-width values.
+    fn fixed(&self, idx: usize, width: usize) -> Result<Option<&[u8]>> {
+        if !self.check_index(idx)? {
+            return Ok(None);
+        }
+        Ok(Some(&self.payload()[idx * width..(idx + 1) * width]))
+    }
+
+    /// The bytes of entry `idx` of a string or binary page, between the
+    /// offset words it starts and ends at.
+    fn variable(&self, idx: usize) -> Result<Option<&[u8]>> {
+        if !self.check_index(idx)? {
+            return Ok(None);
+        }
+        let word = self.offsets.start + idx * 8;
+        let start = LittleEndian::read_u64(&self.map[word..word + 8]) as usize;
+        let end = LittleEndian::read_u64(&self.map[word + 8..word + 16]) as usize;
+        let payload = self.payload();
+        if start > end || end > payload.len() {
+            return Err(Error::Corrupt(format!(
+                "Entry {} spans bytes {} to {} of a payload of {}",
+                idx,
+                start,
+                end,
+                payload.len()
+            )));
+        }
+        Ok(Some(&payload[start..end]))
+    }
+
+    /// Reads the sections of the mapped page file `bytes` up to its payload,
+    /// returning the scale, the null bitmap and where the offset words and
+    /// payload lie. A compressed payload is decompressed, unless `in_place`,
+    /// which gives `None` for pages that cannot be read in place.
+    fn layout(meta: &PageMeta, bytes: &[u8], in_place: bool) -> io::Result<Option<Layout>> {
+        let mut reader = io::Cursor::new(bytes);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        let version = reader.read_u8()?;
+        // Earlier pages record neither a descriptor nor how their offsets are
+        // stored.
+        if magic != MAGIC || !(5..=FORMAT_VERSION).contains(&version) {
+            return Err(invalid_data(format!(
+                "{:?} is not a page of format version 5 to {}, which can be mapped",
+                meta.path, FORMAT_VERSION
+            )));
+        }
+        let body_len = if version >= 9 {
+            bytes.len().saturating_sub(PAGE_FOOTER_LEN as usize)
+        } else {
+            bytes.len()
+        };
+        let scale = PageReader::check_descriptor(meta, &mut reader)?;
+        let nulls = match PageReader::read_null_section(meta, &mut reader, version)? {
+            Some(nulls) => nulls,
+            None => {
+                let mut nulls = BitVec::new();
+                nulls.resize(meta.size, true);
+                return Ok(Some((scale, nulls, 0..0, Payload::Mapped(0..0))));
+            }
+        };
+        if version >= 7 {
+            PageStats::read_from(&mut reader, meta.typ, meta.size)?;
+        }
+
+        let eof = || io::Error::new(io::ErrorKind::UnexpectedEof, "Page values end early");
+        let (offsets, values_len) = if meta.typ.has_offsets() {
+            if OffsetEncoding::from_byte(reader.read_u8()?)? != OffsetEncoding::Absolute {
+                if in_place {
+                    return Ok(None);
+                }
+                return Err(invalid_data(format!(
+                    "Page {:?} stores its offsets as varint lengths and cannot be mapped",
+                    meta.path
+                )));
+            }
+            let start = reader.position() as usize;
+            let end = meta
+                .size
+                .checked_add(1)
+                .and_then(|words| words.checked_mul(8))
+                .and_then(|len| start.checked_add(len))
+                .filter(|end| *end <= body_len)
+                .ok_or_else(eof)?;
+            reader.set_position(end as u64);
+            (
+                start..end,
+                LittleEndian::read_u64(&bytes[end - 8..end]) as usize,
+            )
+        } else {
+            let values_len = match meta.typ {
+                Type::Bool => Some(meta.size.div_ceil(8)),
+                Type::Int32 | Type::Float32 => meta.size.checked_mul(4),
+                Type::Decimal => meta.size.checked_mul(16),
+                _ => meta.size.checked_mul(8),
+            };
+            (0..0, values_len.ok_or_else(eof)?)
+        };
+
+        let encoding = Encoding::read_from(&mut reader)?;
+        if encoding != Encoding::Plain {
+            if in_place {
+                return Ok(None);
+            }
+            return Err(invalid_data(format!(
+                "{:?} encoding on a {:?} page cannot be mapped",
+                encoding, meta.typ
+            )));
+        }
+        let compression = if version >= 6 {
+            Compression::from_byte(reader.read_u8()?)?
+        } else {
+            Compression::Snappy
+        };
+        let payload_start = (reader.position() as usize).min(body_len);
+        let payload = match compression {
+            Compression::Uncompressed => Payload::Mapped(payload_start..body_len),
+            _ if in_place => return Ok(None),
+            _ => {
+                let mut decompressed = vec![];
+                compression
+                    .decoder(&bytes[payload_start..body_len])?
+                    .take(values_len as u64 + 1)
+                    .read_to_end(&mut decompressed)?;
+                Payload::Decompressed(decompressed)
+            }
+        };
+        let payload_len = match &payload {
+            Payload::Mapped(range) => range.len(),
+            Payload::Decompressed(bytes) => bytes.len(),
+        };
+        if payload_len != values_len {
+            return Err(invalid_data(format!(
+                "Page values hold {} bytes but expect {}",
+                payload_len, values_len
+            )));
+        }
+        Ok(Some((scale, nulls, offsets, payload)))
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+
+use crate::cache::PageCache;
+use crate::collection::Collection;
+use crate::Error;
+
+/// Named collections of equal size, read together row by row.
+#[derive(Clone, Default)]
+pub struct Table {
+    columns: BTreeMap<String, Collection>,
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Table::default()
+    }
+
+    /// Adds or replaces the column `name`. Every column of a table must hold
+    /// the same number of rows.
+    pub fn add_column(&mut self, name: &str, collection: Collection) {
+        assert!(self
+            .columns
+            .values()
+            .all(|column| column.size() == collection.size()));
+        self.columns.insert(name.to_string(), collection);
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Collection> {
+        self.columns.get(name)
+    }
+
+    pub fn column_names(&self) -> impl Iterator<Item = &str> {
+        self.columns.keys().map(|name| name.as_str())
+    }
+
+    pub fn size(&self) -> usize {
+        self.columns
+            .values()
+            .next()
+            .map_or(0, |column| column.size())
+    }
+
+    /// Sums the `val_col` entries of every row grouped by its `key_col`
+    /// entry, skipping rows where either is null. The sums are `i128`, so
+    /// fewer than 2^64 rows can never overflow them. Fails with
+    /// `Error::UnknownColumn` if either column is missing.
+    pub fn group_sum_int(
+        &self,
+        key_col: &str,
+        val_col: &str,
+        cache: &mut PageCache,
+    ) -> crate::Result<BTreeMap<i64, i128>> {
+        let keys = self.expect_column(key_col)?;
+        let values = self.expect_column(val_col)?;
+
+        let mut sums = BTreeMap::new();
+        for entry in keys.zip_int_iter(values, cache)? {
+            if let (Some(key), Some(value)) = entry? {
+                *sums.entry(key).or_insert(0i128) += i128::from(value);
+            }
+        }
+        Ok(sums)
+    }
+
+    fn expect_column(&self, name: &str) -> crate::Result<&Collection> {
+        self.column(name)
+            .ok_or_else(|| Error::UnknownColumn(name.to_string()))
+    }
+}
@@ -0,0 +1,47 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where an operation keeps its scratch files. Unless overridden they go in
+/// the data directory the operation writes to.
+#[derive(Clone, Debug, Default)]
+pub struct WorkDir {
+    path: Option<PathBuf>,
+}
+
+impl WorkDir {
+    pub fn new(path: &Path) -> Self {
+        WorkDir {
+            path: Some(path.to_path_buf()),
+        }
+    }
+
+    /// The scratch directory of an operation writing to `data_dir`.
+    pub fn resolve<'a>(&'a self, data_dir: &'a Path) -> &'a Path {
+        self.path.as_deref().unwrap_or(data_dir)
+    }
+}
+
+/// Scratch files that are deleted when the guard is dropped, whether the
+/// operation using them succeeded or failed part way.
+#[derive(Default)]
+pub(crate) struct SpillFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl SpillFiles {
+    /// Registers `path` for deletion and returns it.
+    pub fn add(&mut self, path: PathBuf) -> PathBuf {
+        self.paths.push(path.clone());
+        path
+    }
+}
+
+impl Drop for SpillFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            // The file may never have been created if the operation failed
+            // early, so there is nothing to report.
+            let _ = fs::remove_file(path);
+        }
+    }
+}
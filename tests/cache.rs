@@ -0,0 +1,208 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use eadb::cache::{CacheStats, LoadProfile, PageCache, SharedPageCache};
+use eadb::collection::Collection;
+use eadb::page::{PageData, Type, Value};
+use eadb::Error;
+
+fn tmp() -> PathBuf {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// An int collection with one page per entry of `pages`, each holding
+/// `len` copies of it.
+fn pages_of(dir: &Path, pages: &[i64], len: usize) -> Collection {
+    let mut collection = Collection::empty(Type::Int);
+    for &value in pages {
+        collection
+            .append_page(dir, PageData::from_ints(&vec![Some(value); len]).unwrap())
+            .unwrap();
+    }
+    collection
+}
+
+#[test]
+fn cache_of_one_page_evicts_the_previous_page() {
+    let dir = tmp();
+    let collection = pages_of(&dir, &[1, 2], 1);
+    let mut cache = PageCache::with_capacity(1);
+    let evicted = Arc::new(Mutex::new(vec![]));
+    let on_evict = evicted.clone();
+    cache.set_on_evict(move |_, page| on_evict.lock().unwrap().push(page.get_int(0).unwrap()));
+
+    assert_eq!(collection.get_int(&mut cache, 0).unwrap(), Some(1));
+    assert!(evicted.lock().unwrap().is_empty());
+    assert_eq!(collection.get_int(&mut cache, 1).unwrap(), Some(2));
+    assert_eq!(*evicted.lock().unwrap(), vec![Some(1)]);
+    assert_eq!(cache.capacity(), 1);
+}
+
+#[test]
+fn on_evict_receives_the_least_recently_used_key() {
+    let dir = tmp();
+    let collection = pages_of(&dir, &[1, 2, 3, 4], 1);
+    let key = |idx| (collection.namespace(), collection.page_of(idx).unwrap().0);
+    let mut cache = PageCache::with_capacity(2);
+    let evicted = Arc::new(Mutex::new(vec![]));
+    let on_evict = evicted.clone();
+    cache.set_on_evict(move |key, _| on_evict.lock().unwrap().push(key));
+
+    collection.get_int(&mut cache, 0).unwrap();
+    collection.get_int(&mut cache, 1).unwrap();
+    // Touching page 0 leaves page 1 as the least recently used.
+    collection.get_int(&mut cache, 0).unwrap();
+    assert!(evicted.lock().unwrap().is_empty());
+
+    collection.get_int(&mut cache, 2).unwrap();
+    assert_eq!(*evicted.lock().unwrap(), vec![key(1)]);
+    collection.get_int(&mut cache, 3).unwrap();
+    assert_eq!(*evicted.lock().unwrap(), vec![key(1), key(0)]);
+    assert_eq!(cache.stats().evictions, 2);
+}
+
+#[test]
+#[should_panic(expected = "PageCache capacity must be at least 1")]
+fn cache_of_no_pages_is_rejected() {
+    PageCache::with_capacity(0);
+}
+
+#[test]
+#[should_panic(expected = "SharedPageCache capacity must be at least 1")]
+fn shared_cache_of_no_pages_is_rejected() {
+    SharedPageCache::with_capacity(0);
+}
+
+#[test]
+fn byte_budget_evicts_at_the_threshold() {
+    let dir = tmp();
+    let mut collection = pages_of(&dir, &[0, 1, 2, 3], 100);
+    collection
+        .append_page(&dir, PageData::from_ints(&vec![Some(9); 1000]).unwrap())
+        .unwrap();
+    let page_len = PageData::from_ints(&vec![Some(0); 100])
+        .unwrap()
+        .decoded_len();
+    let mut cache = PageCache::with_byte_budget(page_len * 2);
+    let evictions = Arc::new(Mutex::new(0));
+    let on_evict = evictions.clone();
+    cache.set_on_evict(move |_, _| *on_evict.lock().unwrap() += 1);
+
+    collection.get_int(&mut cache, 0).unwrap();
+    collection.get_int(&mut cache, 100).unwrap();
+    assert_eq!(*evictions.lock().unwrap(), 0);
+    assert_eq!(cache.decoded_len(), page_len * 2);
+    collection.get_int(&mut cache, 200).unwrap();
+    assert_eq!(*evictions.lock().unwrap(), 1);
+    assert_eq!(cache.decoded_len(), page_len * 2);
+
+    // A page larger than the whole budget still loads, alone.
+    assert_eq!(collection.get_int(&mut cache, 400).unwrap(), Some(9));
+    assert_eq!(*evictions.lock().unwrap(), 3);
+    assert!(cache.decoded_len() > page_len * 2);
+    collection.get_int(&mut cache, 0).unwrap();
+    assert_eq!(*evictions.lock().unwrap(), 4);
+    assert_eq!(cache.decoded_len(), page_len);
+}
+
+#[test]
+fn stats_count_a_known_access_pattern() {
+    let dir = tmp();
+    let collection = pages_of(&dir, &[0, 1, 2], 2);
+    let mut cache = PageCache::with_capacity(2);
+    for &idx in &[0, 1, 2, 0, 4, 2] {
+        collection.get_int(&mut cache, idx).unwrap();
+    }
+    assert_eq!(
+        cache.stats(),
+        CacheStats {
+            hits: 2,
+            misses: 4,
+            evictions: 2
+        }
+    );
+
+    cache.reset_stats();
+    assert_eq!(cache.stats(), CacheStats::default());
+    collection.get_int(&mut cache, 5).unwrap();
+    assert_eq!(
+        cache.stats(),
+        CacheStats {
+            hits: 1,
+            misses: 0,
+            evictions: 0
+        }
+    );
+}
+
+#[test]
+fn shared_cache_serves_concurrent_readers() {
+    let dir = tmp();
+    let mut collection = Collection::empty(Type::Int);
+    for page in 0..8 {
+        let values = (0..50)
+            .map(|idx| {
+                if idx % 7 == 0 {
+                    None
+                } else {
+                    Some(page * 50 + idx)
+                }
+            })
+            .collect::<Vec<_>>();
+        collection
+            .append_page(&dir, PageData::from_ints(&values).unwrap())
+            .unwrap();
+    }
+
+    // Fewer pages than the threads touch, so they keep evicting each other's.
+    let cache = SharedPageCache::with_capacity(3);
+    thread::scope(|scope| {
+        for start in 0..6 {
+            let (collection, cache) = (&collection, &cache);
+            scope.spawn(move || {
+                for step in 3..6 {
+                    for idx in (start * 30..400).step_by(step) {
+                        let expected = if idx % 50 % 7 == 0 {
+                            None
+                        } else {
+                            Some(idx as i64)
+                        };
+                        assert_eq!(collection.get_int_shared(cache, idx).unwrap(), expected);
+                        assert_eq!(
+                            collection.get_value_shared(cache, idx).unwrap(),
+                            expected.map_or(Value::Null, Value::Int)
+                        );
+                    }
+                }
+            });
+        }
+    });
+    assert!(matches!(
+        collection.get_int_shared(&cache, 400),
+        Err(Error::OutOfBounds { .. })
+    ));
+}
+
+#[test]
+fn benchmark_load_measures_without_caching() {
+    let dir = tmp();
+    let mut collection = Collection::empty(Type::Int);
+    let metas = (0..3)
+        .map(|value| {
+            collection
+                .append_page(&dir, PageData::from_ints(&vec![Some(value); 1000]).unwrap())
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let cache = PageCache::new();
+
+    let profile = cache.benchmark_load(&metas).unwrap();
+    assert!(profile.avg_load_us > 0.0);
+    assert!(profile.bytes_per_us > 0.0);
+    assert_eq!(cache.stats(), CacheStats::default());
+    assert_eq!(cache.benchmark_load(&[]).unwrap(), LoadProfile::default());
+}
@@ -0,0 +1,1459 @@
+use std::fs;
+use std::io::Write;
+use std::ops::Bound::{Excluded, Included};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use eadb::cache::PageCache;
+use eadb::collection::{Collection, NullPolicy};
+use eadb::mvcc::SharedCollection;
+use eadb::page::{
+    Bound, Compression, Encoding, Page, PageData, PageMeta, PageReader, PageWriter, Type, Value,
+};
+use eadb::work::WorkDir;
+use eadb::Error;
+use uuid::Uuid;
+
+fn tmp() -> PathBuf {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn string_bound_folds_page_bounds() {
+    let dir = tmp();
+    let mut collection = Collection::empty(Type::String);
+    for page in &[
+        vec![Some("kiwi"), None, Some("fig")],
+        vec![Some("plum"), Some("lime")],
+        vec![None, Some("apple")],
+    ] {
+        collection
+            .append_page(&dir, PageData::from_strings(page).unwrap())
+            .unwrap();
+    }
+    let expected = Some(Bound {
+        min: "apple".to_string(),
+        max: "plum".to_string(),
+    });
+    assert_eq!(collection.string_bound(), expected);
+
+    collection.write_manifest(&dir).unwrap();
+    assert_eq!(Collection::open(&dir).unwrap().string_bound(), expected);
+}
+
+#[test]
+fn is_globally_sorted_checks_page_bounds() {
+    let cases: Vec<(Vec<i64>, bool)> = vec![
+        ((0..30).collect(), true),
+        ((0..10).chain(5..15).collect(), false),
+        ((0..30).rev().collect(), false),
+    ];
+    for (values, expected) in cases {
+        let dir = tmp();
+        let collection = Collection::from_ints(&dir, values.into_iter().map(Some), 10).unwrap();
+        assert_eq!(collection.is_globally_sorted(), expected);
+
+        collection.write_manifest(&dir).unwrap();
+        assert_eq!(
+            Collection::open(&dir).unwrap().is_globally_sorted(),
+            expected
+        );
+    }
+}
+
+/// An int collection of `0..12` in pages of four, reopened from its manifest
+/// so that page skipping relies on the persisted stats.
+fn reopened_ints() -> Collection {
+    let dir = tmp();
+    let collection = Collection::from_ints(&dir, (0..12).map(Some), 4).unwrap();
+    collection.write_manifest(&dir).unwrap();
+    Collection::open(&dir).unwrap()
+}
+
+#[test]
+fn between_int_skips_pages_outside_exclusive_bounds() {
+    let collection = reopened_ints();
+    let mut cache = PageCache::new();
+    let range = collection
+        .between_int(&mut cache, Excluded(3), Included(7))
+        .unwrap();
+    assert_eq!(range, vec![(4, 4), (5, 5), (6, 6), (7, 7)]);
+    assert_eq!(cache.stats().misses, 1);
+}
+
+#[test]
+fn int_range_only_reads_overlapping_pages() {
+    let collection = reopened_ints();
+    let mut cache = PageCache::new();
+    assert_eq!(collection.int_range(&mut cache, 5, 6).unwrap(), vec![5, 6]);
+    assert_eq!(cache.stats().misses, 1);
+}
+
+#[test]
+fn top_k_int_skips_pages_that_cannot_enter() {
+    let dir = tmp();
+    let collection =
+        Collection::from_ints(&dir, vec![Some(5), Some(1), Some(9), Some(3)], 2).unwrap();
+    let mut cache = PageCache::new();
+    assert_eq!(
+        collection.top_k_int(&mut cache, 2).unwrap(),
+        vec![(2, 9), (0, 5)]
+    );
+
+    let dir = tmp();
+    let collection = Collection::from_ints(&dir, (0..12).rev().map(Some), 4).unwrap();
+    collection.write_manifest(&dir).unwrap();
+    let collection = Collection::open(&dir).unwrap();
+    let mut cache = PageCache::new();
+    assert_eq!(
+        collection.top_k_int(&mut cache, 2).unwrap(),
+        vec![(0, 11), (1, 10)]
+    );
+    assert_eq!(cache.stats().misses, 1);
+}
+
+#[test]
+fn variance_float_skips_all_null_pages() {
+    let dir = tmp();
+    let mut collection = Collection::empty(Type::Float);
+    for page in &[
+        vec![Some(1.0), None],
+        vec![None, None],
+        vec![Some(3.0), Some(5.0)],
+    ] {
+        collection
+            .append_page(&dir, PageData::from_floats(page).unwrap())
+            .unwrap();
+    }
+    collection.write_manifest(&dir).unwrap();
+
+    let collection = Collection::open(&dir).unwrap();
+    let mut cache = PageCache::new();
+    assert_eq!(
+        collection.variance_float(&mut cache).unwrap(),
+        Some(8.0 / 3.0)
+    );
+    assert_eq!(cache.stats().misses, 2);
+}
+
+#[test]
+fn page_averages_float_skips_all_null_pages() {
+    let dir = tmp();
+    let mut collection = Collection::empty(Type::Float);
+    for page in &[
+        vec![Some(1.0), None, Some(2.0)],
+        vec![None, None],
+        vec![Some(-4.0)],
+    ] {
+        collection
+            .append_page(&dir, PageData::from_floats(page).unwrap())
+            .unwrap();
+    }
+    collection.write_manifest(&dir).unwrap();
+
+    let collection = Collection::open(&dir).unwrap();
+    let mut cache = PageCache::new();
+    let averages = collection
+        .page_averages_float(&mut cache)
+        .unwrap()
+        .into_iter()
+        .map(|(_, average)| average)
+        .collect::<Vec<_>>();
+    assert_eq!(averages, vec![Some(1.5), None, Some(-4.0)]);
+    assert_eq!(cache.stats().misses, 2);
+}
+
+#[test]
+fn stats_json_lists_every_page() {
+    let dir = tmp();
+    let collection =
+        Collection::from_ints(&dir, vec![Some(-1), None, Some(4), Some(7)], 3).unwrap();
+    let json = collection.stats_json();
+    assert_eq!(json.matches("\"key\":").count(), 2);
+    assert!(json.contains(
+        "\"size\":3,\"offset\":0,\"contains_nulls\":true,\"bound\":{\"min\":-1,\"max\":4}"
+    ));
+    assert!(json.contains(
+        "\"size\":1,\"offset\":3,\"contains_nulls\":false,\"bound\":{\"min\":7,\"max\":7}"
+    ));
+}
+
+#[test]
+fn union_nulls_reads_only_null_sections() {
+    let dir = tmp();
+    let left = Collection::from_ints(&dir, vec![None, Some(1), Some(2), Some(3), None], 2).unwrap();
+    let right =
+        Collection::from_bools(&dir, vec![Some(true), None, Some(false), None, None], 3).unwrap();
+    let mut cache = PageCache::new();
+    let nulls = Collection::union_nulls(&[&left, &right], &mut cache).unwrap();
+    assert_eq!(
+        nulls.iter().collect::<Vec<_>>(),
+        vec![true, true, false, true, true]
+    );
+    assert_eq!(cache.stats().misses, 0);
+}
+
+/// An int collection holding `0..9` in pages of three, two and four entries.
+fn uneven_pages(dir: &Path) -> Collection {
+    let mut collection = Collection::empty(Type::Int);
+    for page in &[0..3, 3..5, 5..9] {
+        let values = page.clone().map(Some).collect::<Vec<_>>();
+        collection
+            .append_page(dir, PageData::from_ints(&values).unwrap())
+            .unwrap();
+    }
+    collection
+}
+
+#[test]
+fn get_int_finds_entries_of_uneven_pages() {
+    let dir = tmp();
+    let collection = uneven_pages(&dir);
+    assert_eq!(collection.page_of(5), Some(((collection.id(), 2), 0)));
+
+    let mut cache = PageCache::new();
+    for idx in 0..9 {
+        assert_eq!(
+            collection.get_int(&mut cache, idx).unwrap(),
+            Some(idx as i64)
+        );
+    }
+    assert!(matches!(
+        collection.get_int(&mut cache, 9),
+        Err(Error::OutOfBounds { index: 9, size: 9 })
+    ));
+}
+
+#[test]
+fn get_int_over_ten_thousand_pages() {
+    let dir = tmp();
+    let collection = Collection::from_ints(&dir, (0..20_000).map(Some), 2).unwrap();
+    let mut cache = PageCache::new();
+    for idx in (0..20_000).step_by(7) {
+        assert_eq!(
+            collection.page_of(idx),
+            Some(((collection.id(), idx / 2), idx % 2))
+        );
+        assert_eq!(
+            collection.get_int(&mut cache, idx).unwrap(),
+            Some(idx as i64)
+        );
+    }
+    assert_eq!(collection.page_of(20_000), None);
+}
+
+#[test]
+fn get_int_past_the_end_is_out_of_bounds() {
+    let dir = tmp();
+    let collection = Collection::from_ints(&dir, vec![Some(1), None, Some(3)], 2).unwrap();
+    let mut cache = PageCache::new();
+    assert_eq!(collection.get_int(&mut cache, 1).unwrap(), None);
+    assert_eq!(collection.get_int(&mut cache, 2).unwrap(), Some(3));
+    match collection.get_int(&mut cache, 3) {
+        Err(Error::OutOfBounds { index: 3, size: 3 }) => {}
+        other => panic!("expected out of bounds, got {:?}", other),
+    }
+
+    let data = PageData::from_ints(&[Some(1)]).unwrap();
+    assert!(matches!(
+        data.get_int(1),
+        Err(Error::OutOfBounds { index: 1, size: 1 })
+    ));
+}
+
+#[test]
+fn get_string_on_an_int_collection_is_a_type_mismatch() {
+    let dir = tmp();
+    let collection = Collection::from_ints(&dir, vec![Some(1), None], 2).unwrap();
+    let mut cache = PageCache::new();
+    match collection.get_string(&mut cache, 0) {
+        Err(Error::TypeMismatch {
+            expected: Type::String,
+            found: Type::Int,
+        }) => {}
+        other => panic!("expected a type mismatch, got {:?}", other),
+    }
+    assert_eq!(collection.get_int(&mut cache, 0).unwrap(), Some(1));
+
+    let meta = PageMeta::new(Type::String, &dir.join("page"), 0, 1);
+    let data = PageData::from_ints(&[Some(1)]).unwrap();
+    assert!(matches!(
+        data.get_str(0),
+        Err(Error::TypeMismatch {
+            expected: Type::String,
+            found: Type::Int,
+        })
+    ));
+    assert!(matches!(
+        Page::new(&meta, data),
+        Err(Error::TypeMismatch {
+            expected: Type::String,
+            found: Type::Int,
+        })
+    ));
+}
+
+#[test]
+fn overwrite_history_survives_a_rebuilt_manifest() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let mut collection = Collection::empty(Type::Int);
+    collection
+        .append_page(&dir, PageData::from_ints(&[Some(1), Some(2)]).unwrap())
+        .unwrap();
+    collection
+        .append_page(&dir, PageData::from_ints(&[Some(3)]).unwrap())
+        .unwrap();
+    let before = collection.version();
+    collection
+        .overwrite_int(&dir, &mut cache, 1, Some(20))
+        .unwrap();
+
+    assert_eq!(
+        collection.get_int_at(&mut cache, 1, before).unwrap(),
+        Some(2)
+    );
+    assert_eq!(
+        collection
+            .get_int_at(&mut cache, 1, collection.version())
+            .unwrap(),
+        Some(20)
+    );
+    assert_eq!(collection.get_int(&mut cache, 1).unwrap(), Some(20));
+
+    // The replaced file is out of the way of a rebuild even before pruning.
+    Collection::rebuild_manifest(&dir).unwrap();
+    assert_eq!(Collection::open(&dir).unwrap().size(), 3);
+
+    assert_eq!(collection.prune_history(0).unwrap(), 1);
+    Collection::rebuild_manifest(&dir).unwrap();
+    let rebuilt = Collection::open(&dir).unwrap();
+    let values = (0..3)
+        .map(|idx| rebuilt.get_int(&mut cache, idx).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![Some(1), Some(20), Some(3)]);
+}
+
+#[test]
+fn sealed_collection_rejects_changes_but_reads() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let mut collection = Collection::empty(Type::Int);
+    collection
+        .append_page(&dir, PageData::from_ints(&[Some(1), Some(2)]).unwrap())
+        .unwrap();
+    collection.seal();
+
+    let err = collection
+        .append_page(&dir, PageData::from_ints(&[Some(3)]).unwrap())
+        .err()
+        .unwrap();
+    assert!(matches!(Error::from(err), Error::Sealed(id) if id == collection.id()));
+    let err = collection
+        .overwrite_int(&dir, &mut cache, 0, None)
+        .unwrap_err();
+    assert!(matches!(Error::from(err), Error::Sealed(_)));
+    let err = collection.truncate(&dir, 1).unwrap_err();
+    assert!(matches!(Error::from(err), Error::Sealed(_)));
+    assert_eq!(collection.size(), 2);
+    assert_eq!(collection.get_int(&mut cache, 1).unwrap(), Some(2));
+
+    collection.write_manifest(&dir).unwrap();
+    fs::remove_file(dir.join("MANIFEST")).unwrap();
+    Collection::rebuild_manifest(&dir).unwrap();
+    let rebuilt = Collection::open(&dir).unwrap();
+    assert!(rebuilt.is_sealed());
+    assert_eq!(rebuilt.get_int(&mut cache, 0).unwrap(), Some(1));
+}
+
+#[test]
+fn reads_of_a_truncated_page_fail_instead_of_panicking() {
+    let dir = tmp();
+    let mut collection = Collection::empty(Type::Int);
+    let meta = collection
+        .append_page(&dir, PageData::from_ints(&[Some(1), None]).unwrap())
+        .unwrap();
+    let bytes = fs::read(&meta.path).unwrap();
+    fs::write(&meta.path, &bytes[..3]).unwrap();
+
+    let mut cache = PageCache::new();
+    assert!(collection.get_int(&mut cache, 0).is_err());
+    assert!(collection.get_value(&mut cache, 1).is_err());
+    assert!(collection.int_iter(&mut cache).next().unwrap().is_err());
+    assert!(collection.to_vec_int(&mut cache).is_err());
+    assert!(collection.top_k_int(&mut cache, 1).is_err());
+    assert!(Collection::union_nulls(&[&collection], &mut cache).is_err());
+}
+
+#[test]
+fn coalesce_null_pages_uses_null_counts_and_drops_history() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let mut collection = Collection::empty(Type::Int);
+    let mut paths = vec![];
+    for page in &[
+        vec![Some(1)],
+        vec![None, Some(2)],
+        vec![None],
+        vec![Some(3)],
+    ] {
+        let meta = collection
+            .append_page(&dir, PageData::from_ints(page).unwrap())
+            .unwrap();
+        paths.push(meta.path);
+    }
+    collection.overwrite_int(&dir, &mut cache, 2, None).unwrap();
+    let history = fs::read_dir(dir.join("history"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    assert_eq!(history.len(), 1);
+
+    // Null counts alone pick the pages: their damaged files are never read.
+    let null_paths = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_file() && *path != paths[0] && *path != paths[3])
+        .collect::<Vec<_>>();
+    assert_eq!(null_paths.len(), 2);
+    for path in &null_paths {
+        fs::write(path, b"bad").unwrap();
+    }
+
+    assert_eq!(collection.coalesce_null_pages(&dir).unwrap(), 1);
+    assert_eq!(collection.size(), 5);
+    assert!(null_paths.iter().chain(&history).all(|path| !path.exists()));
+    let mut cache = PageCache::new();
+    let values = (0..5)
+        .map(|idx| collection.get_int(&mut cache, idx).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![Some(1), None, None, None, Some(3)]);
+}
+
+#[test]
+fn and_or_bool_follow_kleene_logic_and_reject_mismatched_sizes() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let values = [Some(true), Some(false), None];
+    let (left, right): (Vec<_>, Vec<_>) = values
+        .iter()
+        .flat_map(|left| values.iter().map(move |right| (*left, *right)))
+        .unzip();
+    let left = Collection::from_bools(&dir, left, 4).unwrap();
+    let right = Collection::from_bools(&dir, right, 2).unwrap();
+
+    let and = left.and_bool(&right, &dir, &mut cache, 3).unwrap();
+    let or = left.or_bool(&right, &dir, &mut cache, 3).unwrap();
+    let entries = |collection: &Collection, cache: &mut PageCache| {
+        (0..collection.size())
+            .map(|idx| collection.get_bool(cache, idx).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let (t, f) = (Some(true), Some(false));
+    assert_eq!(
+        entries(&and, &mut cache),
+        vec![t, f, None, f, f, f, None, f, None]
+    );
+    assert_eq!(
+        entries(&or, &mut cache),
+        vec![t, t, t, t, f, None, t, None, None]
+    );
+
+    let short = Collection::from_bools(&dir, vec![Some(true)], 1).unwrap();
+    for err in [
+        left.and_bool(&short, &dir, &mut cache, 3).err().unwrap(),
+        short.or_bool(&left, &dir, &mut cache, 3).err().unwrap(),
+    ] {
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
+
+#[test]
+fn coalesce_and_zip_int_reject_mismatched_sizes() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let first = Collection::from_ints(&dir, vec![Some(1), None, None, Some(4)], 3).unwrap();
+    let second = Collection::from_ints(&dir, vec![Some(10), Some(20), None, None], 2).unwrap();
+    let short = Collection::from_ints(&dir, vec![Some(1)], 1).unwrap();
+
+    let coalesced = Collection::coalesce_int(&[&first, &second], &dir, &mut cache, 3).unwrap();
+    let values = (0..4)
+        .map(|idx| coalesced.get_int(&mut cache, idx).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![Some(1), Some(20), None, Some(4)]);
+    let pairs = first
+        .zip_int_iter(&second, &mut cache)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(pairs[1], (None, Some(20)));
+
+    let err = Collection::coalesce_int(&[&first, &short], &dir, &mut cache, 3)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(first.zip_int_iter(&short, &mut cache).is_err());
+    let err = first
+        .zip_map_int(&short, &dir, &mut cache, |left, _| left, 3)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn pages_for_indices_covers_only_the_touched_pages() {
+    let dir = tmp();
+    let collection = uneven_pages(&dir);
+    let id = collection.id();
+    let keys = collection.pages_for_indices(&[4, 1, 3, 2, 100]);
+    assert_eq!(keys.into_iter().collect::<Vec<_>>(), vec![(id, 0), (id, 1)]);
+    assert!(collection.pages_for_indices(&[]).is_empty());
+    assert_eq!(
+        collection
+            .pages_for_indices(&[8])
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec![(id, 2)]
+    );
+}
+
+#[test]
+fn int_or_getters_substitute_the_default_for_nulls() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let collection = Collection::from_ints(&dir, vec![Some(5), None, Some(-1), None], 3).unwrap();
+    assert_eq!(collection.get_int_or(&mut cache, 0, 9).unwrap(), 5);
+    assert_eq!(collection.get_int_or(&mut cache, 3, 9).unwrap(), 9);
+    assert!(collection.get_int_or(&mut cache, 4, 9).is_err());
+
+    let values = collection
+        .int_iter_or(&mut cache, 0)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(values, vec![5, 0, -1, 0]);
+}
+
+/// A page file in the layout written before the magic number: the null
+/// bitmap with its length, the offsets of a string page, then the values
+/// in a snappy frame.
+fn legacy_page(path: &Path, nulls: &[bool], offsets: &[u64], values: &[u8]) {
+    let mut bitmap = vec![0u8; nulls.len().div_ceil(8)];
+    for (idx, null) in nulls.iter().enumerate() {
+        bitmap[idx / 8] |= (*null as u8) << (idx % 8);
+    }
+    let mut bytes = (bitmap.len() as u64).to_le_bytes().to_vec();
+    bytes.extend(bitmap);
+    for offset in offsets {
+        bytes.extend(offset.to_le_bytes());
+    }
+    let mut encoder = snap::write::FrameEncoder::new(bytes);
+    encoder.write_all(values).unwrap();
+    fs::write(path, encoder.into_inner().unwrap()).unwrap();
+}
+
+#[test]
+fn legacy_pages_read_beside_current_ones() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+
+    let legacy = dir.join("legacy-ints");
+    let values = [7i64, 0, -3]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect::<Vec<_>>();
+    legacy_page(&legacy, &[false, true, false], &[], &values);
+    let mut ints = Collection::new(vec![PageMeta::new(Type::Int, &legacy, 0, 3)]);
+    ints.append_page(&dir, PageData::from_ints(&[Some(4)]).unwrap())
+        .unwrap();
+    let values = (0..4)
+        .map(|idx| ints.get_int(&mut cache, idx).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![Some(7), None, Some(-3), Some(4)]);
+
+    let legacy = dir.join("legacy-strings");
+    legacy_page(&legacy, &[false, true, false], &[0, 2, 2, 5], b"hiyes");
+    let strings = Collection::new(vec![PageMeta::new(Type::String, &legacy, 0, 3)]);
+    assert_eq!(
+        strings.to_vec_string(&mut cache).unwrap(),
+        vec![Some("hi".to_string()), None, Some("yes".to_string())]
+    );
+
+    // Bytes that are neither layout are reported as not a page.
+    fs::write(&legacy, b"not a page at all").unwrap();
+    let strings = Collection::new(vec![PageMeta::new(Type::String, &legacy, 0, 3)]);
+    assert!(strings.get_string(&mut PageCache::new(), 0).is_err());
+}
+
+#[test]
+fn sparse_nulls_take_a_few_bytes() {
+    let dir = tmp();
+    let len = 1_000_000;
+    let null_rows = [3, 500_000, len - 1];
+    let dense = (0..len as i64).map(Some).collect::<Vec<_>>();
+    let mut sparse = dense.clone();
+    for row in &null_rows {
+        sparse[*row] = None;
+    }
+
+    let mut metas = vec![];
+    for entries in &[&dense, &sparse] {
+        let mut collection = Collection::empty(Type::Int);
+        collection.set_compression(Compression::Uncompressed);
+        metas.push(
+            collection
+                .append_page(&dir, PageData::from_ints(entries).unwrap())
+                .unwrap(),
+        );
+    }
+    let sizes = metas
+        .iter()
+        .map(|meta| fs::metadata(&meta.path).unwrap().len())
+        .collect::<Vec<_>>();
+    // Far below the 125,000 bytes of a plain bitmap.
+    assert!(sizes[1] - sizes[0] < 100, "{:?}", sizes);
+
+    let nulls = PageReader::read_nulls(&metas[1]).unwrap();
+    let rows = nulls
+        .iter()
+        .enumerate()
+        .filter(|(_, null)| *null)
+        .map(|(row, _)| row)
+        .collect::<Vec<_>>();
+    assert_eq!(rows, null_rows.to_vec());
+}
+
+#[test]
+fn audit_page_sizes_reports_pages_off_the_modal_size() {
+    let dir = tmp();
+    let collection = Collection::from_ints(&dir, (0..10).map(Some), 3).unwrap();
+    let id = collection.id();
+    assert_eq!(collection.audit_page_sizes(), vec![((id, 3), 1)]);
+    assert!(collection.has_contiguous_offsets());
+
+    let mut collection = uneven_pages(&dir);
+    collection
+        .append_page(&dir, PageData::from_ints(&[Some(9), Some(10)]).unwrap())
+        .unwrap();
+    let id = collection.id();
+    assert_eq!(
+        collection.audit_page_sizes(),
+        vec![((id, 0), 3), ((id, 2), 4)]
+    );
+    assert!(collection.has_contiguous_offsets());
+    assert!(Collection::empty(Type::Int).audit_page_sizes().is_empty());
+}
+
+#[test]
+fn snapshots_keep_their_version_while_the_writer_appends() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let mut collection = Collection::empty(Type::Int);
+    collection
+        .append_page(
+            &dir,
+            PageData::from_ints(&[Some(1), Some(2), Some(3)]).unwrap(),
+        )
+        .unwrap();
+    let shared = SharedCollection::new(collection);
+
+    let reader = shared.snapshot();
+    let writer = shared.clone();
+    thread::spawn(move || {
+        writer
+            .append_page(&dir, PageData::from_ints(&[Some(4), Some(5)]).unwrap())
+            .unwrap();
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(reader.collection().size(), 3);
+    assert!(reader.collection().get_int(&mut cache, 3).is_err());
+    assert_eq!(reader.collection().to_vec_int(&mut cache).unwrap().len(), 3);
+    assert_eq!(shared.oldest_pinned_version(), Some(reader.version()));
+
+    let latest = shared.snapshot();
+    assert_eq!(latest.version(), shared.version());
+    assert!(latest.version() > reader.version());
+    assert_eq!(latest.collection().get_int(&mut cache, 4).unwrap(), Some(5));
+
+    drop(reader);
+    assert_eq!(shared.oldest_pinned_version(), Some(latest.version()));
+    drop(latest);
+    assert_eq!(shared.oldest_pinned_version(), None);
+}
+
+#[test]
+fn lazy_pages_decompress_only_up_to_the_requested_index() {
+    let dir = tmp();
+    let entries = (0..200_000)
+        .map(|value| if value % 5 == 0 { None } else { Some(value) })
+        .collect::<Vec<_>>();
+    let meta = PageMeta::new(Type::Int, &dir.join("page"), 0, entries.len());
+    let page = Page::new(&meta, PageData::from_ints(&entries).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Snappy).unwrap();
+
+    let mut lazy = PageReader::read_lazy(&meta).unwrap();
+    assert_eq!(lazy.get_int(1).unwrap(), Some(1));
+    let peeked = lazy.decompressed_len();
+    assert!(peeked < entries.len() * 8 / 2, "{}", peeked);
+
+    assert_eq!(lazy.get_int(0).unwrap(), None);
+    assert_eq!(lazy.decompressed_len(), peeked);
+    assert_eq!(lazy.get_int(199_999).unwrap(), Some(199_999));
+    assert_eq!(lazy.decompressed_len(), entries.len() * 8);
+    for (idx, expected) in entries.iter().enumerate().step_by(997) {
+        assert_eq!(lazy.get_int(idx).unwrap(), *expected);
+    }
+}
+
+#[test]
+fn values_downcast_to_their_own_type_only() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let ints = Collection::from_ints(&dir, vec![Some(7), None], 2).unwrap();
+    let mut strings = Collection::empty(Type::String);
+    strings
+        .append_page(&dir, PageData::from_strings(&[Some("kiwi")]).unwrap())
+        .unwrap();
+    let bools = Collection::from_bools(&dir, vec![Some(true)], 1).unwrap();
+
+    let int = ints.get_value(&mut cache, 0).unwrap();
+    assert_eq!(int.as_int(), Some(7));
+    assert_eq!(int.as_float(), None);
+    assert_eq!(int.as_str(), None);
+    let null = ints.get_value(&mut cache, 1).unwrap();
+    assert!(null.is_null());
+    assert_eq!(null.as_int(), None);
+
+    let string = strings.get_value(&mut cache, 0).unwrap();
+    assert_eq!(string.as_str(), Some("kiwi"));
+    assert_eq!(string.as_bool(), None);
+    let bool_value = bools.get_value(&mut cache, 0).unwrap();
+    assert_eq!(bool_value.as_bool(), Some(true));
+    assert_eq!(bool_value.as_int(), None);
+    assert_eq!(Value::Float(0.5).as_float(), Some(0.5));
+    assert_eq!(Value::Float(0.5).as_int(), None);
+}
+
+#[test]
+fn pages_keep_a_caller_chosen_id() {
+    let dir = tmp();
+    let id = Uuid::from_bytes([7; 16]);
+    let meta = PageMeta::with_id(id, Type::Int, &dir.join("page"), 0, 2);
+    let page = Page::new(&meta, PageData::from_ints(&[Some(1), None]).unwrap()).unwrap();
+    assert_eq!(page.meta().id, id);
+    PageWriter::write(&page, Compression::Snappy).unwrap();
+    assert_eq!(PageReader::read_meta(&meta.path).unwrap().id, id);
+    assert_eq!(PageReader::read(&meta).unwrap().meta().id, id);
+
+    let other = Uuid::from_bytes([42; 16]);
+    let mut collection = Collection::empty(Type::Int);
+    let appended = collection
+        .append_page_with_id(&dir, other, PageData::from_ints(&[Some(3)]).unwrap())
+        .unwrap();
+    assert_eq!(appended.id, other);
+    collection.write_manifest(&dir).unwrap();
+    fs::remove_file(dir.join("MANIFEST")).unwrap();
+    fs::remove_file(&meta.path).unwrap();
+    Collection::rebuild_manifest(&dir).unwrap();
+    let reopened = Collection::open(&dir).unwrap();
+    assert_eq!(reopened.get_int(&mut PageCache::new(), 0).unwrap(), Some(3));
+    assert_eq!(PageReader::read_meta(&appended.path).unwrap().id, other);
+}
+
+#[test]
+fn retain_int_compacts_the_kept_rows() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let source = Collection::from_ints(
+        &dir,
+        vec![Some(1), Some(2), None, Some(3), Some(4), Some(6)],
+        4,
+    )
+    .unwrap();
+    let out = tmp();
+    let evens = source
+        .retain_int(
+            &out,
+            &mut cache,
+            |entry| entry.is_some_and(|value| value % 2 == 0),
+            2,
+        )
+        .unwrap();
+    assert_eq!(
+        evens.to_vec_int(&mut cache).unwrap(),
+        vec![Some(2), Some(4), Some(6)]
+    );
+    assert_eq!(evens.audit_page_sizes().len(), 1);
+    assert_eq!(fs::read_dir(&out).unwrap().count(), 2);
+
+    let nulls = source
+        .retain_int(&tmp(), &mut cache, |entry| entry.is_none(), 2)
+        .unwrap();
+    assert_eq!(nulls.to_vec_int(&mut cache).unwrap(), vec![None]);
+}
+
+#[test]
+fn bitpacked_pages_round_trip_in_a_fraction_of_the_space() {
+    let dir = tmp();
+    let ages = (0..10_000)
+        .map(|idx| if idx % 97 == 0 { None } else { Some(idx % 121) })
+        .collect::<Vec<_>>();
+    let data = PageData::from_ints_bitpacked(&ages).unwrap();
+    assert_eq!(data.encoding(), Encoding::BitPacked { base: 0, width: 7 });
+
+    let mut collection = Collection::empty(Type::Int);
+    collection.set_compression(Compression::Uncompressed);
+    let meta = collection.append_page(&dir, data).unwrap();
+    // Seven bits a value instead of 64, plus the null bitmap.
+    assert!(fs::metadata(&meta.path).unwrap().len() < 10_000 * 8 / 6);
+
+    let mut cache = PageCache::new();
+    assert_eq!(collection.to_vec_int(&mut cache).unwrap(), ages);
+    for idx in (0..ages.len()).step_by(101) {
+        assert_eq!(collection.get_int(&mut cache, idx).unwrap(), ages[idx]);
+    }
+}
+
+#[test]
+fn namespaces_keep_equal_keys_of_two_stores_apart() {
+    let dir = tmp();
+    let metas = [1, 2]
+        .iter()
+        .map(|value| {
+            let meta = PageMeta::new(Type::Int, &dir.join(format!("store-{}", value)), 0, 1);
+            let page = Page::new(&meta, PageData::from_ints(&[Some(*value)]).unwrap()).unwrap();
+            PageWriter::write(&page, Compression::Snappy).unwrap();
+            meta
+        })
+        .collect::<Vec<_>>();
+
+    // Both stores number their only page the same way.
+    let key = (Uuid::from_bytes([9; 16]), 0);
+    let namespaces = [Uuid::from_bytes([1; 16]), Uuid::from_bytes([2; 16])];
+    let mut cache = PageCache::new();
+    for _ in 0..2 {
+        for (expected, (namespace, meta)) in (1..).zip(namespaces.iter().zip(&metas)) {
+            let page = cache.get_in(*namespace, &key, meta).unwrap();
+            assert_eq!(page.get_int(0).unwrap(), Some(expected));
+        }
+    }
+    assert_eq!(cache.stats().misses, 2);
+
+    let mut collection = Collection::new(vec![metas[1].clone()]);
+    collection.set_namespace(namespaces[1]);
+    assert_eq!(collection.namespace(), namespaces[1]);
+    assert_eq!(collection.get_int(&mut cache, 0).unwrap(), Some(2));
+}
+
+#[test]
+fn int_values_and_validity_match_the_entries() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let entries = vec![Some(3), None, Some(-1), None, Some(8)];
+    let collection = Collection::from_ints(&dir, entries.clone(), 2).unwrap();
+
+    let (values, validity) = collection.int_values_and_validity(&mut cache).unwrap();
+    let values = values.collect::<Vec<_>>();
+    assert_eq!(values, vec![3, 0, -1, 0, 8]);
+    assert_eq!(
+        validity.iter().collect::<Vec<_>>(),
+        entries.iter().map(Option::is_some).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn sort_int_spills_to_the_work_dir_and_cleans_up() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let entries = (0..50)
+        .map(|idx| {
+            if idx % 9 == 0 {
+                None
+            } else {
+                Some(idx * 37 % 50)
+            }
+        })
+        .collect::<Vec<_>>();
+    let source = Collection::from_ints(&dir, entries.clone(), 8).unwrap();
+
+    let (out, scratch) = (tmp(), tmp());
+    let sorted = source
+        .sort_int(&out, &mut cache, 8, &WorkDir::new(&scratch))
+        .unwrap();
+    let mut expected = entries
+        .iter()
+        .flatten()
+        .map(|value| Some(*value))
+        .collect::<Vec<_>>();
+    expected.sort();
+    expected.resize(entries.len(), None);
+    assert_eq!(sorted.to_vec_int(&mut cache).unwrap(), expected);
+    assert_eq!(fs::read_dir(&scratch).unwrap().count(), 0);
+    assert!(fs::read_dir(&out)
+        .unwrap()
+        .all(|entry| entry.unwrap().path().extension().is_none()));
+
+    // Runs spilled before a page fails to load are removed too.
+    let mut broken = Collection::empty(Type::Int);
+    for page in entries.chunks(8) {
+        broken
+            .append_page(&dir, PageData::from_ints(page).unwrap())
+            .unwrap();
+    }
+    let last = broken
+        .append_page(&dir, PageData::from_ints(&[Some(1)]).unwrap())
+        .unwrap();
+    fs::write(&last.path, b"bad").unwrap();
+    let result = broken.sort_int(&tmp(), &mut cache, 8, &WorkDir::new(&scratch));
+    assert!(result.is_err());
+    assert_eq!(fs::read_dir(&scratch).unwrap().count(), 0);
+}
+
+#[test]
+fn might_contain_int_rules_out_absent_values_without_loading() {
+    let dir = tmp();
+    let collection =
+        Collection::from_ints(&dir, (0..300).map(|value| Some(value * 2)), 64).unwrap();
+    for value in (0..300).map(|value| value * 2) {
+        assert!(collection.might_contain_int(value));
+    }
+    let ruled_out = (0..300)
+        .map(|value| value * 2 + 1)
+        .filter(|value| !collection.might_contain_int(*value))
+        .count();
+    assert!(ruled_out > 250, "{}", ruled_out);
+
+    // The filters live in the metadata, so even missing pages answer.
+    collection.write_manifest(&dir).unwrap();
+    let reopened = Collection::open(&dir).unwrap();
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() && !path.ends_with("MANIFEST") {
+            fs::remove_file(path).unwrap();
+        }
+    }
+    assert!(reopened.might_contain_int(598));
+    let reopened_out = (0..300)
+        .map(|value| value * 2 + 1)
+        .filter(|value| !reopened.might_contain_int(*value))
+        .count();
+    assert_eq!(reopened_out, ruled_out);
+}
+
+#[test]
+fn checked_sums_report_overflow() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let small = Collection::from_ints(&dir, vec![Some(1), None, Some(-4), Some(10)], 2).unwrap();
+    assert_eq!(small.checked_sum_int(&mut cache).unwrap(), Some(7));
+    assert_eq!(small.sum_i128(&mut cache).unwrap(), 7);
+
+    let large = Collection::from_ints(&dir, vec![Some(i64::MAX), None, Some(i64::MAX), Some(2)], 2)
+        .unwrap();
+    assert_eq!(large.checked_sum_int(&mut cache).unwrap(), None);
+    assert_eq!(
+        large.sum_i128(&mut cache).unwrap(),
+        2 * i128::from(i64::MAX) + 2
+    );
+}
+
+#[test]
+fn zip_map_int_writes_the_computed_column() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let left =
+        Collection::from_ints(&dir, vec![Some(1), Some(2), None, Some(4), Some(5)], 2).unwrap();
+    let right =
+        Collection::from_ints(&dir, vec![Some(10), Some(20), Some(30), None, Some(50)], 3).unwrap();
+
+    let out = tmp();
+    let sums = left
+        .zip_map_int(
+            &right,
+            &out,
+            &mut cache,
+            |left, right| Some(left? + right?),
+            2,
+        )
+        .unwrap();
+    assert_eq!(
+        sums.to_vec_int(&mut cache).unwrap(),
+        vec![Some(11), Some(22), None, None, Some(55)]
+    );
+    assert_eq!(fs::read_dir(&out).unwrap().count(), 3);
+
+    let coalesced = left
+        .zip_map_int(&right, &tmp(), &mut cache, |left, right| left.or(right), 4)
+        .unwrap();
+    assert_eq!(
+        coalesced.to_vec_int(&mut cache).unwrap(),
+        vec![Some(1), Some(2), Some(30), Some(4), Some(5)]
+    );
+}
+
+#[test]
+fn open_typed_rejects_another_type() {
+    let dir = tmp();
+    let mut collection = Collection::empty(Type::Float);
+    collection
+        .append_page(&dir, PageData::from_floats(&[Some(1.5), None]).unwrap())
+        .unwrap();
+    collection.write_manifest(&dir).unwrap();
+
+    let err = Collection::open_typed(&dir, Type::Int).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("Float"), "{}", err);
+    let opened = Collection::open_typed(&dir, Type::Float).unwrap();
+    assert_eq!(opened.typ(), Type::Float);
+    assert_eq!(opened.size(), 2);
+}
+
+#[test]
+fn footprint_adds_up_the_page_files() {
+    let dir = tmp();
+    let entries = (0..1000).map(|value| if value % 4 == 0 { None } else { Some(value) });
+    let collection = Collection::from_ints(&dir, entries, 10).unwrap();
+
+    let footprint = collection.footprint().unwrap();
+    let on_disk = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().metadata().unwrap().len())
+        .sum::<u64>();
+    assert_eq!(footprint.total, on_disk);
+    assert_eq!(footprint.total, footprint.overhead + footprint.payload);
+    assert_eq!((footprint.pages, footprint.size), (100, 1000));
+    assert!(footprint.overhead > footprint.payload / 10);
+
+    // Ten times fewer pages pay ninety fewer fixed overheads.
+    let compacted = footprint.estimate_compacted(100);
+    assert!(compacted < footprint.total, "{:?} {}", footprint, compacted);
+    assert_eq!(footprint.estimate_compacted(10), footprint.total);
+}
+
+#[test]
+fn read_nulls_matches_the_full_page() {
+    let dir = tmp();
+    let cases = vec![
+        PageData::from_ints(&[Some(1), None, None, Some(4), None]).unwrap(),
+        PageData::from_strings(&[None, Some("a"), Some("b")]).unwrap(),
+        PageData::from_bools(&[Some(true), Some(false)]).unwrap(),
+        PageData::from_floats(&[None, None]).unwrap(),
+    ];
+    for (idx, data) in cases.into_iter().enumerate() {
+        let meta = PageMeta::new(data.typ(), &dir.join(idx.to_string()), 0, data.len());
+        let page = Page::new(&meta, data).unwrap();
+        PageWriter::write(&page, Compression::Zstd { level: 3 }).unwrap();
+
+        let nulls = PageReader::read_nulls(&meta).unwrap();
+        let full = PageReader::read(&meta).unwrap();
+        assert_eq!(
+            nulls.iter().collect::<Vec<_>>(),
+            (0..meta.size)
+                .map(|idx| full.is_null(idx))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    // The values are never read, so damage past the null section goes
+    // unnoticed.
+    let meta = PageMeta::new(Type::Int, &dir.join("cut"), 0, 3);
+    let page = Page::new(&meta, PageData::from_ints(&[None, Some(2), None]).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Uncompressed).unwrap();
+    let bytes = fs::read(&meta.path).unwrap();
+    fs::write(&meta.path, &bytes[..bytes.len() - 12]).unwrap();
+    assert!(PageReader::read(&meta).is_err());
+    assert_eq!(
+        PageReader::read_nulls(&meta)
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>(),
+        vec![true, false, true]
+    );
+}
+
+#[test]
+fn to_vec_matches_the_iterators() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let ints =
+        Collection::from_ints(&dir, vec![Some(1), None, Some(3), Some(-4), None], 2).unwrap();
+    let from_iter = ints
+        .int_iter(&mut cache)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let vec = ints.to_vec_int(&mut cache).unwrap();
+    assert_eq!(vec, from_iter);
+    assert_eq!(vec.len(), ints.size());
+
+    let bools = Collection::from_bools(&dir, vec![Some(true), None, Some(false)], 2).unwrap();
+    assert_eq!(
+        bools.to_vec_bool(&mut cache).unwrap(),
+        bools
+            .bool_iter(&mut cache)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    );
+
+    let mut floats = Collection::empty(Type::Float);
+    let mut strings = Collection::empty(Type::String);
+    for idx in 0..3 {
+        floats
+            .append_page(
+                &dir,
+                PageData::from_floats(&[Some(idx as f64), None]).unwrap(),
+            )
+            .unwrap();
+        strings
+            .append_page(
+                &dir,
+                PageData::from_strings(&[None, Some(&idx.to_string())]).unwrap(),
+            )
+            .unwrap();
+    }
+    assert_eq!(
+        floats.to_vec_float(&mut cache).unwrap(),
+        vec![Some(0.0), None, Some(1.0), None, Some(2.0), None]
+    );
+    assert_eq!(
+        strings.to_vec_string(&mut cache).unwrap(),
+        (0..6)
+            .map(|idx| strings.get_string(&mut cache, idx).unwrap())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn find_duplicates_int_reports_every_index() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let collection = Collection::from_ints(
+        &dir,
+        vec![Some(1), Some(2), None, Some(1), Some(3), None, Some(2)],
+        2,
+    )
+    .unwrap();
+
+    assert_eq!(
+        collection.find_duplicates_int(&mut cache).unwrap(),
+        vec![(1, vec![0, 3]), (2, vec![1, 6])]
+    );
+    assert_eq!(
+        collection.duplicate_counts_int(&mut cache).unwrap(),
+        vec![(1, 2), (2, 2)]
+    );
+
+    let distinct = Collection::from_ints(&dir, vec![Some(1), None, Some(2)], 2).unwrap();
+    assert!(distinct.find_duplicates_int(&mut cache).unwrap().is_empty());
+    assert!(distinct
+        .duplicate_counts_int(&mut cache)
+        .unwrap()
+        .is_empty());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_map_int_keeps_the_order_across_batches() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    // More pages than threads, so the input spans several batches.
+    let entries = (0..(rayon::current_num_threads() as i64 * 3 + 1) * 4)
+        .map(|value| if value % 5 == 0 { None } else { Some(value) })
+        .collect::<Vec<_>>();
+    let collection = Collection::from_ints(&dir, entries.clone(), 4).unwrap();
+
+    let mapped = collection
+        .par_map_int(&dir, |entry| entry.map(|value| value * 2), 3)
+        .unwrap();
+    assert_eq!(
+        mapped.to_vec_int(&mut cache).unwrap(),
+        entries
+            .iter()
+            .map(|entry| entry.map(|value| value * 2))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(mapped.page_of(2).unwrap().1, 2);
+    assert_eq!(mapped.page_of(3).unwrap().1, 0);
+}
+
+#[test]
+fn truncate_rewrites_the_boundary_page_and_drops_the_rest() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let mut collection = Collection::from_ints(&dir, (0..9).map(Some), 3).unwrap();
+    let dropped = collection.page_of(6).unwrap().0;
+    assert_eq!(collection.get_int(&mut cache, 5).unwrap(), Some(5));
+
+    collection.truncate(&dir, 4).unwrap();
+    assert_eq!(collection.size(), 4);
+    assert!(collection.page_of(4).is_none());
+    assert_ne!(collection.page_of(0).unwrap().0 .0, dropped.0);
+    assert_eq!(
+        collection.to_vec_int(&mut cache).unwrap(),
+        vec![Some(0), Some(1), Some(2), Some(3)]
+    );
+    assert_eq!(collection.get_int(&mut cache, 3).unwrap(), Some(3));
+    assert!(collection.get_int(&mut cache, 5).is_err());
+
+    // A size at or past the end leaves the collection alone.
+    collection.truncate(&dir, 4).unwrap();
+    collection.truncate(&dir, 10).unwrap();
+    assert_eq!(collection.size(), 4);
+
+    collection.write_manifest(&dir).unwrap();
+    let pages = fs::read_dir(&dir)
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().path().is_file())
+        .count();
+    // Two pages and the manifest.
+    assert_eq!(pages, 3);
+    let reopened = Collection::open(&dir).unwrap();
+    assert_eq!(
+        reopened.to_vec_int(&mut PageCache::new()).unwrap(),
+        vec![Some(0), Some(1), Some(2), Some(3)]
+    );
+}
+
+#[test]
+fn appended_pages_store_their_running_offset() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let mut collection = Collection::empty(Type::Int);
+    let mut next = 0;
+    let mut metas = vec![];
+    for &size in &[3, 2, 4] {
+        let entries = (next..next + size).map(Some).collect::<Vec<_>>();
+        metas.push(
+            collection
+                .append_page(&dir, PageData::from_ints(&entries).unwrap())
+                .unwrap(),
+        );
+        next += size;
+    }
+
+    assert_eq!(
+        metas.iter().map(PageMeta::offset).collect::<Vec<_>>(),
+        vec![0, 3, 5]
+    );
+    // The offset is also written to each page header.
+    for meta in &metas {
+        assert_eq!(
+            PageReader::read(meta).unwrap().meta().offset(),
+            meta.offset()
+        );
+    }
+    for idx in 0..9 {
+        assert_eq!(
+            collection.get_int(&mut cache, idx).unwrap(),
+            Some(idx as i64)
+        );
+        let (key, within) = collection.page_of(idx).unwrap();
+        assert_eq!(idx - within, metas[key.1].offset());
+    }
+}
+
+#[test]
+fn pages_of_different_codecs_read_back_after_open() {
+    let dir = tmp();
+    let codecs = [
+        Compression::Snappy,
+        Compression::Zstd { level: 3 },
+        Compression::Lz4,
+        Compression::Uncompressed,
+    ];
+    let mut collection = Collection::empty(Type::String);
+    for (idx, &codec) in codecs.iter().enumerate() {
+        collection.set_compression(codec);
+        let value = format!("page {}", idx);
+        collection
+            .append_page(
+                &dir,
+                PageData::from_strings(&[Some(&value), None, Some(&value)]).unwrap(),
+            )
+            .unwrap();
+    }
+    collection.write_manifest(&dir).unwrap();
+
+    let reopened = Collection::open(&dir).unwrap();
+    let mut cache = PageCache::new();
+    for idx in 0..codecs.len() {
+        let value = format!("page {}", idx);
+        assert_eq!(
+            reopened.get_string(&mut cache, idx * 3).unwrap(),
+            Some(value.clone())
+        );
+        assert_eq!(reopened.get_string(&mut cache, idx * 3 + 1).unwrap(), None);
+        assert_eq!(
+            reopened.get_string(&mut cache, idx * 3 + 2).unwrap(),
+            Some(value)
+        );
+    }
+}
+
+#[test]
+fn rolling_min_and_max_slide_across_pages() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let collection =
+        Collection::from_ints(&dir, vec![Some(3), Some(1), Some(2), Some(4)], 3).unwrap();
+
+    assert_eq!(
+        collection
+            .rolling_min_int(&mut cache, 2)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        vec![Some(3), Some(1), Some(1), Some(2)]
+    );
+    assert_eq!(
+        collection
+            .rolling_max_int(&mut cache, 2)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        vec![Some(3), Some(3), Some(2), Some(4)]
+    );
+
+    // A window of only nulls has no minimum.
+    let nullable = Collection::from_ints(&dir, vec![Some(5), None, None, Some(2)], 2).unwrap();
+    assert_eq!(
+        nullable
+            .rolling_min_int(&mut cache, 2)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        vec![Some(5), Some(5), None, Some(2)]
+    );
+}
+
+#[test]
+fn tail_int_loads_only_the_last_pages() {
+    let dir = tmp();
+    let collection = Collection::from_ints(&dir, (0..10).map(Some), 3).unwrap();
+
+    let mut cache = PageCache::new();
+    assert_eq!(
+        collection.tail_int(&mut cache, 4).unwrap(),
+        vec![Some(6), Some(7), Some(8), Some(9)]
+    );
+    // Rows 6..10 sit in the last two of four pages.
+    assert_eq!(cache.stats().misses, 2);
+
+    let mut cache = PageCache::new();
+    assert_eq!(collection.tail_int(&mut cache, 1).unwrap(), vec![Some(9)]);
+    assert_eq!(cache.stats().misses, 1);
+    assert!(collection.tail_int(&mut cache, 0).unwrap().is_empty());
+    assert_eq!(
+        collection.tail_int(&mut cache, 20).unwrap(),
+        (0..10).map(Some).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn to_ndjson_writes_one_value_per_line() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let ints = Collection::from_ints(&dir, vec![Some(1), None, Some(-3)], 2).unwrap();
+    let mut out = vec![];
+    ints.to_ndjson(&mut cache, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "1\nnull\n-3\n");
+
+    let mut strings = Collection::empty(Type::String);
+    strings
+        .append_page(
+            &dir,
+            PageData::from_strings(&[Some("say \"hi\"\n"), None, Some("a\\b\u{1}")]).unwrap(),
+        )
+        .unwrap();
+    let mut out = vec![];
+    strings.to_ndjson(&mut cache, &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "\"say \\\"hi\\\"\\n\"\nnull\n\"a\\\\b\\u0001\"\n"
+    );
+}
+
+#[test]
+fn cached_count_sums_the_registered_page_counts() {
+    let dir = tmp();
+    let mut collection = Collection::empty(Type::Int);
+    collection.register_count("positive", |value| match value {
+        Value::Int(value) => *value > 0,
+        _ => false,
+    });
+    collection
+        .append_page(
+            &dir,
+            PageData::from_ints(&[Some(1), Some(-1), None]).unwrap(),
+        )
+        .unwrap();
+    collection
+        .append_page(&dir, PageData::from_ints(&[Some(2), Some(3)]).unwrap())
+        .unwrap();
+    assert_eq!(collection.cached_count("positive"), Some(3));
+    assert_eq!(collection.cached_count("negative"), None);
+
+    collection.write_manifest(&dir).unwrap();
+    let mut reopened = Collection::open(&dir).unwrap();
+    assert_eq!(reopened.cached_count("positive"), Some(3));
+
+    // Pages written before a predicate was registered never counted it.
+    reopened.register_count("negative", |value| match value {
+        Value::Int(value) => *value < 0,
+        _ => false,
+    });
+    reopened
+        .append_page(&dir, PageData::from_ints(&[Some(-5)]).unwrap())
+        .unwrap();
+    assert_eq!(reopened.cached_count("negative"), None);
+}
+
+#[test]
+fn pages_of_another_page_size_read_after_open() {
+    let dir = tmp();
+    let mut collection = Collection::from_ints(&dir, (0..20).map(Some), 7).unwrap();
+    collection
+        .append_page(
+            &dir,
+            PageData::from_ints(&[Some(20), None, Some(22)]).unwrap(),
+        )
+        .unwrap();
+    collection.write_manifest(&dir).unwrap();
+
+    let reopened = Collection::open(&dir).unwrap();
+    let mut cache = PageCache::new();
+    assert_eq!(reopened.size(), 23);
+    for idx in 0..20 {
+        assert_eq!(reopened.get_int(&mut cache, idx).unwrap(), Some(idx as i64));
+    }
+    assert_eq!(reopened.get_int(&mut cache, 21).unwrap(), None);
+    assert_eq!(reopened.get_int(&mut cache, 22).unwrap(), Some(22));
+    assert_eq!(reopened.page_of(13).unwrap().1, 6);
+    assert_eq!(reopened.page_of(14).unwrap().1, 0);
+}
+
+#[test]
+fn cumsum_int_carries_the_total_across_pages() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let collection = Collection::from_ints(
+        &dir,
+        vec![Some(1), None, Some(2), Some(3), None, Some(-4)],
+        4,
+    )
+    .unwrap();
+
+    let as_zero = collection
+        .cumsum_int(&dir, &mut cache, 3, NullPolicy::AsZero)
+        .unwrap();
+    assert_eq!(
+        as_zero.to_vec_int(&mut cache).unwrap(),
+        vec![Some(1), Some(1), Some(3), Some(6), Some(6), Some(2)]
+    );
+    let propagated = collection
+        .cumsum_int(&dir, &mut cache, 3, NullPolicy::Propagate)
+        .unwrap();
+    assert_eq!(
+        propagated.to_vec_int(&mut cache).unwrap(),
+        vec![Some(1), None, Some(3), Some(6), None, Some(2)]
+    );
+}
@@ -0,0 +1,129 @@
+use std::fs;
+use std::path::PathBuf;
+
+use eadb::cache::PageCache;
+use eadb::collection::Collection;
+use eadb::page::{Compression, Encoding, Page, PageData, PageMeta, PageReader, PageWriter, Type};
+
+fn tmp() -> PathBuf {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn rle_pages_round_trip_smaller_than_plain() {
+    let dir = tmp();
+    let values = (0..1000)
+        .map(|idx| if idx % 97 == 5 { None } else { Some(idx / 100) })
+        .collect::<Vec<_>>();
+    let plain_meta = PageMeta::new(Type::Int, &dir.join("plain"), 0, 1000);
+    let plain = Page::new(&plain_meta, PageData::from_ints(&values).unwrap()).unwrap();
+    let rle_meta = PageMeta::new(Type::Int, &dir.join("rle"), 0, 1000);
+    let rle = Page::new(&rle_meta, PageData::from_ints_rle(&values).unwrap()).unwrap();
+    assert_eq!(rle.data().encoding(), Encoding::Rle { runs: 10 });
+    assert_eq!(plain.meta().checksum, rle.meta().checksum);
+
+    PageWriter::write(&plain, Compression::Uncompressed).unwrap();
+    PageWriter::write(&rle, Compression::Uncompressed).unwrap();
+    let plain_len = fs::metadata(&plain_meta.path).unwrap().len();
+    let rle_len = fs::metadata(&rle_meta.path).unwrap().len();
+    assert!(rle_len * 10 < plain_len, "{} {}", rle_len, plain_len);
+
+    let read = PageReader::read(&rle_meta).unwrap();
+    for (idx, value) in values.iter().enumerate() {
+        assert_eq!(read.get_int(idx).unwrap(), *value);
+    }
+    let mut lazy = PageReader::read_lazy(&rle_meta).unwrap();
+    assert_eq!(lazy.get_int(999).unwrap(), Some(9));
+    assert_eq!(lazy.get_int(5).unwrap(), None);
+
+    let bools = vec![None, Some(true), Some(true), None, Some(false), Some(true)];
+    let meta = PageMeta::new(Type::Bool, &dir.join("bools"), 0, bools.len());
+    let page = Page::new(&meta, PageData::from_bools_rle(&bools).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Snappy).unwrap();
+    let read = PageReader::read(&meta).unwrap();
+    assert_eq!(
+        (0..6)
+            .map(|idx| read.get_bool(idx).unwrap())
+            .collect::<Vec<_>>(),
+        bools
+    );
+
+    let mut collection = Collection::empty(Type::Int);
+    collection
+        .append_page(&dir, PageData::from_ints_rle(&values).unwrap())
+        .unwrap();
+    assert_eq!(
+        collection.to_vec_int(&mut PageCache::new()).unwrap(),
+        values
+    );
+}
+
+#[test]
+fn delta_pages_round_trip_with_random_access() {
+    let dir = tmp();
+    let mut values = (0..1000i64)
+        .map(|idx| {
+            if idx % 37 == 3 {
+                None
+            } else {
+                Some(1_600_000_000_000 + idx * 3 - (idx % 5) * 7)
+            }
+        })
+        .collect::<Vec<_>>();
+    values[0] = None;
+    values[500] = Some(i64::MIN);
+    values[501] = Some(i64::MAX);
+
+    let plain_meta = PageMeta::new(Type::Int, &dir.join("plain"), 0, 1000);
+    let plain = Page::new(&plain_meta, PageData::from_ints(&values).unwrap()).unwrap();
+    let delta_meta = PageMeta::new(Type::Int, &dir.join("delta"), 0, 1000);
+    let delta = Page::new(&delta_meta, PageData::from_ints_delta(&values).unwrap()).unwrap();
+    assert!(matches!(delta.data().encoding(), Encoding::Delta { .. }));
+    assert_eq!(plain.meta().checksum, delta.meta().checksum);
+    assert_eq!(plain.meta().stats.int_bound, delta.meta().stats.int_bound);
+
+    PageWriter::write(&plain, Compression::Uncompressed).unwrap();
+    PageWriter::write(&delta, Compression::Uncompressed).unwrap();
+    let plain_len = fs::metadata(&plain_meta.path).unwrap().len();
+    let delta_len = fs::metadata(&delta_meta.path).unwrap().len();
+    assert!(delta_len * 3 < plain_len, "{} {}", delta_len, plain_len);
+
+    let read = PageReader::read(&delta_meta).unwrap();
+    for (idx, value) in values.iter().enumerate() {
+        assert_eq!(read.get_int(idx).unwrap(), *value, "{}", idx);
+    }
+    // Entries on both sides of a checkpoint and around the extremes.
+    let mut lazy = PageReader::read_lazy(&delta_meta).unwrap();
+    for &idx in &[999, 0, 128, 127, 500, 501, 502, 3, 777] {
+        assert_eq!(lazy.get_int(idx).unwrap(), values[idx], "{}", idx);
+    }
+}
+
+#[test]
+fn small_range_pages_take_four_bits_per_value() {
+    let dir = tmp();
+    let values = (0..4096i64)
+        .map(|idx| Some((idx * 7 + idx / 3) % 16))
+        .collect::<Vec<_>>();
+    let mut collection = Collection::empty(Type::Int);
+    collection.set_compression(Compression::Uncompressed);
+    collection.set_auto_encoding(true);
+    let meta = collection
+        .append_page(&dir, PageData::from_ints(&values).unwrap())
+        .unwrap();
+
+    let len = fs::metadata(&meta.path).unwrap().len();
+    let overhead = PageReader::overhead_len(&meta).unwrap();
+    assert_eq!(len - overhead, 4096 / 2);
+    let page = PageReader::read(&meta).unwrap();
+    assert_eq!(
+        page.data().encoding(),
+        Encoding::BitPacked { base: 0, width: 4 }
+    );
+    assert_eq!(
+        collection.to_vec_int(&mut PageCache::new()).unwrap(),
+        values
+    );
+}
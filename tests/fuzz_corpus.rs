@@ -0,0 +1,164 @@
+//! Hand-built malformed page files. Every case must be rejected with an
+//! error rather than a panic or an oversized allocation.
+
+use std::fs;
+use std::path::PathBuf;
+
+use eadb::page::{Compression, Page, PageData, PageMeta, PageReader, PageWriter, Type};
+use eadb::Error;
+
+/// Magic, version, type, uuid, offset and size.
+const HEADER_LEN: usize = 4 + 1 + 1 + 16 + 8 + 8;
+
+fn tmp() -> PathBuf {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn written(page: &Page, fixed_offsets: bool) -> Vec<u8> {
+    if fixed_offsets {
+        PageWriter::write_fixed_offsets(page, Compression::Uncompressed).unwrap();
+    } else {
+        PageWriter::write(page, Compression::Uncompressed).unwrap();
+    }
+    fs::read(&page.meta().path).unwrap()
+}
+
+/// Recomputes the CRC32 footer so the damage reaches the parser.
+fn reseal(bytes: &mut [u8]) {
+    let len = bytes.len();
+    let checksum = crc32fast::hash(&bytes[..len - 4]);
+    bytes[len - 4..].copy_from_slice(&checksum.to_le_bytes());
+}
+
+fn position(bytes: &[u8], pattern: &[u8]) -> usize {
+    bytes
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+        .expect("pattern not in page")
+}
+
+fn words(values: &[u64]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes().to_vec())
+        .collect()
+}
+
+fn rejected(bytes: &[u8], meta: &PageMeta) -> Error {
+    match PageReader::from_bytes(bytes, meta) {
+        Ok(_) => panic!("malformed page of {} bytes was accepted", bytes.len()),
+        Err(err) => err,
+    }
+}
+
+#[test]
+fn truncated_header() {
+    let dir = tmp();
+    let meta = PageMeta::new(Type::Int, &dir.join("page"), 0, 3);
+    let page = Page::new(
+        &meta,
+        PageData::from_ints(&[Some(1), None, Some(3)]).unwrap(),
+    )
+    .unwrap();
+    let bytes = written(&page, false);
+
+    for cut in 0..=HEADER_LEN {
+        rejected(&bytes[..cut], &meta);
+
+        // The same prefix behind a valid footer.
+        let mut sealed = bytes[..cut].to_vec();
+        sealed.extend_from_slice(&[0; 4]);
+        reseal(&mut sealed);
+        rejected(&sealed, &meta);
+    }
+}
+
+#[test]
+fn descending_offsets() {
+    let dir = tmp();
+    let meta = PageMeta::new(Type::String, &dir.join("page"), 0, 3);
+    let data = PageData::from_strings(&[Some("ab"), Some("cd"), Some("ef")]).unwrap();
+    let mut bytes = written(&Page::new(&meta, data).unwrap(), true);
+
+    let at = position(&bytes, &words(&[0, 2, 4, 6]));
+    bytes[at..at + 32].copy_from_slice(&words(&[0, 4, 2, 6]));
+    reseal(&mut bytes);
+    let err = rejected(&bytes, &meta);
+    assert!(matches!(err, Error::Corrupt(_)), "{}", err);
+}
+
+#[test]
+fn offsets_past_the_values() {
+    let dir = tmp();
+    let meta = PageMeta::new(Type::String, &dir.join("page"), 0, 3);
+    let data = PageData::from_strings(&[Some("ab"), Some("cd"), Some("ef")]).unwrap();
+    let mut bytes = written(&Page::new(&meta, data).unwrap(), true);
+
+    let at = position(&bytes, &words(&[0, 2, 4, 6]));
+    bytes[at..at + 32].copy_from_slice(&words(&[0, 2, 4, 600]));
+    reseal(&mut bytes);
+    let err = rejected(&bytes, &meta);
+    assert!(matches!(err, Error::Corrupt(_)), "{}", err);
+}
+
+#[test]
+fn invalid_utf8() {
+    let dir = tmp();
+    let meta = PageMeta::new(Type::String, &dir.join("page"), 0, 2);
+    let data = PageData::from_strings(&[Some("ok"), Some("\u{e9}")]).unwrap();
+    let mut bytes = written(&Page::new(&meta, data).unwrap(), false);
+
+    // The last value byte sits just before the footer.
+    let len = bytes.len();
+    bytes[len - 5] = 0xff;
+    reseal(&mut bytes);
+    let err = rejected(&bytes, &meta);
+    assert!(matches!(err, Error::Utf8(_)), "{}", err);
+}
+
+#[test]
+fn oversized_decompression_claim() {
+    let dir = tmp();
+    let entries = vec![Some("same"); 1000];
+    let meta = PageMeta::new(Type::String, &dir.join("page"), 0, entries.len());
+    let data = PageData::from_strings(&entries).unwrap();
+    let mut bytes = written(&Page::new(&meta, data).unwrap(), false);
+
+    // Compressed delta offsets open with the snappy varint of their
+    // decoded length, 1000; claim 16383 bytes instead.
+    let at = position(&bytes, &[0xe8, 0x07]);
+    bytes[at..at + 2].copy_from_slice(&[0xff, 0x7f]);
+    reseal(&mut bytes);
+    let err = rejected(&bytes, &meta);
+    assert!(matches!(err, Error::Corrupt(_)), "{}", err);
+}
+
+#[test]
+fn short_bitmap() {
+    let dir = tmp();
+    let entries = (0..64)
+        .map(|idx| if idx % 2 == 0 { Some(idx) } else { None })
+        .collect::<Vec<_>>();
+    let meta = PageMeta::new(Type::Int, &dir.join("page"), 0, entries.len());
+    let data = PageData::from_ints(&entries).unwrap();
+    let bytes = written(&Page::new(&meta, data).unwrap(), false);
+
+    // The null encoding byte follows the header, then the bitmap length.
+    let at = HEADER_LEN + 1;
+    assert_eq!(&bytes[at..at + 8], &8u64.to_le_bytes());
+
+    let mut claimed = bytes.clone();
+    claimed[at..at + 8].copy_from_slice(&7u64.to_le_bytes());
+    reseal(&mut claimed);
+    let err = rejected(&claimed, &meta);
+    assert!(matches!(err, Error::Corrupt(_)), "{}", err);
+
+    let mut cut = bytes[..at + 8 + 7].to_vec();
+    cut[at..at + 8].copy_from_slice(&7u64.to_le_bytes());
+    cut.extend_from_slice(&[0; 4]);
+    reseal(&mut cut);
+    let err = rejected(&cut, &meta);
+    assert!(matches!(err, Error::Corrupt(_)), "{}", err);
+}
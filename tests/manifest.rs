@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use eadb::cache::PageCache;
+use eadb::collection::Collection;
+use eadb::page::{PageData, Type};
+
+fn tmp() -> PathBuf {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn sorted_ints(dir: &Path) -> Collection {
+    let values = (0..40).map(|value| if value % 7 == 0 { None } else { Some(value) });
+    let collection = Collection::from_ints(dir, values, 10).unwrap();
+    collection.write_manifest(dir).unwrap();
+    collection
+}
+
+#[test]
+fn page_stats_survive_reopen() {
+    let dir = tmp();
+    let collection = sorted_ints(&dir);
+    assert!(collection.is_globally_sorted());
+
+    let reopened = Collection::open(&dir).unwrap();
+    assert!(reopened.is_globally_sorted());
+    assert!(!reopened.might_contain_int(100));
+    assert!(reopened.might_contain_int(39));
+}
+
+#[test]
+fn page_stats_rebuilt_from_version_5_manifest() {
+    let dir = tmp();
+    sorted_ints(&dir);
+
+    // Downgrade the manifest by dropping the stats field of every page line.
+    let manifest = fs::read_to_string(dir.join("MANIFEST")).unwrap();
+    let mut lines = manifest.lines();
+    let mut downgraded = String::new();
+    assert_eq!(lines.next(), Some("eadb-manifest 6"));
+    downgraded.push_str("eadb-manifest 5\n");
+    downgraded.push_str(lines.next().unwrap());
+    downgraded.push('\n');
+    for line in lines {
+        if line.starts_with("~\t") || line.starts_with("=\t") {
+            downgraded.push_str(line);
+        } else {
+            let mut fields: Vec<_> = line.splitn(7, '\t').collect();
+            fields.remove(5);
+            downgraded.push_str(&fields.join("\t"));
+        }
+        downgraded.push('\n');
+    }
+    fs::write(dir.join("MANIFEST"), downgraded).unwrap();
+
+    let reopened = Collection::open(&dir).unwrap();
+    assert!(reopened.is_globally_sorted());
+    reopened.write_manifest(&dir).unwrap();
+    assert!(Collection::open(&dir).unwrap().is_globally_sorted());
+}
+
+#[test]
+fn stats_json_survives_reopen() {
+    let pages = vec![
+        vec![
+            PageData::from_ints(&[Some(3), None, Some(-2)]).unwrap(),
+            PageData::from_ints(&[Some(9), Some(4)]).unwrap(),
+        ],
+        vec![PageData::from_floats(&[None, Some(1.5), Some(-0.25)]).unwrap()],
+        vec![PageData::from_strings(&[Some("pear"), None, Some("fig")]).unwrap()],
+    ];
+    for pages in pages {
+        let dir = tmp();
+        let mut collection = Collection::empty(pages[0].typ());
+        for data in pages {
+            collection.append_page(&dir, data).unwrap();
+        }
+        collection.write_manifest(&dir).unwrap();
+
+        let reopened = Collection::open(&dir).unwrap();
+        let expected = collection
+            .stats_json()
+            .replace(&collection.id().to_string(), &reopened.id().to_string());
+        assert_eq!(reopened.stats_json(), expected);
+        assert!(expected.contains("\"contains_nulls\":true"));
+        assert!(!expected.contains("\"bound\":null"));
+    }
+}
+
+#[test]
+fn rebuild_manifest_restores_the_collection_beside_its_history() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let mut collection = Collection::empty(Type::Int);
+    for page in &[
+        vec![Some(1), None, Some(3)],
+        vec![Some(4)],
+        vec![None, Some(6)],
+    ] {
+        collection
+            .append_page(&dir, PageData::from_ints(page).unwrap())
+            .unwrap();
+    }
+    collection
+        .overwrite_int(&dir, &mut cache, 3, Some(40))
+        .unwrap();
+    collection.write_manifest(&dir).unwrap();
+    assert_eq!(fs::read_dir(dir.join("history")).unwrap().count(), 1);
+
+    fs::remove_file(dir.join("MANIFEST")).unwrap();
+    assert!(Collection::open(&dir).is_err());
+    Collection::rebuild_manifest(&dir).unwrap();
+
+    let rebuilt = Collection::open(&dir).unwrap();
+    assert_eq!(rebuilt.size(), 6);
+    assert_eq!(rebuilt.content_hash(), collection.content_hash());
+    let values = (0..6)
+        .map(|idx| rebuilt.get_int(&mut cache, idx).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        vec![Some(1), None, Some(3), Some(40), None, Some(6)]
+    );
+
+    let empty = tmp();
+    let err = Collection::rebuild_manifest(&empty).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
@@ -0,0 +1,190 @@
+#![cfg(feature = "memmap2")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use eadb::cache::{CacheStats, SharedPageCache};
+use eadb::collection::Collection;
+use eadb::page::{Compression, Page, PageData, PageMeta, PageReader, PageWriter, Type, Value};
+use eadb::Error;
+
+fn tmp() -> PathBuf {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn string(idx: usize) -> Option<String> {
+    if idx.is_multiple_of(11) {
+        None
+    } else {
+        Some(format!("value-{}-{}", idx, "x".repeat(idx % 23)))
+    }
+}
+
+fn large_page(path: PathBuf, compression: Compression) -> PageMeta {
+    let size = 200_000;
+    let strings = (0..size).map(string).collect::<Vec<_>>();
+    let data = PageData::from_strings(
+        &strings
+            .iter()
+            .map(|value| value.as_deref())
+            .collect::<Vec<_>>(),
+    )
+    .unwrap();
+    let meta = PageMeta::new(Type::String, &path, 0, size);
+    PageWriter::write_fixed_offsets(&Page::new(&meta, data).unwrap(), compression).unwrap();
+    meta
+}
+
+#[test]
+fn random_string_access_over_a_mapped_page() {
+    let dir = tmp();
+    let meta = large_page(dir.join("page"), Compression::Uncompressed);
+    let page = PageReader::map(&meta).unwrap();
+    assert_eq!(page.len(), meta.size);
+
+    let mut idx = 7usize;
+    for _ in 0..10_000 {
+        idx = (idx * 48_271 + 11) % meta.size;
+        assert_eq!(page.get_string(idx).unwrap(), string(idx));
+    }
+    // Neither the offsets nor the payload are copied out of the map.
+    assert_eq!(page.resident_len(), meta.size.div_ceil(8));
+    assert!(matches!(
+        page.get_str(meta.size),
+        Err(Error::OutOfBounds { .. })
+    ));
+}
+
+#[test]
+fn compressed_mapped_page_decompresses_only_its_payload() {
+    let dir = tmp();
+    let meta = large_page(dir.join("page"), Compression::Snappy);
+    let page = PageReader::map(&meta).unwrap();
+    let payload_len = (0..meta.size)
+        .filter_map(string)
+        .map(|value| value.len())
+        .sum::<usize>();
+    assert_eq!(page.resident_len(), meta.size.div_ceil(8) + payload_len);
+    assert_eq!(page.get_string(199_999).unwrap(), string(199_999));
+    assert_eq!(page.get_str(11).unwrap(), None);
+}
+
+#[test]
+fn varint_offsets_cannot_be_mapped() {
+    let dir = tmp();
+    let meta = PageMeta::new(Type::String, &dir.join("page"), 0, 2);
+    let page = Page::new(
+        &meta,
+        PageData::from_strings(&[Some("a"), Some("b")]).unwrap(),
+    )
+    .unwrap();
+    PageWriter::write(&page, Compression::Uncompressed).unwrap();
+    assert!(PageReader::map(&meta).is_err());
+
+    PageWriter::write_fixed_offsets(&page, Compression::Uncompressed).unwrap();
+    let mapped = PageReader::map(&meta).unwrap();
+    assert_eq!(mapped.get_str(1).unwrap(), Some("b"));
+    assert!(matches!(mapped.get_int(1), Err(Error::TypeMismatch { .. })));
+    assert_eq!(
+        PageReader::read(&meta).unwrap().get_string(0).unwrap(),
+        Some("a".to_string())
+    );
+}
+
+/// A string collection of `pages` pages of 1000 entries, the first
+/// `uncompressed` of them written uncompressed and the rest with snappy.
+fn strings(dir: &Path, pages: usize, uncompressed: usize) -> Collection {
+    let mut collection = Collection::empty(Type::String);
+    collection.set_fixed_offsets(true);
+    for page_idx in 0..pages {
+        collection.set_compression(if page_idx < uncompressed {
+            Compression::Uncompressed
+        } else {
+            Compression::Snappy
+        });
+        let values = (page_idx * 1000..(page_idx + 1) * 1000)
+            .map(string)
+            .collect::<Vec<_>>();
+        let values = values
+            .iter()
+            .map(|value| value.as_deref())
+            .collect::<Vec<_>>();
+        collection
+            .append_page(dir, PageData::from_strings(&values).unwrap())
+            .unwrap();
+    }
+    collection
+}
+
+#[test]
+fn mapped_collection_reads_uncompressed_pages_without_the_cache() {
+    let dir = tmp();
+    let collection = strings(&dir, 8, 8).into_mapped().unwrap();
+    assert_eq!(collection.mapped_pages(), 8);
+    let cache = SharedPageCache::new();
+
+    thread::scope(|scope| {
+        for thread_idx in 0..4 {
+            let (collection, cache) = (&collection, &cache);
+            scope.spawn(move || {
+                for idx in thread_idx * 2000..(thread_idx + 1) * 2000 {
+                    assert_eq!(collection.get_string(cache, idx).unwrap(), string(idx));
+                }
+            });
+        }
+    });
+    // No lookup reached the cache, so none took its lock.
+    assert_eq!(cache.stats(), CacheStats::default());
+    assert!(matches!(
+        collection.get_string(&cache, 8000),
+        Err(Error::OutOfBounds { .. })
+    ));
+}
+
+#[test]
+fn mapped_collection_caches_compressed_pages() {
+    let dir = tmp();
+    let collection = strings(&dir, 4, 2).into_mapped().unwrap();
+    assert_eq!(collection.mapped_pages(), 2);
+    let cache = SharedPageCache::new();
+
+    for idx in 0..4000 {
+        let expected = string(idx).map_or(Value::Null, Value::String);
+        assert_eq!(collection.get_value(&cache, idx).unwrap(), expected);
+    }
+    let stats = cache.stats();
+    assert_eq!(stats.misses, 2);
+    assert_eq!(stats.hits, 1998);
+}
+
+#[test]
+fn mapped_collection_reads_fixed_width_pages() {
+    let dir = tmp();
+    let mut collection = Collection::empty(Type::Int);
+    collection.set_compression(Compression::Uncompressed);
+    collection
+        .append_page(&dir, PageData::from_ints(&[Some(4), None]).unwrap())
+        .unwrap();
+    collection
+        .append_page(
+            &dir,
+            PageData::from_ints_bitpacked(&[Some(7), Some(9)]).unwrap(),
+        )
+        .unwrap();
+    let collection = collection.into_mapped().unwrap();
+    // Bit-packed pages are read through the cache.
+    assert_eq!(collection.mapped_pages(), 1);
+
+    let cache = SharedPageCache::new();
+    let values = (0..4)
+        .map(|idx| collection.get_int(&cache, idx).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![Some(4), None, Some(7), Some(9)]);
+    assert!(matches!(
+        collection.get_float(&cache, 0),
+        Err(Error::TypeMismatch { .. })
+    ));
+}
@@ -0,0 +1,611 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use eadb::builder::StringPageBuilder;
+use eadb::cache::PageCache;
+use eadb::collection::Collection;
+use eadb::page::{
+    Compression, Encoding, Page, PageData, PageMeta, PageReader, PageWriter, Type, FORMAT_VERSION,
+    MAGIC, STRING_BOUND_LEN,
+};
+use eadb::Error;
+
+fn tmp() -> PathBuf {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn null_count_survives_write() {
+    let dir = tmp();
+    let meta = PageMeta::new(Type::Int, &dir.join("page"), 0, 5);
+    let page = Page::new(
+        &meta,
+        PageData::from_ints(&[None, Some(1), None, None, Some(4)]).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(page.meta().null_count(), 3);
+
+    PageWriter::write(&page, Compression::Snappy).unwrap();
+    assert_eq!(PageReader::read(&meta).unwrap().meta().null_count(), 3);
+}
+
+#[test]
+fn write_to_uses_the_given_compression() {
+    let meta = PageMeta::new(Type::String, &PathBuf::from("in-memory"), 0, 3);
+    let page = Page::new(
+        &meta,
+        PageData::from_strings(&[Some("a"), None, Some("b")]).unwrap(),
+    )
+    .unwrap();
+    let mut sizes = vec![];
+    for compression in &[
+        Compression::Uncompressed,
+        Compression::Snappy,
+        Compression::Lz4,
+    ] {
+        let mut bytes = vec![];
+        PageWriter::write_to(&page, &mut bytes, *compression).unwrap();
+        let read = PageReader::from_bytes(&bytes, &meta).unwrap();
+        assert_eq!(read.get_string(0).unwrap(), Some("a".to_string()));
+        assert!(read.is_null(1));
+        sizes.push(bytes.len());
+    }
+    // Framed compressors add headers that a three byte payload cannot repay.
+    assert!(sizes[0] < sizes[1]);
+    assert!(sizes[0] < sizes[2]);
+}
+
+#[test]
+fn recommend_encoding_picks_delta_for_sorted_samples() {
+    let ids = (1_000_000..1_001_000).map(Some).collect::<Vec<_>>();
+    let encoding = PageData::recommend_encoding(&ids);
+    assert_eq!(
+        encoding,
+        PageData::from_ints_delta(&ids).unwrap().encoding()
+    );
+    assert!(matches!(encoding, Encoding::Delta { .. }));
+
+    let mut falling = (0..500)
+        .map(|value| Some(value * -1_000))
+        .collect::<Vec<_>>();
+    falling[7] = None;
+    assert!(matches!(
+        PageData::recommend_encoding(&falling),
+        Encoding::Delta { .. }
+    ));
+
+    let small = (0..1000)
+        .map(|value| Some(value * 7 % 16))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        PageData::recommend_encoding(&small),
+        Encoding::BitPacked { base: 0, width: 4 }
+    );
+    let repeated = (0..1000)
+        .map(|value| Some(value / 250 * 3 % 5))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        PageData::recommend_encoding(&repeated),
+        Encoding::Rle { runs: 4 }
+    );
+    let scattered = (0..1000)
+        .map(|value: i64| Some(value.wrapping_mul(0x1e37_79b9_7f4a_7c15)))
+        .collect::<Vec<_>>();
+    assert_eq!(PageData::recommend_encoding(&scattered), Encoding::Plain);
+
+    let data = PageData::from_ints(&falling)
+        .unwrap()
+        .with_recommended_encoding()
+        .unwrap();
+    assert!(matches!(data.encoding(), Encoding::Delta { .. }));
+    assert_eq!(data.get_int(7).unwrap(), None);
+    assert_eq!(data.get_int(499).unwrap(), Some(-499_000));
+}
+
+#[test]
+fn recommend_encoding_picks_a_dictionary_for_few_spread_values() {
+    let codes = [i64::MIN, -7, 0, 1 << 40, i64::MAX];
+    let mut sample = (0..1000)
+        .map(|value| Some(codes[value * 7919 % codes.len()]))
+        .collect::<Vec<_>>();
+    sample[3] = None;
+    assert_eq!(
+        PageData::recommend_encoding(&sample),
+        Encoding::Dictionary {
+            values: 5,
+            width: 3
+        }
+    );
+
+    let dir = tmp();
+    let meta = PageMeta::new(Type::Int, &dir.join("page"), 0, sample.len());
+    let data = PageData::from_ints(&sample)
+        .unwrap()
+        .with_recommended_encoding()
+        .unwrap();
+    assert_eq!(data.encoding(), PageData::recommend_encoding(&sample));
+    let page = Page::new(&meta, data).unwrap();
+    PageWriter::write(&page, Compression::Uncompressed).unwrap();
+    // The dictionary and three bit indexes, not eight bytes an entry.
+    assert!(fs::metadata(&meta.path).unwrap().len() < 1000);
+
+    let read = PageReader::read(&meta).unwrap();
+    let mut lazy = PageReader::read_lazy(&meta).unwrap();
+    for (idx, expected) in sample.iter().enumerate() {
+        assert_eq!(read.get_int(idx).unwrap(), *expected);
+        assert_eq!(lazy.get_int(idx).unwrap(), *expected);
+    }
+}
+
+#[test]
+fn bool_pages_without_nulls_store_only_value_bits() {
+    let dir = tmp();
+    let required = (0..4000).map(|idx| Some(idx % 3 == 0)).collect::<Vec<_>>();
+    let nullable = (0..4000)
+        .map(|idx| {
+            if idx % 2 == 0 {
+                Some(idx % 3 == 0)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut sizes = vec![];
+    for (name, entries) in &[("required", &required), ("nullable", &nullable)] {
+        let meta = PageMeta::new(Type::Bool, &dir.join(name), 0, entries.len());
+        let page = Page::new(&meta, PageData::from_bools(entries).unwrap()).unwrap();
+        PageWriter::write(&page, Compression::Uncompressed).unwrap();
+        sizes.push(fs::metadata(&meta.path).unwrap().len());
+
+        let read = PageReader::read(&meta).unwrap();
+        for (idx, expected) in entries.iter().enumerate() {
+            assert_eq!(read.get_bool(idx).unwrap(), *expected);
+        }
+    }
+    // 500 bytes of value bits, against those and a 500 byte null bitmap.
+    assert!(sizes[0] < 600, "{:?}", sizes);
+    assert!(sizes[1] >= sizes[0] + 500, "{:?}", sizes);
+}
+
+/// A writer that fails once more than `budget` bytes are written to it.
+struct FailingSink {
+    budget: usize,
+}
+
+impl Write for FailingSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.budget {
+            return Err(io::Error::other("sink full"));
+        }
+        self.budget -= buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_surfaces_compressor_finish_error() {
+    let data = PageData::from_ints(&(0..10).map(Some).collect::<Vec<_>>()).unwrap();
+    let page = Page::new(
+        &PageMeta::new(Type::Int, &PathBuf::from("sink"), 0, 10),
+        data,
+    )
+    .unwrap();
+    // Room for every section before the values, but not for the values
+    // the encoder only writes when it is finished.
+    let mut plain = vec![];
+    PageWriter::write_to(&page, &mut plain, Compression::Uncompressed).unwrap();
+    let budget = plain.len() - 10 * 8 - 4;
+    let err = PageWriter::write_to(&page, FailingSink { budget }, Compression::Snappy).unwrap_err();
+    assert!(err.to_string().contains("sink full"), "{}", err);
+    PageWriter::write_to(&page, FailingSink { budget: 10_000 }, Compression::Snappy).unwrap();
+}
+
+#[test]
+fn truncated_page_is_corrupt() {
+    let dir = tmp();
+    let strings = (0..300)
+        .map(|idx| format!("value-{}", idx * 7919 % 1000))
+        .collect::<Vec<_>>();
+    let entries = strings
+        .iter()
+        .map(|value| Some(value.as_str()))
+        .collect::<Vec<_>>();
+    let meta = PageMeta::new(Type::String, &dir.join("page"), 0, entries.len());
+    let page = Page::new(&meta, PageData::from_strings(&entries).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Uncompressed).unwrap();
+
+    let bytes = fs::read(&meta.path).unwrap();
+    // Cuts through the header, the stats, the offsets and the values.
+    for &cut in &[10, 60, 120, bytes.len() - 5] {
+        fs::write(&meta.path, &bytes[..cut]).unwrap();
+        let err = PageReader::read(&meta).err().unwrap();
+        assert!(matches!(err, Error::Corrupt(_)), "cut at {}: {}", cut, err);
+    }
+}
+
+#[test]
+fn every_codec_round_trips() {
+    let dir = tmp();
+    let strings = (0..2000)
+        .map(|idx| format!("row {} of many", idx % 37))
+        .collect::<Vec<_>>();
+    let entries = strings
+        .iter()
+        .map(|value| Some(value.as_str()))
+        .collect::<Vec<_>>();
+    let ints = (0..500).map(|value| Some(value * 3)).collect::<Vec<_>>();
+    let codecs = [
+        Compression::Uncompressed,
+        Compression::Snappy,
+        Compression::Zstd { level: 3 },
+        Compression::Lz4,
+    ];
+    for (idx, &compression) in codecs.iter().enumerate() {
+        let meta = PageMeta::new(Type::String, &dir.join(format!("s{}", idx)), 0, 2000);
+        let page = Page::new(&meta, PageData::from_strings(&entries).unwrap()).unwrap();
+        PageWriter::write(&page, compression).unwrap();
+        let read = PageReader::read(&meta).unwrap();
+        assert_eq!(read.get_string(1999).unwrap(), Some(strings[1999].clone()));
+        let mut lazy = PageReader::read_lazy(&meta).unwrap();
+        assert_eq!(lazy.get_string(5).unwrap(), Some(strings[5].clone()));
+
+        let meta = PageMeta::new(Type::Int, &dir.join(format!("i{}", idx)), 0, 500);
+        let page = Page::new(&meta, PageData::from_ints_bitpacked(&ints).unwrap()).unwrap();
+        PageWriter::write(&page, compression).unwrap();
+        assert_eq!(
+            PageReader::read(&meta).unwrap().get_int(499).unwrap(),
+            Some(1497)
+        );
+    }
+}
+
+#[test]
+fn read_rejects_a_meta_of_another_type() {
+    let dir = tmp();
+    let meta = PageMeta::new(Type::Int, &dir.join("page"), 0, 2);
+    let page = Page::new(&meta, PageData::from_ints(&[Some(1), Some(2)]).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Snappy).unwrap();
+
+    let wrong = PageMeta::new(Type::String, &meta.path, 0, 2);
+    match PageReader::read(&wrong) {
+        Err(Error::TypeMismatch {
+            expected: Type::String,
+            found: Type::Int,
+        }) => {}
+        other => panic!("expected a type mismatch, got {:?}", other.err()),
+    }
+    assert!(PageReader::read_lazy(&wrong).is_err());
+}
+
+#[test]
+fn invalid_utf8_fails_at_load() {
+    let dir = tmp();
+    let meta = PageMeta::new(Type::String, &dir.join("page"), 0, 1);
+    let page = Page::new(&meta, PageData::from_strings(&[Some("\u{e9}")]).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Uncompressed).unwrap();
+
+    // Break the last value byte and patch the checksum footer to match.
+    let mut bytes = fs::read(&meta.path).unwrap();
+    let len = bytes.len();
+    bytes[len - 5] = 0xff;
+    let checksum = crc32fast::hash(&bytes[..len - 4]);
+    bytes[len - 4..].copy_from_slice(&checksum.to_le_bytes());
+    fs::write(&meta.path, &bytes).unwrap();
+
+    let err = PageReader::read(&meta).err().unwrap();
+    assert!(matches!(err, Error::Utf8(_)), "{}", err);
+}
+
+#[test]
+fn append_page_checks_the_type_and_places_the_page() {
+    let dir = tmp();
+    let mut collection = Collection::empty(Type::Int);
+    collection
+        .append_page(&dir, PageData::from_ints(&[Some(1), None]).unwrap())
+        .unwrap();
+    let meta = collection
+        .append_page(&dir, PageData::from_ints(&[Some(3)]).unwrap())
+        .unwrap();
+    assert_eq!(meta.offset(), 2);
+    assert_eq!(meta.size, 1);
+    assert_eq!(collection.size(), 3);
+
+    let err = collection
+        .append_page(&dir, PageData::from_floats(&[Some(1.0)]).unwrap())
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(collection.size(), 3);
+
+    let mut cache = PageCache::new();
+    let values = (0..3)
+        .map(|idx| collection.get_int(&mut cache, idx).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![Some(1), None, Some(3)]);
+}
+
+#[test]
+fn content_hash_changes_with_any_page() {
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let mut collection =
+        Collection::from_ints(&dir, vec![Some(1), Some(2), None, Some(4)], 2).unwrap();
+    let before = collection.content_hash();
+    collection.write_manifest(&dir).unwrap();
+    assert_eq!(Collection::open(&dir).unwrap().content_hash(), before);
+
+    collection
+        .overwrite_int(&dir, &mut cache, 3, Some(5))
+        .unwrap();
+    let after = collection.content_hash();
+    assert_ne!(after, before);
+
+    collection
+        .overwrite_int(&dir, &mut cache, 3, Some(4))
+        .unwrap();
+    assert_eq!(collection.content_hash(), before);
+}
+
+#[test]
+fn scan_int_pages_hands_out_values_and_validity() {
+    let dir = tmp();
+    let entries = (0..1000)
+        .map(|value| if value % 7 == 0 { None } else { Some(value) })
+        .collect::<Vec<_>>();
+    let collection = Collection::from_ints(&dir, entries.clone(), 300).unwrap();
+
+    let (mut sum, mut rows, mut pages) = (0, 0, 0);
+    collection
+        .scan_int_pages(&mut PageCache::new(), |values, validity| {
+            assert_eq!(values.len(), validity.len());
+            for (value, valid) in values.iter().zip(validity.iter()) {
+                if valid {
+                    sum += value;
+                } else {
+                    assert_eq!(*value, 0);
+                }
+            }
+            rows += values.len();
+            pages += 1;
+        })
+        .unwrap();
+    assert_eq!(sum, entries.iter().flatten().sum::<i64>());
+    assert_eq!((rows, pages), (1000, 4));
+}
+
+#[test]
+fn string_page_builder_matches_an_in_memory_page() {
+    let dir = tmp();
+    let strings = (0..20_000)
+        .map(|idx| format!("row {} {}", idx, "x".repeat(idx % 13)))
+        .collect::<Vec<_>>();
+    let entries = strings
+        .iter()
+        .enumerate()
+        .map(|(idx, value)| {
+            if idx % 11 == 0 {
+                None
+            } else {
+                Some(value.as_str())
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut builder = StringPageBuilder::new(&dir.join("built"), 0).unwrap();
+    for entry in &entries {
+        builder.push(*entry).unwrap();
+    }
+    assert_eq!(builder.len(), entries.len());
+    let meta = builder.finish().unwrap();
+    // Only the page is left once the spill files are stitched together.
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let expected = Page::new(
+        &PageMeta::new(Type::String, &dir.join("expected"), 0, entries.len()),
+        PageData::from_strings(&entries).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(meta.checksum, expected.meta().checksum);
+    assert_eq!(meta.null_count(), expected.meta().null_count());
+
+    let page = PageReader::read(&meta).unwrap();
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(page.get_str(idx).unwrap(), *entry);
+    }
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn to_arrow_concatenates_pages() {
+    use arrow_array::{Array, Int64Array, StringArray};
+
+    let dir = tmp();
+    let mut cache = PageCache::new();
+    let ints = Collection::from_ints(&dir, vec![Some(1), None, Some(3), Some(4), None], 2).unwrap();
+    let array = ints.to_arrow(&mut cache).unwrap();
+    let array = array.as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(
+        array.iter().collect::<Vec<_>>(),
+        vec![Some(1), None, Some(3), Some(4), None]
+    );
+    assert_eq!(array.null_count(), 2);
+
+    let mut strings = Collection::empty(Type::String);
+    for page in &[vec![Some("a"), None], vec![Some("bc"), Some("")]] {
+        strings
+            .append_page(&dir, PageData::from_strings(page).unwrap())
+            .unwrap();
+    }
+    let array = strings.to_arrow(&mut cache).unwrap();
+    let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(
+        array.iter().collect::<Vec<_>>(),
+        vec![Some("a"), None, Some("bc"), Some("")]
+    );
+}
+
+#[test]
+fn string_bound_is_cut_to_a_prefix_that_still_bounds() {
+    let long_max = format!("zz{}", "y".repeat(200));
+    let long_min = format!("b{}", "\u{e9}".repeat(100));
+    let entries = vec![Some(long_max.as_str()), None, Some(long_min.as_str())];
+    let data = PageData::from_strings(&entries).unwrap();
+
+    let bound = data.string_bound(STRING_BOUND_LEN).unwrap();
+    assert!(bound.min.len() <= STRING_BOUND_LEN && bound.max.len() <= STRING_BOUND_LEN);
+    // The min is a prefix, and the max rounds its last character up.
+    assert!(long_min.starts_with(&bound.min));
+    assert!(bound.max > long_max);
+    assert!(bound.max.starts_with("zz"));
+
+    let meta = PageMeta::new(Type::String, &PathBuf::from("in-memory"), 0, 3);
+    let page = Page::new(&meta, data).unwrap();
+    assert_eq!(page.meta().stats.string_bound, Some(bound));
+
+    // Empty strings are values, so they can be the min.
+    let data = PageData::from_strings(&[Some("b"), Some(""), None]).unwrap();
+    let bound = data.string_bound(STRING_BOUND_LEN).unwrap();
+    assert_eq!((bound.min.as_str(), bound.max.as_str()), ("", "b"));
+    assert_eq!(
+        PageData::from_strings(&[None, None])
+            .unwrap()
+            .string_bound(STRING_BOUND_LEN),
+        None
+    );
+}
+
+#[test]
+fn compressed_offsets_shrink_a_large_string_page() {
+    let dir = tmp();
+    let values = (0..10_000)
+        .map(|idx| match idx % 7 {
+            0 => None,
+            _ => Some(format!("value-{}", idx % 100)),
+        })
+        .collect::<Vec<_>>();
+    let entries = values
+        .iter()
+        .map(|value| value.as_deref())
+        .collect::<Vec<_>>();
+    let compact_meta = PageMeta::new(Type::String, &dir.join("compact"), 0, entries.len());
+    PageWriter::write(
+        &Page::new(&compact_meta, PageData::from_strings(&entries).unwrap()).unwrap(),
+        Compression::Uncompressed,
+    )
+    .unwrap();
+    let fixed_meta = PageMeta::new(Type::String, &dir.join("fixed"), 0, entries.len());
+    PageWriter::write_fixed_offsets(
+        &Page::new(&fixed_meta, PageData::from_strings(&entries).unwrap()).unwrap(),
+        Compression::Uncompressed,
+    )
+    .unwrap();
+
+    let compact_len = fs::metadata(&compact_meta.path).unwrap().len();
+    let fixed_len = fs::metadata(&fixed_meta.path).unwrap().len();
+    // Absolute offsets alone take 8 bytes per entry.
+    assert!(compact_len + 8 * entries.len() as u64 / 2 < fixed_len);
+
+    let compact = PageReader::read(&compact_meta).unwrap();
+    let fixed = PageReader::read(&fixed_meta).unwrap();
+    for (idx, value) in values.iter().enumerate() {
+        assert_eq!(compact.get_str(idx).unwrap(), value.as_deref());
+        assert_eq!(fixed.get_str(idx).unwrap(), value.as_deref());
+    }
+}
+
+#[test]
+fn flipped_bit_fails_the_checksum() {
+    let dir = tmp();
+    let meta = PageMeta::new(Type::Int, &dir.join("page"), 0, 3);
+    let page = Page::new(
+        &meta,
+        PageData::from_ints(&[Some(1), None, Some(3)]).unwrap(),
+    )
+    .unwrap();
+    PageWriter::write(&page, Compression::Uncompressed).unwrap();
+    let bytes = fs::read(&meta.path).unwrap();
+
+    // One bit anywhere past the magic number and version, footer included.
+    for at in MAGIC.len() + 1..bytes.len() {
+        let mut flipped = bytes.clone();
+        flipped[at] ^= 0x10;
+        fs::write(&meta.path, &flipped).unwrap();
+        match PageReader::read(&meta) {
+            Err(Error::Corrupt(message)) => assert!(message.contains("checksum"), "{}", message),
+            Err(err) => panic!("byte {}: {}", at, err),
+            Ok(_) => panic!("byte {}: flipped page was accepted", at),
+        }
+    }
+}
+
+#[test]
+fn foreign_magic_and_future_versions_are_explained() {
+    let dir = tmp();
+    let meta = PageMeta::new(Type::Int, &dir.join("page"), 0, 3);
+    let page = Page::new(
+        &meta,
+        PageData::from_ints(&[Some(1), None, Some(3)]).unwrap(),
+    )
+    .unwrap();
+    PageWriter::write(&page, Compression::Uncompressed).unwrap();
+    let bytes = fs::read(&meta.path).unwrap();
+
+    let mut foreign = bytes.clone();
+    foreign[..MAGIC.len()].copy_from_slice(b"PAR1");
+    fs::write(&meta.path, &foreign).unwrap();
+    let err = PageReader::read(&meta).err().unwrap().to_string();
+    assert!(err.contains("magic number"), "{}", err);
+
+    let mut future = bytes;
+    future[MAGIC.len()] = FORMAT_VERSION + 1;
+    let len = future.len();
+    let checksum = crc32fast::hash(&future[..len - 4]);
+    future[len - 4..].copy_from_slice(&checksum.to_le_bytes());
+    fs::write(&meta.path, &future).unwrap();
+    let err = PageReader::read(&meta).err().unwrap().to_string();
+    assert!(
+        err.contains(&format!(
+            "Unsupported page format version {}",
+            FORMAT_VERSION + 1
+        )),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn zstd_level_changes_the_file_size() {
+    let dir = tmp();
+    let values = (0..20_000)
+        .map(|idx: u64| Some(format!("{:x}", idx.wrapping_mul(0x9e37_79b9) % 5000)))
+        .collect::<Vec<_>>();
+    let entries = values
+        .iter()
+        .map(|value| value.as_deref())
+        .collect::<Vec<_>>();
+    let written = |level, name| {
+        let meta = PageMeta::new(Type::String, &dir.join(name), 0, entries.len());
+        let page = Page::new(&meta, PageData::from_strings(&entries).unwrap()).unwrap();
+        PageWriter::write(&page, Compression::Zstd { level }).unwrap();
+        meta
+    };
+    let fast = written(1, "fast");
+    let small = written(19, "small");
+
+    let fast_len = fs::metadata(&fast.path).unwrap().len();
+    let small_len = fs::metadata(&small.path).unwrap().len();
+    assert!(small_len < fast_len, "{} >= {}", small_len, fast_len);
+    let fast = PageReader::read(&fast).unwrap();
+    let small = PageReader::read(&small).unwrap();
+    for (idx, value) in values.iter().enumerate().step_by(97) {
+        assert_eq!(fast.get_str(idx).unwrap(), value.as_deref());
+        assert_eq!(small.get_str(idx).unwrap(), value.as_deref());
+    }
+}
@@ -0,0 +1,37 @@
+use std::fs;
+use std::path::PathBuf;
+
+use eadb::cache::PageCache;
+use eadb::collection::Collection;
+use eadb::table::Table;
+use eadb::Error;
+
+fn tmp() -> PathBuf {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn group_sum_int_does_not_overflow() {
+    let dir = tmp();
+    let keys = vec![Some(1), Some(2), Some(1), None, Some(2)];
+    let values = vec![Some(i64::MAX), Some(3), Some(1), Some(5), Some(4)];
+    let mut table = Table::new();
+    table.add_column("key", Collection::from_ints(&dir, keys, 2).unwrap());
+    table.add_column("value", Collection::from_ints(&dir, values, 2).unwrap());
+
+    let sums = table
+        .group_sum_int("key", "value", &mut PageCache::new())
+        .unwrap();
+    assert_eq!(
+        sums.into_iter().collect::<Vec<_>>(),
+        vec![(1, i128::from(i64::MAX) + 1), (2, 7)]
+    );
+
+    let err = table
+        .group_sum_int("key", "missing", &mut PageCache::new())
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::UnknownColumn(name) if name == "missing"));
+}
@@ -0,0 +1,330 @@
+use std::fs;
+use std::path::PathBuf;
+
+use eadb::cache::{PageCache, SharedPageCache};
+use eadb::collection::Collection;
+use eadb::page::{
+    Bound, Compression, Decimal, Page, PageData, PageMeta, PageReader, PageWriter, Type,
+};
+use eadb::Error;
+
+fn tmp() -> PathBuf {
+    let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn int32_round_trip() {
+    let dir = tmp();
+    let values = vec![Some(-5), None, Some(i32::MAX), Some(i32::MIN), Some(0)];
+    let meta = PageMeta::new(Type::Int32, &dir.join("page"), 0, values.len());
+    let page = Page::new(&meta, PageData::from_i32(&values).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Snappy).unwrap();
+    let read = PageReader::read(&meta).unwrap();
+    assert_eq!(
+        read.meta().stats.int_bound,
+        Some(Bound {
+            min: i32::MIN as i64,
+            max: i32::MAX as i64
+        })
+    );
+    assert_eq!(read.get_i32(1).unwrap(), None);
+    assert_eq!(read.get_i32(3).unwrap(), Some(i32::MIN));
+
+    let mut collection = Collection::empty(Type::Int32);
+    collection
+        .append_page(&dir, PageData::from_i32(&values).unwrap())
+        .unwrap();
+    collection
+        .append_page(&dir, PageData::from_i32(&[None, Some(-7)]).unwrap())
+        .unwrap();
+    let mut expected = values;
+    expected.extend(vec![None, Some(-7)]);
+    let mut cache = PageCache::new();
+    assert_eq!(
+        collection
+            .i32_iter(&mut cache)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        expected
+    );
+
+    let shared = SharedPageCache::new();
+    for (idx, value) in expected.iter().enumerate() {
+        assert_eq!(collection.get_i32_shared(&shared, idx).unwrap(), *value);
+        assert_eq!(collection.get_i32(&mut cache, idx).unwrap(), *value);
+    }
+    assert!(matches!(
+        collection.get_i32(&mut cache, expected.len()),
+        Err(Error::OutOfBounds { .. })
+    ));
+    assert!(matches!(
+        collection.get_int(&mut cache, 0),
+        Err(Error::TypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn uint_round_trip_above_i64_max() {
+    let dir = tmp();
+    let big = i64::MAX as u64 + 10;
+    let values = vec![Some(big), None, Some(u64::MAX), Some(3)];
+    let meta = PageMeta::new(Type::UInt, &dir.join("page"), 0, values.len());
+    let page = Page::new(&meta, PageData::from_u64(&values).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Snappy).unwrap();
+    let read = PageReader::read(&meta).unwrap();
+    assert_eq!(
+        read.meta().stats.uint_bound,
+        Some(Bound {
+            min: 3,
+            max: u64::MAX
+        })
+    );
+    assert_eq!(read.get_u64(0).unwrap(), Some(big));
+
+    let mut collection = Collection::empty(Type::UInt);
+    collection
+        .append_page(&dir, PageData::from_u64(&values).unwrap())
+        .unwrap();
+    let mut cache = PageCache::new();
+    assert_eq!(
+        collection
+            .uint_iter(&mut cache)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        values
+    );
+    assert!(collection
+        .stats_json()
+        .contains(&format!("\"max\":{}", u64::MAX)));
+
+    let shared = SharedPageCache::new();
+    for (idx, value) in values.iter().enumerate() {
+        assert_eq!(collection.get_u64_shared(&shared, idx).unwrap(), *value);
+        assert_eq!(collection.get_u64(&mut cache, idx).unwrap(), *value);
+    }
+    assert!(matches!(
+        collection.get_u64(&mut cache, values.len()),
+        Err(Error::OutOfBounds { .. })
+    ));
+}
+
+#[test]
+fn float32_round_trip_keeps_bit_patterns() {
+    let dir = tmp();
+    let nan = f32::from_bits(0x7fc0_1234);
+    let values = vec![
+        Some(nan),
+        Some(f32::INFINITY),
+        None,
+        Some(f32::NEG_INFINITY),
+        Some(-0.0),
+        Some(1.5),
+    ];
+    let bits = |values: &[Option<f32>]| {
+        values
+            .iter()
+            .map(|value| value.map(f32::to_bits))
+            .collect::<Vec<_>>()
+    };
+    let meta = PageMeta::new(Type::Float32, &dir.join("page"), 0, values.len());
+    let page = Page::new(&meta, PageData::from_f32(&values).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Lz4).unwrap();
+    let read = PageReader::read(&meta).unwrap();
+    let read_values = (0..values.len())
+        .map(|idx| read.get_f32(idx).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(bits(&read_values), bits(&values));
+
+    let mut collection = Collection::empty(Type::Float32);
+    collection
+        .append_page(&dir, PageData::from_f32(&values).unwrap())
+        .unwrap();
+    let mut cache = PageCache::new();
+    let iterated = collection
+        .float32_iter(&mut cache)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(bits(&iterated), bits(&values));
+
+    let shared = SharedPageCache::new();
+    let shared_values = (0..values.len())
+        .map(|idx| collection.get_f32_shared(&shared, idx).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(bits(&shared_values), bits(&values));
+    let checked_values = (0..values.len())
+        .map(|idx| collection.get_f32(&mut cache, idx).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(bits(&checked_values), bits(&values));
+    assert!(matches!(
+        collection.get_float(&mut cache, 0),
+        Err(Error::TypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn timestamp_round_trip_before_the_epoch() {
+    let dir = tmp();
+    let values = vec![Some(-86_400_000), None, Some(1_700_000_000_123), Some(-1)];
+    let meta = PageMeta::new(Type::Timestamp, &dir.join("page"), 0, values.len());
+    let page = Page::new(&meta, PageData::from_timestamps(&values).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Snappy).unwrap();
+    let read = PageReader::read(&meta).unwrap();
+    assert_eq!(
+        read.meta().stats.int_bound,
+        Some(Bound {
+            min: -86_400_000,
+            max: 1_700_000_000_123
+        })
+    );
+    assert_eq!(read.get_timestamp(1).unwrap(), None);
+    assert_eq!(read.get_timestamp(3).unwrap(), Some(-1));
+
+    let mut collection = Collection::empty(Type::Timestamp);
+    collection
+        .append_page(&dir, PageData::from_timestamps(&values).unwrap())
+        .unwrap();
+    let mut cache = PageCache::new();
+    assert_eq!(
+        collection
+            .timestamp_iter(&mut cache)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        values
+    );
+
+    let shared = SharedPageCache::new();
+    for (idx, value) in values.iter().enumerate() {
+        assert_eq!(
+            collection.get_timestamp_shared(&shared, idx).unwrap(),
+            *value
+        );
+        assert_eq!(collection.get_timestamp(&mut cache, idx).unwrap(), *value);
+    }
+    assert!(matches!(
+        collection.get_int(&mut cache, 0),
+        Err(Error::TypeMismatch { .. })
+    ));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn datetime_round_trip_before_the_epoch() {
+    use chrono::TimeZone;
+
+    let time = chrono::Utc.timestamp_millis_opt(-5_000).single().unwrap();
+    let data = PageData::from_datetimes(&[Some(time), None]).unwrap();
+    assert_eq!(data.get_datetime(0).unwrap(), Some(time));
+    assert_eq!(data.get_timestamp(0).unwrap(), Some(-5_000));
+    assert_eq!(data.get_datetime(1).unwrap(), None);
+}
+
+#[test]
+fn binary_round_trip_with_invalid_utf8() {
+    let dir = tmp();
+    let blobs: Vec<Option<&[u8]>> = vec![
+        Some(&[0xff, 0xfe, 0x00]),
+        Some(&[]),
+        None,
+        Some(&[0xc3]),
+        Some(b"ok"),
+    ];
+    let meta = PageMeta::new(Type::Binary, &dir.join("page"), 0, blobs.len());
+    let page = Page::new(&meta, PageData::from_binaries(&blobs).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Snappy).unwrap();
+    let read = PageReader::read(&meta).unwrap();
+    for (idx, blob) in blobs.iter().enumerate() {
+        assert_eq!(read.get_binary(idx).unwrap(), *blob);
+    }
+    let mut lazy = PageReader::read_lazy(&meta).unwrap();
+    assert_eq!(lazy.get_binary(0).unwrap(), Some(vec![0xff, 0xfe, 0x00]));
+    assert_eq!(lazy.get_binary(2).unwrap(), None);
+
+    let mut collection = Collection::empty(Type::Binary);
+    collection
+        .append_page(&dir, PageData::from_binaries(&blobs).unwrap())
+        .unwrap();
+    let mut cache = PageCache::new();
+    let shared = SharedPageCache::new();
+    for (idx, blob) in blobs.iter().enumerate() {
+        assert_eq!(
+            collection.get_binary(&mut cache, idx).unwrap().as_deref(),
+            *blob
+        );
+        assert_eq!(
+            collection
+                .get_binary_shared(&shared, idx)
+                .unwrap()
+                .as_deref(),
+            *blob
+        );
+        assert_eq!(
+            collection.get_binary(&mut cache, idx).unwrap().as_deref(),
+            *blob
+        );
+    }
+    assert!(matches!(
+        collection.get_string(&mut cache, 0),
+        Err(Error::TypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn decimal_round_trip_with_scale() {
+    let dir = tmp();
+    let values = vec![
+        Some(123_456),
+        None,
+        Some(-5),
+        Some(-123_456),
+        Some(i128::MAX),
+    ];
+    let mut collection = Collection::empty(Type::Decimal);
+    collection
+        .append_page(&dir, PageData::from_decimals(&values, 2).unwrap())
+        .unwrap();
+    collection
+        .append_page(&dir, PageData::from_decimals(&[Some(7)], 0).unwrap())
+        .unwrap();
+
+    let mut cache = PageCache::new();
+    let shared = SharedPageCache::new();
+    for (idx, value) in values.iter().enumerate() {
+        let expected = value.map(|value| Decimal { value, scale: 2 });
+        assert_eq!(collection.get_decimal(&mut cache, idx).unwrap(), expected);
+        assert_eq!(
+            collection.get_decimal_shared(&shared, idx).unwrap(),
+            expected
+        );
+        assert_eq!(collection.get_decimal(&mut cache, idx).unwrap(), expected);
+    }
+    let formatted = [0, 2, 3, 5]
+        .iter()
+        .map(|&idx| {
+            collection
+                .get_decimal(&mut cache, idx)
+                .unwrap()
+                .unwrap()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(formatted, vec!["1234.56", "-0.05", "-1234.56", "7"]);
+    assert!(matches!(
+        collection.get_decimal(&mut cache, 6),
+        Err(Error::OutOfBounds { index: 6, size: 6 })
+    ));
+
+    let meta = PageMeta::new(Type::Decimal, &dir.join("page"), 0, values.len());
+    let page = Page::new(&meta, PageData::from_decimals(&values, 2).unwrap()).unwrap();
+    PageWriter::write(&page, Compression::Snappy).unwrap();
+    let mut lazy = PageReader::read_lazy(&meta).unwrap();
+    assert_eq!(
+        lazy.get_decimal(3).unwrap(),
+        Some(Decimal {
+            value: -123_456,
+            scale: 2
+        })
+    );
+    assert_eq!(PageReader::read(&meta).unwrap().data().scale(), 2);
+}